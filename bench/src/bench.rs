@@ -0,0 +1,47 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use oridion_client::svm::SvmCluster;
+
+/// Measured values, keyed `cu <instruction>` or `size <account>`.
+pub type Results = BTreeMap<String, u64>;
+
+/// Sends one instruction per transaction and records what it cost.
+pub struct Bench {
+    pub cluster: SvmCluster,
+    pub results: Results,
+}
+
+impl Bench {
+    pub fn new(cluster: SvmCluster) -> Self {
+        Bench { cluster, results: Results::new() }
+    }
+
+    /// Compute units of `ix`. An instruction measured more than once keeps its most expensive run.
+    pub fn cu(&mut self, name: &str, payer: &Pubkey, ix: Instruction) -> Result<(), Box<dyn Error>> {
+        let meta = self.cluster.send_as(payer, &[ix]).map_err(|err| format!("{}: {}", name, err))?;
+        self.record(format!("cu {}", name), meta.compute_units_consumed);
+        Ok(())
+    }
+
+    /// Data length of the account at `address`.
+    pub fn size(&mut self, name: &str, address: &Pubkey) -> Result<(), Box<dyn Error>> {
+        let len = self.cluster.data_len(address).ok_or_else(|| format!("{}: account {} not found", name, address))?;
+        self.record(format!("size {}", name), len as u64);
+        Ok(())
+    }
+
+    /// Write locks two instructions share. Transactions holding them cannot land in parallel.
+    pub fn locks(&mut self, name: &str, first: &Instruction, second: &Instruction) {
+        let shared = first.accounts.iter()
+            .filter(|meta| meta.is_writable && second.accounts.iter().any(|other| other.is_writable && other.pubkey == meta.pubkey))
+            .count();
+        self.record(format!("locks {}", name), shared as u64);
+    }
+
+    fn record(&mut self, key: String, value: u64) {
+        let entry = self.results.entry(key).or_default();
+        *entry = (*entry).max(value);
+    }
+}
@@ -0,0 +1,138 @@
+//! Oridion bench. Runs every instruction once in LiteSVM, against a universe
+//! with a full planet index, and records its compute units and the size of
+//! every account type. Fails when a value grew past `baseline.txt`, or when
+//! one breaks a bound of `BOUNDS`.
+//!
+//! Usage:
+//! - `cargo run -p oridion-bench`              compare with the baseline
+//! - `cargo run -p oridion-bench -- --update`  rewrite the baseline
+//!
+//! The program is loaded like the LiteSVM tests (`anchor build` output, or
+//! `ORIDION_PROGRAM_SO`).
+mod bench;
+mod scenarios;
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use oridion_client::svm::SvmCluster;
+use crate::bench::{Bench, Results};
+
+/// `(key, limit)`: the value of `key` may not exceed the value of `limit`. Checked
+/// with or without a baseline.
+const BOUNDS: [(&str, &str); 2] = [
+    //The planet index is not read by deposits, a full one costs nothing extra
+    ("cu create_deposit", "cu create_deposit_three_planets"),
+    //create_deposit no longer takes the universe, bucket deposits still read its header
+    ("cu create_deposit", "cu create_bucket_deposit"),
+];
+
+fn baseline_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("baseline.txt")
+}
+
+/// `<kind> <name> <value>` per line, `#` starts a comment.
+fn parse_baseline(text: &str) -> Result<Results, Box<dyn Error>> {
+    let mut results = Results::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        let (key, value) = line.rsplit_once(' ').ok_or_else(|| format!("invalid baseline line: {}", line))?;
+        let value: u64 = value.parse().map_err(|_| format!("invalid baseline value: {}", line))?;
+        results.insert(key.to_string(), value);
+    }
+    Ok(results)
+}
+
+fn format_baseline(results: &Results) -> String {
+    let mut text = String::from("# Written by `cargo run -p oridion-bench -- --update`\n");
+    for (key, value) in results {
+        text.push_str(&format!("{} {}\n", key, value));
+    }
+    text
+}
+
+/// Regressions of `results` against `baseline`, one message each.
+fn regressions(results: &Results, baseline: &Results) -> Vec<String> {
+    let mut failures = Vec::new();
+    for (key, value) in results {
+        match baseline.get(key) {
+            Some(limit) if value > limit => failures.push(format!("{}: {} > baseline {}", key, value, limit)),
+            Some(_) => {}
+            None => failures.push(format!("{}: {} has no baseline", key, value)),
+        }
+    }
+    for key in baseline.keys().filter(|key| !results.contains_key(*key)) {
+        failures.push(format!("{}: no longer measured", key));
+    }
+    failures
+}
+
+/// Broken `BOUNDS`, one message each.
+fn broken_bounds(results: &Results) -> Vec<String> {
+    let mut failures = Vec::new();
+    for (key, limit) in BOUNDS {
+        match (results.get(key), results.get(limit)) {
+            (Some(value), Some(bound)) if value > bound => failures.push(format!("{}: {} > {} {}", key, value, limit, bound)),
+            (Some(_), Some(_)) => {}
+            _ => failures.push(format!("{}: bound by {} but not both are measured", key, limit)),
+        }
+    }
+    failures
+}
+
+fn run() -> Result<Results, Box<dyn Error>> {
+    let load = || SvmCluster::load().ok_or_else(|| format!("{} not found, run `anchor build` first", SvmCluster::program_path().display()));
+    let mut galaxy = Bench::new(load()?);
+    scenarios::galaxy(&mut galaxy)?;
+    let mut migrations = Bench::new(load()?);
+    scenarios::migrations(&mut migrations)?;
+
+    let mut results = galaxy.results;
+    results.extend(migrations.results);
+    Ok(results)
+}
+
+fn main() -> ExitCode {
+    let update = env::args().any(|arg| arg == "--update");
+    let results = match run() {
+        Ok(results) => results,
+        Err(err) => {
+            eprintln!("bench failed: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    for (key, value) in &results {
+        println!("{:<40} {:>8}", key, value);
+    }
+    let broken = broken_bounds(&results);
+    for failure in &broken {
+        eprintln!("bound broken: {}", failure);
+    }
+    if !broken.is_empty() {
+        return ExitCode::FAILURE;
+    }
+
+    let path = baseline_path();
+    if update {
+        if let Err(err) = fs::write(&path, format_baseline(&results)) {
+            eprintln!("cannot write {}: {}", path.display(), err);
+            return ExitCode::FAILURE;
+        }
+        println!("baseline written to {}", path.display());
+        return ExitCode::SUCCESS;
+    }
+
+    let baseline = match fs::read_to_string(&path).map_err(Box::<dyn Error>::from).and_then(|text| parse_baseline(&text)) {
+        Ok(baseline) => baseline,
+        Err(err) => {
+            eprintln!("cannot read baseline {}: {} (create it with --update)", path.display(), err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let failures = regressions(&results, &baseline);
+    for failure in &failures {
+        eprintln!("regression: {}", failure);
+    }
+    if failures.is_empty() { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
@@ -0,0 +1,329 @@
+use std::error::Error;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{AnchorSerialize, Discriminator};
+use anchor_spl::token::spl_token;
+use oridion::accounts_comet::{Deposit, DestinationSplit, RoutePolicy, Star};
+use oridion::accounts_migrate::{DepositV3, PlanetV3, StarV3, UniverseV3};
+use oridion::accounts_planet::Planet;
+use oridion::accounts_universe::{PlanetIndex, Universe};
+use oridion::shared::{get_destination_commitment, get_note_hash, select_next_planet};
+use oridion::variables::{DEPOSIT_PDA_SEED_POST, DEPOSIT_PDA_SEED_PRE, MANAGER_PUBKEY, MAX_PLANETS};
+use oridion_client::crank::{star_ids, MODE_DELAY, MODE_MANUAL};
+use oridion_client::instructions::{MintFees, UniverseFees};
+use oridion_client::svm::SvmCluster;
+use oridion_client::{instructions, lookup_table, pda, Cluster};
+use crate::bench::Bench;
+
+const START: i64 = 1_700_000_000;
+const SOL: u64 = 1_000_000_000;
+const WITHDRAW_AT: i64 = START + 10_000;
+const SALT: [u8; 32] = [7; 32];
+const SECRET: [u8; 32] = [9; 32];
+const MAX_RELAYER_FEE: u64 = 10_000_000;
+
+//Planets serving the token mint, the rest of the index is filled up to MAX_PLANETS
+const TOKEN_PLANETS: [u16; 3] = [1, 2, 3];
+//Planet ids run from 0 to MAX_PLANETS - 1, this one fills the index last
+const LAST_PLANET: u16 = 0;
+
+type BenchResult = Result<(), Box<dyn Error>>;
+
+/// Every instruction against a universe whose planet index is full.
+pub fn galaxy(bench: &mut Bench) -> BenchResult {
+    let mint = Pubkey::new_unique();
+    setup(bench, &mint)?;
+    sol_deposit(bench)?;
+    keeper(bench)?;
+    batch_hop(bench)?;
+    deposit_variants(bench)?;
+    relayer(bench)?;
+    token(bench, &mint)?;
+    deposit_locks(bench, &mint);
+    bench.cu("aggregate_stats", &MANAGER_PUBKEY, instructions::aggregate_stats(&TOKEN_PLANETS))
+}
+
+/// Migration of a v3.0.0 universe, planet, deposit and star.
+pub fn migrations(bench: &mut Bench) -> BenchResult {
+    let name = "mercury";
+    let universe = UniverseV3 {
+        pda: pda::universe(),
+        p: vec![name.to_string()],
+        st: START,
+        up: START,
+        bp: 255,
+        cfe: 1,
+        hpfe: 2,
+        hsfe2: 3,
+        hsfe3: 4,
+        wfe: 5,
+    };
+    let planet = PlanetV3 { name: name.to_string(), pda: pda::legacy_planet(name), created: START, bump: 255, visits: 0 };
+    let deposit = DepositV3 {
+        mode: MODE_DELAY,
+        next_process_at: START,
+        next_process: 0,
+        last_process: 0,
+        location: name.to_string(),
+        delay: 0,
+        hops: 0,
+        withdraw_at: WITHDRAW_AT,
+        created_at: START,
+        last_process_at: START,
+        lamports: SOL,
+        destination: Pubkey::new_unique(),
+    };
+    let address = Pubkey::new_unique();
+    let cluster = &mut bench.cluster;
+    cluster.set_unix_timestamp(START);
+    cluster.set_program_account(&pda::universe(), legacy::<Universe>(&universe, UniverseV3::LEN));
+    cluster.set_program_account(&pda::legacy_planet(name), legacy::<Planet>(&planet, PlanetV3::LEN));
+    cluster.set_program_account(&address, legacy::<Deposit>(&deposit, DepositV3::LEN));
+    cluster.airdrop(&pda::legacy_planet(name), SOL);
+    let star = "legacy star";
+    cluster.set_program_account(&pda::star(star), legacy::<Star>(&StarV3 { amount: SOL, manager: MANAGER_PUBKEY }, StarV3::LEN));
+
+    bench.cu("migrate_universe", &MANAGER_PUBKEY, instructions::migrate_universe(&MANAGER_PUBKEY))?;
+    bench.cu("migrate_planet", &MANAGER_PUBKEY, instructions::migrate_planet(&MANAGER_PUBKEY, name, 1))?;
+    bench.cu("migrate_deposit", &MANAGER_PUBKEY, instructions::migrate_deposit(&MANAGER_PUBKEY, &address, 1, 0))?;
+    bench.cu("migrate_star", &MANAGER_PUBKEY, instructions::migrate_star(&MANAGER_PUBKEY, star))
+}
+
+/// Account data as v3.0.0 wrote it: discriminator, fields, zero padding.
+fn legacy<T: Discriminator>(value: &impl AnchorSerialize, len: usize) -> Vec<u8> {
+    let mut data = T::DISCRIMINATOR.to_vec();
+    value.serialize(&mut data).expect("legacy account serializes");
+    data.resize(len, 0);
+    data
+}
+
+fn setup(bench: &mut Bench, mint: &Pubkey) -> BenchResult {
+    bench.cluster.set_unix_timestamp(START);
+    bench.cluster.create_mint(mint, 6);
+    bench.cu("bang", &MANAGER_PUBKEY, instructions::bang(&MANAGER_PUBKEY))?;
+    bench.cu("create_galaxy_stats", &MANAGER_PUBKEY, instructions::create_galaxy_stats(&MANAGER_PUBKEY))?;
+    let fees = UniverseFees { cfe: 1_000_000, hpfe: 1_000_000, hsfe2: 2_000_000, hsfe3: 3_000_000, wfe: 1_000_000 };
+    bench.cu("update_fee", &MANAGER_PUBKEY, instructions::update_fee(&MANAGER_PUBKEY, fees))?;
+    bench.cu("update_buckets", &MANAGER_PUBKEY, instructions::update_buckets(&MANAGER_PUBKEY, &[SOL / 10, SOL, 10 * SOL]))?;
+    bench.cu("update_recent_window", &MANAGER_PUBKEY, instructions::update_recent_window(&MANAGER_PUBKEY, 0))?;
+    bench.cu("update_hop_destination_mode", &MANAGER_PUBKEY, instructions::update_hop_destination_mode(&MANAGER_PUBKEY, 0))?;
+    let (create, table) = lookup_table::create(&MANAGER_PUBKEY, &MANAGER_PUBKEY, bench.cluster.slot()?);
+    bench.cluster.send(&[create])?;
+    bench.cu("update_lookup_table", &MANAGER_PUBKEY, instructions::update_lookup_table(&MANAGER_PUBKEY, &table))?;
+    bench.cu("create_mint_config", &MANAGER_PUBKEY, instructions::create_mint_config(&MANAGER_PUBKEY, mint, 1_000, 100 * SOL, MintFees::default()))?;
+    bench.cu("update_mint_config", &MANAGER_PUBKEY, instructions::update_mint_config(&MANAGER_PUBKEY, mint, 1_000, 100 * SOL, MintFees { hpfe: Some(1_000), wfe: Some(1_000) }))?;
+
+    for id in (0..MAX_PLANETS as u16).filter(|id| *id != LAST_PLANET).chain([LAST_PLANET]) {
+        let mints: &[Pubkey] = if TOKEN_PLANETS.contains(&id) { std::slice::from_ref(mint) } else { &[] };
+        bench.cu("create_planet", &MANAGER_PUBKEY, instructions::create_planet(&MANAGER_PUBKEY, id, &format!("planet {id}"), mints))?;
+
+        //Same deposit as sol_deposit makes with the index full, to compare the two in the baseline
+        if id == TOKEN_PLANETS.len() as u16 {
+            let creator = first_bump_creator(&mut bench.cluster, SOL, None);
+            let ix = instructions::create_deposit(&creator, 1, &Pubkey::new_unique(), SOL, MODE_MANUAL, 0, START);
+            bench.cu("create_deposit_three_planets", &creator, ix)?;
+        }
+    }
+    for id in TOKEN_PLANETS {
+        let ix = instructions::create_planet_token_account(&MANAGER_PUBKEY, id, mint, &spl_token::ID);
+        bench.cu("create_planet_token_account", &MANAGER_PUBKEY, ix)?;
+    }
+
+    //Delete the last planet of the full index and create it again
+    let id = LAST_PLANET;
+    bench.cu("delete_planet", &MANAGER_PUBKEY, instructions::delete_planet(&MANAGER_PUBKEY, id, &[]))?;
+    bench.cu("create_planet", &MANAGER_PUBKEY, instructions::create_planet(&MANAGER_PUBKEY, id, &format!("planet {id}"), &[]))?;
+
+    bench.size("Universe", &pda::universe())?;
+    bench.size("PlanetIndex", &pda::planet_index())?;
+    bench.size("Planet", &pda::planet(TOKEN_PLANETS[0]))?;
+    bench.size("GalaxyStats", &pda::galaxy_stats())?;
+    bench.size("PlanetStats", &pda::planet_stats(TOKEN_PLANETS[0]))?;
+    bench.size("MintConfig", &pda::mint_config(mint))
+}
+
+/// New account holding `lamports` plus a SOL for fees and rent.
+fn funded(cluster: &mut SvmCluster, lamports: u64) -> Pubkey {
+    let account = Pubkey::new_unique();
+    cluster.airdrop(&account, lamports + SOL);
+    account
+}
+
+/// Funded creator whose deposit address (bucket deposit with `nonce`) is found at
+/// the first bump, so the deposits it opens cost the same however many bumps
+/// other keys need.
+fn first_bump_creator(cluster: &mut SvmCluster, lamports: u64, nonce: Option<u8>) -> Pubkey {
+    let bump = |creator: &Pubkey| match nonce {
+        Some(nonce) => Pubkey::find_program_address(&[DEPOSIT_PDA_SEED_PRE, creator.as_ref(), &[nonce], DEPOSIT_PDA_SEED_POST], &oridion::ID).1,
+        None => Pubkey::find_program_address(&[DEPOSIT_PDA_SEED_PRE, creator.as_ref(), DEPOSIT_PDA_SEED_POST], &oridion::ID).1,
+    };
+    let creator = std::iter::repeat_with(Pubkey::new_unique)
+        .find(|creator| bump(creator) == u8::MAX)
+        .expect("keys are endless");
+    cluster.airdrop(&creator, lamports + SOL);
+    creator
+}
+
+/// Deposit, planet hop, both star hop variants and a manager withdraw.
+fn sol_deposit(bench: &mut Bench) -> BenchResult {
+    let creator = first_bump_creator(&mut bench.cluster, SOL, None);
+    let destination = Pubkey::new_unique();
+    let address = pda::deposit(&creator);
+    let ix = instructions::create_deposit(&creator, 1, &destination, SOL, MODE_MANUAL, 0, START);
+    bench.cu("create_deposit", &creator, ix)?;
+    bench.size("Deposit", &address)?;
+
+    bench.cu("planet_hop", &MANAGER_PUBKEY, instructions::planet_hop(&MANAGER_PUBKEY, &address, 1, 2))?;
+
+    let stars = star_ids(b"bench", &address, 1, 2);
+    let stars = [stars[0].as_str(), stars[1].as_str()];
+    bench.cu("star_hop_two_start", &MANAGER_PUBKEY, instructions::star_hop_two_start(&MANAGER_PUBKEY, &address, 2, stars))?;
+    bench.size("Star", &pda::star(stars[0]))?;
+    bench.cu("star_hop_two_end", &MANAGER_PUBKEY, instructions::star_hop_two_end(&MANAGER_PUBKEY, &address, 3, stars))?;
+
+    let stars = star_ids(b"bench", &address, 2, 3);
+    let stars = [stars[0].as_str(), stars[1].as_str(), stars[2].as_str()];
+    bench.cu("star_hop_three_start", &MANAGER_PUBKEY, instructions::star_hop_three_start(&MANAGER_PUBKEY, &address, 3, stars))?;
+    bench.cu("star_hop_three_end", &MANAGER_PUBKEY, instructions::star_hop_three_end(&MANAGER_PUBKEY, &address, LAST_PLANET, stars))?;
+
+    let ix = instructions::withdraw(&MANAGER_PUBKEY, &address, LAST_PLANET, &destination);
+    bench.cu("withdraw", &MANAGER_PUBKEY, ix)
+}
+
+/// Keeper hop over the full planet index, then a keeper withdraw.
+fn keeper(bench: &mut Bench) -> BenchResult {
+    let creator = funded(&mut bench.cluster, SOL);
+    let destination = Pubkey::new_unique();
+    let address = pda::deposit(&creator);
+    let keeper = funded(&mut bench.cluster, 0);
+    let ix = instructions::create_deposit(&creator, 1, &destination, SOL, MODE_DELAY, 0, START + 1_000);
+    bench.cluster.send_as(&creator, &[ix])?;
+
+    bench.cluster.set_unix_timestamp(START + 200);
+    let index: PlanetIndex = bench.cluster.account(&pda::planet_index())?.ok_or("planet index not found")?;
+    let deposit: Deposit = bench.cluster.account(&address)?.ok_or("deposit not found")?;
+    let to = select_next_planet(&index.p, &address, &deposit).ok_or("no planet selected")?;
+    bench.cu("keeper_hop", &keeper, instructions::keeper_hop(&keeper, &address, 1, to))?;
+
+    bench.cluster.set_unix_timestamp(START + 1_000);
+    bench.cu("keeper_withdraw", &keeper, instructions::keeper_withdraw(&keeper, &address, to, &destination))?;
+    bench.cluster.set_unix_timestamp(START);
+    Ok(())
+}
+
+/// Batch hop of as many deposits as the crank sends without a lookup table,
+/// each landing on a different planet.
+fn batch_hop(bench: &mut Bench) -> BenchResult {
+    let mut hops = Vec::new();
+    for to in 2..=6 {
+        let creator = funded(&mut bench.cluster, SOL);
+        bench.cluster.send_as(&creator, &[instructions::create_deposit(&creator, 1, &Pubkey::new_unique(), SOL, MODE_MANUAL, 0, START)])?;
+        hops.push((pda::deposit(&creator), 1, to));
+    }
+    bench.cu("batch_hop", &MANAGER_PUBKEY, instructions::batch_hop(&MANAGER_PUBKEY, &hops))
+}
+
+/// Bucket, committed and note deposits, splits and withdraw schedules.
+fn deposit_variants(bench: &mut Bench) -> BenchResult {
+    let creator = first_bump_creator(&mut bench.cluster, SOL, Some(0));
+    let ix = instructions::create_bucket_deposit(&creator, 1, &Pubkey::new_unique(), 0, SOL, MODE_MANUAL, 0, START);
+    bench.cu("create_bucket_deposit", &creator, ix)?;
+
+    let creator = funded(&mut bench.cluster, SOL);
+    let destination = Pubkey::new_unique();
+    let commitment = get_destination_commitment(&destination, &SALT);
+    let ix = instructions::create_committed_deposit(&creator, 1, SOL, MODE_MANUAL, 0, START, commitment);
+    bench.cu("create_committed_deposit", &creator, ix)?;
+    let ix = instructions::withdraw_committed(&MANAGER_PUBKEY, &pda::deposit(&creator), 1, &destination, None, SALT);
+    bench.cu("withdraw_committed", &MANAGER_PUBKEY, ix)?;
+
+    let creator = funded(&mut bench.cluster, SOL);
+    let destination = Pubkey::new_unique();
+    let ix = instructions::create_note_deposit(&creator, 1, SOL, MODE_MANUAL, 0, START, get_note_hash(&SECRET, &destination, 0));
+    bench.cu("create_note_deposit", &creator, ix)?;
+    let ix = instructions::withdraw_note(&pda::deposit(&creator), 1, &destination, SECRET, 0);
+    bench.cu("withdraw_note", &MANAGER_PUBKEY, ix)?;
+
+    let creator = funded(&mut bench.cluster, SOL);
+    bench.cluster.send_as(&creator, &[instructions::create_deposit(&creator, 1, &Pubkey::new_unique(), SOL, MODE_MANUAL, 0, START)])?;
+    let splits = [
+        DestinationSplit { destination: Pubkey::new_unique(), bps: 5_000, min: 0 },
+        DestinationSplit { destination: Pubkey::new_unique(), bps: 5_000, min: 0 },
+    ];
+    bench.cu("set_deposit_splits", &creator, instructions::set_deposit_splits(&creator, None, 1, &splits))?;
+
+    let creator = funded(&mut bench.cluster, SOL);
+    bench.cluster.send_as(&creator, &[instructions::create_deposit(&creator, 1, &Pubkey::new_unique(), SOL, MODE_MANUAL, 0, START)])?;
+    bench.cu("set_withdraw_schedule", &creator, instructions::set_withdraw_schedule(&creator, None, 3, 60, 120))?;
+    let policy = RoutePolicy { min_hops: 4, max_hops: 8, min_stars: 2, max_fees: SOL / 100, avoid_revisit: true };
+    bench.cu("set_route_policy", &creator, instructions::set_route_policy(&creator, None, policy))
+}
+
+fn relayer(bench: &mut Bench) -> BenchResult {
+    let relayer = funded(&mut bench.cluster, 0);
+    bench.cu("create_relayer_registry", &MANAGER_PUBKEY, instructions::create_relayer_registry(&MANAGER_PUBKEY, MAX_RELAYER_FEE))?;
+    bench.cu("update_relayer_fee", &MANAGER_PUBKEY, instructions::update_relayer_fee(&MANAGER_PUBKEY, MAX_RELAYER_FEE))?;
+    bench.cu("add_relayer", &MANAGER_PUBKEY, instructions::add_relayer(&MANAGER_PUBKEY, &relayer))?;
+    bench.size("RelayerRegistry", &pda::relayer_registry())?;
+
+    let creator = funded(&mut bench.cluster, SOL);
+    let destination = Pubkey::new_unique();
+    let ix = instructions::create_note_deposit(&creator, 1, SOL, MODE_MANUAL, 0, START, get_note_hash(&SECRET, &destination, MAX_RELAYER_FEE));
+    bench.cluster.send_as(&creator, &[ix])?;
+    let ix = instructions::relay_withdraw(&relayer, &pda::deposit(&creator), 1, &destination, SECRET, MAX_RELAYER_FEE);
+    bench.cu("relay_withdraw", &relayer, ix)?;
+
+    bench.cu("remove_relayer", &MANAGER_PUBKEY, instructions::remove_relayer(&MANAGER_PUBKEY, &relayer))
+}
+
+/// SPL token deposit through the same route as the SOL deposit.
+fn token(bench: &mut Bench, mint: &Pubkey) -> BenchResult {
+    let program = spl_token::ID;
+    let creator = funded(&mut bench.cluster, 0);
+    let destination = Pubkey::new_unique();
+    let address = pda::deposit(&creator);
+    let [first, second, third] = TOKEN_PLANETS;
+    bench.cluster.mint_to(&creator, mint, 10 * SOL);
+    let ix = instructions::create_token_deposit(&creator, first, mint, &program, &destination, 10 * SOL, MODE_MANUAL, 0, START);
+    bench.cu("create_token_deposit", &creator, ix)?;
+
+    bench.cu("token_planet_hop", &MANAGER_PUBKEY, instructions::token_planet_hop(&MANAGER_PUBKEY, &address, mint, &program, first, second))?;
+
+    let stars = star_ids(b"bench", &address, 1, 2);
+    let stars = [stars[0].as_str(), stars[1].as_str()];
+    let ix = instructions::token_star_hop_two_start(&MANAGER_PUBKEY, &address, mint, &program, second, stars);
+    bench.cu("token_star_hop_two_start", &MANAGER_PUBKEY, ix)?;
+    let ix = instructions::token_star_hop_two_end(&MANAGER_PUBKEY, &address, mint, &program, third, stars);
+    bench.cu("token_star_hop_two_end", &MANAGER_PUBKEY, ix)?;
+
+    let stars = star_ids(b"bench", &address, 2, 3);
+    let stars = [stars[0].as_str(), stars[1].as_str(), stars[2].as_str()];
+    let ix = instructions::token_star_hop_three_start(&MANAGER_PUBKEY, &address, mint, &program, third, stars);
+    bench.cu("token_star_hop_three_start", &MANAGER_PUBKEY, ix)?;
+    let ix = instructions::token_star_hop_three_end(&MANAGER_PUBKEY, &address, mint, &program, first, stars);
+    bench.cu("token_star_hop_three_end", &MANAGER_PUBKEY, ix)?;
+
+    bench.cu("token_withdraw", &MANAGER_PUBKEY, instructions::token_withdraw(&MANAGER_PUBKEY, &address, mint, &program, first, &destination))?;
+
+    let unused = Pubkey::new_unique();
+    bench.cluster.create_mint(&unused, 6);
+    bench.cluster.send(&[instructions::create_mint_config(&MANAGER_PUBKEY, &unused, 1_000, SOL, MintFees::default())])?;
+    bench.cu("delete_mint_config", &MANAGER_PUBKEY, instructions::delete_mint_config(&MANAGER_PUBKEY, &unused))
+}
+
+/// Write locks shared by two deposits from different creators on different planets.
+fn deposit_locks(bench: &mut Bench, mint: &Pubkey) {
+    let [first, second] = [(Pubkey::new_unique(), TOKEN_PLANETS[0]), (Pubkey::new_unique(), TOKEN_PLANETS[1])];
+    let pair = |build: &dyn Fn(&Pubkey, u16) -> Instruction| (build(&first.0, first.1), build(&second.0, second.1));
+
+    let (a, b) = pair(&|creator, planet| instructions::create_deposit(creator, planet, &Pubkey::new_unique(), SOL, MODE_MANUAL, 0, START));
+    bench.locks("create_deposit", &a, &b);
+    let (a, b) = pair(&|creator, planet| instructions::create_bucket_deposit(creator, planet, &Pubkey::new_unique(), 0, SOL, MODE_MANUAL, 0, START));
+    bench.locks("create_bucket_deposit", &a, &b);
+    let (a, b) = pair(&|creator, planet| instructions::create_note_deposit(creator, planet, SOL, MODE_MANUAL, 0, START, get_note_hash(&SECRET, &Pubkey::new_unique(), 0)));
+    bench.locks("create_note_deposit", &a, &b);
+    let (a, b) = pair(&|creator, planet| {
+        instructions::create_token_deposit(creator, planet, mint, &spl_token::ID, &Pubkey::new_unique(), SOL, MODE_MANUAL, 0, START)
+    });
+    bench.locks("create_token_deposit", &a, &b);
+}
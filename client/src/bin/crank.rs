@@ -0,0 +1,49 @@
+//! Oridion crank. Moves due deposits along by hopping them between planets
+//! and stars, then withdraws them once their withdraw time has come.
+//!
+//! Configuration (environment):
+//! - `ORIDION_RPC_URL`        RPC endpoint (default `http://127.0.0.1:8899`)
+//! - `ORIDION_KEYPAIR`        manager keypair file (default `~/.config/solana/id.json`)
+//! - `ORIDION_STAR_SALT`      secret used to derive star ids (required)
+//! - `ORIDION_CRANK_INTERVAL` seconds between ticks (default `10`)
+//! - `ORIDION_KEEPER`         set to `1` to run as a permissionless keeper
+use std::env;
+use std::error::Error;
+use std::thread;
+use std::time::Duration;
+use oridion_client::{ClientError, Crank, CrankConfig, RpcCluster};
+use rand::rngs::OsRng;
+use solana_sdk::signature::read_keypair_file;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let url = env::var("ORIDION_RPC_URL").unwrap_or_else(|_| "http://127.0.0.1:8899".to_string());
+    let keypair_path = env::var("ORIDION_KEYPAIR")
+        .unwrap_or_else(|_| format!("{}/.config/solana/id.json", env::var("HOME").unwrap_or_default()));
+    let salt = env::var("ORIDION_STAR_SALT")
+        .map_err(|_| ClientError::Config("ORIDION_STAR_SALT must be set".to_string()))?;
+    let interval: u64 = env::var("ORIDION_CRANK_INTERVAL")
+        .unwrap_or_else(|_| "10".to_string())
+        .parse()
+        .map_err(|_| ClientError::Config("ORIDION_CRANK_INTERVAL must be a number of seconds".to_string()))?;
+
+    let manager = read_keypair_file(&keypair_path)
+        .map_err(|err| ClientError::Config(format!("cannot read keypair {}: {}", keypair_path, err)))?;
+    let keeper = env::var("ORIDION_KEEPER").is_ok_and(|value| value == "1");
+    let config = CrankConfig { salt: salt.into_bytes(), keeper, ..CrankConfig::default() };
+    let mut crank = Crank::new(RpcCluster::new(url, manager), OsRng, config);
+
+    loop {
+        match crank.tick() {
+            Ok(outcomes) => {
+                for (deposit, result) in outcomes {
+                    match result {
+                        Ok(action) => println!("{}: {:?}", deposit, action),
+                        Err(err) => eprintln!("{}: {}", deposit, err),
+                    }
+                }
+            }
+            Err(err) => eprintln!("tick failed: {}", err),
+        }
+        thread::sleep(Duration::from_secs(interval));
+    }
+}
@@ -0,0 +1,131 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::address_lookup_table::state::AddressLookupTable;
+use anchor_lang::solana_program::address_lookup_table::AddressLookupTableAccount;
+use anchor_lang::solana_program::clock::Clock;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+use crate::{lookup_table, ClientError};
+
+/// Everything the crank needs from a cluster. Implemented over RPC for
+/// production and over LiteSVM (`svm` feature) for local tests.
+pub trait Cluster {
+    /// Fee payer and signer of every transaction sent through `send`.
+    fn payer(&self) -> Pubkey;
+
+    fn unix_timestamp(&self) -> Result<i64, ClientError>;
+
+    fn slot(&self) -> Result<u64, ClientError>;
+
+    fn account<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<Option<T>, ClientError>;
+
+    /// Program owning the account, e.g. the token program of a mint.
+    fn owner(&self, address: &Pubkey) -> Result<Option<Pubkey>, ClientError>;
+
+    /// All program accounts of type `T`, matched by discriminator.
+    fn program_accounts<T: AccountDeserialize + Discriminator>(&self) -> Result<Vec<(Pubkey, T)>, ClientError>;
+
+    /// Address lookup table at `address`, `None` if there is none.
+    fn lookup_table(&self, address: &Pubkey) -> Result<Option<AddressLookupTableAccount>, ClientError>;
+
+    fn send(&mut self, instructions: &[Instruction]) -> Result<Signature, ClientError>;
+
+    /// Send as a v0 transaction, resolving accounts through `tables`.
+    fn send_v0(&mut self, instructions: &[Instruction], tables: &[AddressLookupTableAccount]) -> Result<Signature, ClientError>;
+}
+
+pub(crate) fn deserialize<T: AccountDeserialize>(address: &Pubkey, mut data: &[u8]) -> Result<T, ClientError> {
+    T::try_deserialize(&mut data).map_err(|err| ClientError::Deserialize(*address, err))
+}
+
+pub(crate) fn deserialize_lookup_table(address: &Pubkey, data: &[u8]) -> Result<AddressLookupTableAccount, ClientError> {
+    let table = AddressLookupTable::deserialize(data).map_err(|err| ClientError::LookupTable(*address, err.to_string()))?;
+    Ok(AddressLookupTableAccount { key: *address, addresses: table.addresses.to_vec() })
+}
+
+pub struct RpcCluster {
+    rpc: RpcClient,
+    payer: Keypair,
+}
+
+impl RpcCluster {
+    pub fn new(url: String, payer: Keypair) -> Self {
+        RpcCluster {
+            rpc: RpcClient::new_with_commitment(url, CommitmentConfig::confirmed()),
+            payer,
+        }
+    }
+}
+
+impl Cluster for RpcCluster {
+    fn payer(&self) -> Pubkey {
+        self.payer.pubkey()
+    }
+
+    fn unix_timestamp(&self) -> Result<i64, ClientError> {
+        let account = self.rpc.get_account(&sysvar::clock::ID)?;
+        let clock: Clock = solana_sdk::account::from_account(&account)
+            .ok_or_else(|| ClientError::Config("clock sysvar could not be read".to_string()))?;
+        Ok(clock.unix_timestamp)
+    }
+
+    fn slot(&self) -> Result<u64, ClientError> {
+        Ok(self.rpc.get_slot()?)
+    }
+
+    fn account<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<Option<T>, ClientError> {
+        let response = self.rpc.get_account_with_commitment(address, self.rpc.commitment())?;
+        response.value.map(|account| deserialize(address, &account.data)).transpose()
+    }
+
+    fn owner(&self, address: &Pubkey) -> Result<Option<Pubkey>, ClientError> {
+        let response = self.rpc.get_account_with_commitment(address, self.rpc.commitment())?;
+        Ok(response.value.map(|account| account.owner))
+    }
+
+    fn lookup_table(&self, address: &Pubkey) -> Result<Option<AddressLookupTableAccount>, ClientError> {
+        let response = self.rpc.get_account_with_commitment(address, self.rpc.commitment())?;
+        response.value.map(|account| deserialize_lookup_table(address, &account.data)).transpose()
+    }
+
+    fn program_accounts<T: AccountDeserialize + Discriminator>(&self) -> Result<Vec<(Pubkey, T)>, ClientError> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &T::DISCRIMINATOR))]),
+            account_config: RpcAccountInfoConfig {
+                commitment: Some(self.rpc.commitment()),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        self.rpc
+            .get_program_accounts_with_config(&oridion::ID, config)?
+            .into_iter()
+            .map(|(address, account)| Ok((address, deserialize(&address, &account.data)?)))
+            .collect()
+    }
+
+    fn send(&mut self, instructions: &[Instruction]) -> Result<Signature, ClientError> {
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &[&self.payer],
+            blockhash,
+        );
+        Ok(self.rpc.send_and_confirm_transaction(&transaction)?)
+    }
+
+    fn send_v0(&mut self, instructions: &[Instruction], tables: &[AddressLookupTableAccount]) -> Result<Signature, ClientError> {
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let message = lookup_table::v0_message(&self.payer.pubkey(), instructions, tables, blockhash)?;
+        let transaction = VersionedTransaction::try_new(message, &[&self.payer])
+            .map_err(|err| ClientError::Transaction(err.to_string()))?;
+        Ok(self.rpc.send_and_confirm_transaction(&transaction)?)
+    }
+}
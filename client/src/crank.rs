@@ -0,0 +1,385 @@
+use std::thread;
+use std::time::Duration;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::address_lookup_table::AddressLookupTableAccount;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::instruction::Instruction;
+use oridion::accounts_comet::{Deposit, Star};
+use oridion::accounts_stats::{GalaxyStats, PlanetStats};
+use oridion::accounts_token::MintConfig;
+use oridion::accounts_universe::{PlanetIndex, Universe};
+use oridion::shared::{get_route_candidates, is_route_complete, is_route_exhausted, is_route_full, select_next_planet};
+use oridion::variables::NATIVE_SOL;
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+use crate::{instructions, lookup_table, pda, Cluster, ClientError};
+
+/// Deposit modes (`Deposit.mode`)
+pub const MODE_DELAY: u8 = 1;
+pub const MODE_INSTANT: u8 = 2;
+pub const MODE_MANUAL: u8 = 3;
+
+/// `Deposit.next_process` value meaning the deposit is ready to withdraw.
+pub const NEXT_PROCESS_WITHDRAW: u8 = 1;
+
+/// `Deposit.dm` values: plaintext destination, hash(destination + salt) commitment
+/// or hash(secret) note.
+pub const DESTINATION_PLAINTEXT: u8 = 0;
+pub const DESTINATION_COMMITTED: u8 = 1;
+pub const DESTINATION_NOTE: u8 = 2;
+
+/// `Universe.hdm` values: the manager picks where SOL hops land, or the program does.
+pub const HOP_DESTINATION_MANAGER: u8 = 0;
+pub const HOP_DESTINATION_PROGRAM: u8 = 1;
+
+/// Hex characters per star id. Star ids are used as a PDA seed.
+const STAR_ID_LENGTH: usize = 16;
+
+/// Planet stats folded in per aggregate_stats transaction.
+const AGGREGATE_STATS_PER_TX: usize = 16;
+
+/// Planet hops per batch_hop transaction. Planets and their stats are one byte
+/// each through the lookup table, but a full account key without it.
+const BATCH_HOPS_PER_TX: usize = 8;
+const BATCH_HOPS_PER_LEGACY_TX: usize = 5;
+
+/// What the crank will do with a due deposit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    PlanetHop { to: u16 },
+    /// Split into two or three stars and land on `to`.
+    StarHop { stars: Vec<String>, to: u16 },
+    /// Stars of an earlier star hop already hold the funds; only land them on `to`.
+    StarHopEnd { stars: Vec<String>, to: u16 },
+    Withdraw,
+}
+
+pub struct CrankConfig {
+    /// Secret mixed into star ids so observers cannot link stars to deposits.
+    /// Must stay the same across restarts to resume interrupted star hops.
+    pub salt: Vec<u8>,
+    pub max_attempts: u32,
+    pub retry_delay: Duration,
+    /// Run as a permissionless keeper: only delay deposits, planet hops to the
+    /// program-selected planet and withdrawals, paid through the keeper reward.
+    pub keeper: bool,
+}
+
+impl Default for CrankConfig {
+    fn default() -> Self {
+        CrankConfig {
+            salt: Vec::new(),
+            max_attempts: 3,
+            retry_delay: Duration::from_secs(2),
+            keeper: false,
+        }
+    }
+}
+
+/// Outcome of one deposit processed during a tick.
+pub type Outcome = (Pubkey, Result<Action, ClientError>);
+
+pub struct Crank<C: Cluster, R: RngCore> {
+    cluster: C,
+    rng: R,
+    config: CrankConfig,
+    /// The universe's lookup table, reloaded every tick. Transactions are sent as v0 once it is set.
+    tables: Vec<AddressLookupTableAccount>,
+    /// The universe's hop destination mode, reloaded every tick.
+    hdm: u8,
+}
+
+impl<C: Cluster, R: RngCore> Crank<C, R> {
+    pub fn new(cluster: C, rng: R, config: CrankConfig) -> Self {
+        Crank { cluster, rng, config, tables: Vec::new(), hdm: HOP_DESTINATION_MANAGER }
+    }
+
+    pub fn cluster(&self) -> &C {
+        &self.cluster
+    }
+
+    pub fn cluster_mut(&mut self) -> &mut C {
+        &mut self.cluster
+    }
+
+    /// Process every due deposit once.
+    pub fn tick(&mut self) -> Result<Vec<Outcome>, ClientError> {
+        let now = self.cluster.unix_timestamp()?;
+        let index_address = pda::planet_index();
+        let index: PlanetIndex = self.cluster.account(&index_address)?
+            .ok_or(ClientError::AccountNotFound(index_address))?;
+
+        let universe: Universe = self.cluster.account(&pda::universe())?
+            .ok_or(ClientError::AccountNotFound(pda::universe()))?;
+        self.tables = lookup_table::recorded_table(&self.cluster, &universe)?.into_iter().collect();
+        self.hdm = universe.hdm;
+        let due = self.due_deposits(now, &index.p)?;
+        let mut configs: Vec<(Pubkey, MintConfig)> = Vec::new();
+        for (_, deposit) in &due {
+            if deposit.mint != NATIVE_SOL && !configs.iter().any(|(mint, _)| *mint == deposit.mint) {
+                let address = pda::mint_config(&deposit.mint);
+                let config: MintConfig = self.cluster.account(&address)?
+                    .ok_or(ClientError::AccountNotFound(address))?;
+                configs.push((deposit.mint, config));
+            }
+        }
+
+        let mut outcomes = Vec::new();
+        let mut hops = Vec::new();
+        for (address, deposit) in due {
+            //Token deposits only hop between the planets serving their mint, as the program checks
+            let candidates = match configs.iter().find(|(mint, _)| *mint == deposit.mint) {
+                Some((_, config)) => config.p.clone(),
+                None => index.p.clone(),
+            };
+            match self.plan(&address, &deposit, &candidates, now) {
+                Ok(Action::PlanetHop { to }) if !self.config.keeper && deposit.mint == NATIVE_SOL => hops.push((address, deposit, to)),
+                planned => {
+                    let result = planned.and_then(|action| self.execute(&address, &deposit, &action).map(|_| action));
+                    outcomes.push((address, result));
+                }
+            }
+        }
+        outcomes.extend(self.batch_hop(&hops));
+        self.aggregate_stats(&index.p)?;
+        Ok(outcomes)
+    }
+
+    /// Planet hops of SOL deposits, sent as batch_hop transactions. A batch fails as a
+    /// whole, so its deposits then fall back to one planet_hop each and a bad entry
+    /// only fails itself.
+    pub fn batch_hop(&mut self, hops: &[(Pubkey, Deposit, u16)]) -> Vec<Outcome> {
+        let size = if self.tables.is_empty() { BATCH_HOPS_PER_LEGACY_TX } else { BATCH_HOPS_PER_TX };
+        let signer = self.cluster.payer();
+        let mut outcomes = Vec::new();
+        for chunk in hops.chunks(size) {
+            let entries: Vec<(Pubkey, u16, u16)> = chunk.iter().map(|(address, deposit, to)| (*address, deposit.location, *to)).collect();
+            let batched = self.submit(&[instructions::batch_hop(&signer, &entries)], |cluster| {
+                for (address, deposit, _) in chunk {
+                    if !hopped(cluster, address, deposit.hops)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }).is_ok();
+            for (address, deposit, to) in chunk {
+                let action = Action::PlanetHop { to: *to };
+                let result = if batched { Ok(action) } else { self.execute(address, deposit, &action).map(|_| action) };
+                outcomes.push((*address, result));
+            }
+        }
+        outcomes
+    }
+
+    /// Folds the stats of `planets` that recorded anything into the galaxy stats.
+    /// Does nothing until the manager has created the galaxy stats.
+    pub fn aggregate_stats(&mut self, planets: &[u16]) -> Result<(), ClientError> {
+        if self.cluster.account::<GalaxyStats>(&pda::galaxy_stats())?.is_none() {
+            return Ok(());
+        }
+        let mut pending = Vec::new();
+        for id in planets {
+            let stats: Option<PlanetStats> = self.cluster.account(&pda::planet_stats(*id))?;
+            if stats.is_some_and(|stats| stats.days.iter().any(|day| day.day != 0)) {
+                pending.push(*id);
+            }
+        }
+        for chunk in pending.chunks(AGGREGATE_STATS_PER_TX) {
+            self.cluster.send(&[instructions::aggregate_stats(chunk)])?;
+        }
+        Ok(())
+    }
+
+    /// Deposits whose `next_process_at` has passed. Manual deposits are never cranked
+    /// and keepers may only process native SOL delay deposits landed on a planet. Committed and note
+    /// deposits are left alone once due to withdraw, only their owner knows the destination.
+    pub fn due_deposits(&self, now: i64, planets: &[u16]) -> Result<Vec<(Pubkey, Deposit)>, ClientError> {
+        let mut deposits = self.cluster.program_accounts::<Deposit>()?;
+        let keeper = self.config.keeper;
+        deposits.retain(|(_, deposit)| {
+            let eligible = if keeper { deposit.mode == MODE_DELAY && deposit.mint == NATIVE_SOL && deposit.stars == 0 } else { deposit.mode != MODE_MANUAL };
+            let revealed = deposit.dm == DESTINATION_PLAINTEXT || !done_hopping(deposit, planets, now);
+            eligible && revealed && deposit.next_process_at <= now
+        });
+        Ok(deposits)
+    }
+
+    pub fn plan(&mut self, address: &Pubkey, deposit: &Deposit, planets: &[u16], now: i64) -> Result<Action, ClientError> {
+        if self.config.keeper {
+            if deposit.next_process == NEXT_PROCESS_WITHDRAW {
+                return Ok(Action::Withdraw);
+            }
+            let to = select_next_planet(planets, address, deposit)
+                .ok_or(ClientError::NoDestination(deposit.location))?;
+            return Ok(Action::PlanetHop { to });
+        }
+
+        //Funds sitting in stars must land before anything else happens to the deposit
+        if let Some(stars) = self.pending_stars(address, deposit) {
+            let to = self.destination(address, deposit, planets)?;
+            return Ok(Action::StarHopEnd { stars, to });
+        }
+
+        if done_hopping(deposit, planets, now) {
+            return Ok(Action::Withdraw);
+        }
+
+        let to = self.destination(address, deposit, planets)?;
+        let stars_needed = deposit.star_hops < deposit.route.min_stars;
+        Ok(match self.rng.gen_range(0..3u8) {
+            0 if !stars_needed => Action::PlanetHop { to },
+            n => Action::StarHop {
+                stars: star_ids(&self.config.salt, address, deposit.hops, n.max(1) as usize + 1),
+                to,
+            },
+        })
+    }
+
+    /// Where the deposit's next hop lands: the program-selected planet once the universe
+    /// asks for it, otherwise a random planet the route allows. `planets` are the
+    /// planets serving the mint for token deposits.
+    fn destination(&mut self, address: &Pubkey, deposit: &Deposit, planets: &[u16]) -> Result<u16, ClientError> {
+        if self.hdm == HOP_DESTINATION_PROGRAM {
+            return select_next_planet(planets, address, deposit)
+                .ok_or(ClientError::NoDestination(deposit.location));
+        }
+        pick_destination(&mut self.rng, &get_route_candidates(planets, deposit), deposit.location)
+    }
+
+    pub fn execute(&mut self, address: &Pubkey, deposit: &Deposit, action: &Action) -> Result<(), ClientError> {
+        let signer = self.cluster.payer();
+        let hops = deposit.hops;
+        let token = self.token_program(deposit)?;
+        match action {
+            Action::PlanetHop { to } if self.config.keeper => {
+                let ix = instructions::keeper_hop(&signer, address, deposit.location, *to);
+                self.submit(&[ix], |cluster| hopped(cluster, address, hops))
+            }
+            Action::PlanetHop { to } => {
+                let ix = match &token {
+                    Some(program) => instructions::token_planet_hop(&signer, address, &deposit.mint, program, deposit.location, *to),
+                    None => instructions::planet_hop(&signer, address, deposit.location, *to),
+                };
+                self.submit(&[ix], |cluster| hopped(cluster, address, hops))
+            }
+            Action::StarHop { stars, to } => {
+                let start = star_hop_start(&signer, address, deposit, token.as_ref(), stars);
+                let first_star = pda::star(&stars[0]);
+                self.submit(&[start], |cluster| star_exists(cluster, &first_star))?;
+                let end = star_hop_end(&signer, address, deposit, token.as_ref(), *to, stars);
+                self.submit(&[end], |cluster| hopped(cluster, address, hops))
+            }
+            Action::StarHopEnd { stars, to } => {
+                let end = star_hop_end(&signer, address, deposit, token.as_ref(), *to, stars);
+                self.submit(&[end], |cluster| hopped(cluster, address, hops))
+            }
+            Action::Withdraw => {
+                let mut ix = match &token {
+                    _ if self.config.keeper => instructions::keeper_withdraw(&signer, address, deposit.location, &deposit.destination),
+                    Some(program) => instructions::token_withdraw(&signer, address, &deposit.mint, program, deposit.location, &deposit.destination),
+                    None => instructions::withdraw(&signer, address, deposit.location, &deposit.destination),
+                };
+                instructions::add_split_destinations(&mut ix, deposit);
+                let tranches = deposit.tranches;
+                self.submit(&[ix], |cluster| withdrawn(cluster, address, tranches))
+            }
+        }
+    }
+
+    /// Token program of a token deposit's mint, `None` for SOL deposits.
+    fn token_program(&self, deposit: &Deposit) -> Result<Option<Pubkey>, ClientError> {
+        if deposit.mint == NATIVE_SOL {
+            return Ok(None);
+        }
+        self.cluster.owner(&deposit.mint)?
+            .map(Some)
+            .ok_or(ClientError::AccountNotFound(deposit.mint))
+    }
+
+    /// Stars left behind by a star hop whose end never landed.
+    fn pending_stars(&self, address: &Pubkey, deposit: &Deposit) -> Option<Vec<String>> {
+        (deposit.stars > 0).then(|| star_ids(&self.config.salt, address, deposit.hops, deposit.stars as usize))
+    }
+
+    /// Send with retries. `landed` is checked after every failure so an attempt that
+    /// landed but was reported as failed (e.g. confirmation timeout) is not repeated.
+    fn submit(&mut self, ixs: &[Instruction], landed: impl Fn(&C) -> Result<bool, ClientError>) -> Result<(), ClientError> {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let sent = if self.tables.is_empty() { self.cluster.send(ixs) } else { self.cluster.send_v0(ixs, &self.tables) };
+            let err = match sent {
+                Ok(_) => return Ok(()),
+                Err(err) => err,
+            };
+            if landed(&self.cluster)? {
+                return Ok(());
+            }
+            if attempts >= self.config.max_attempts {
+                return Err(ClientError::RetriesExhausted { attempts, last: Box::new(err) });
+            }
+            thread::sleep(self.config.retry_delay);
+        }
+    }
+}
+
+/// Due to withdraw with its route policy's minimums met, or out of hops or planets.
+fn done_hopping(deposit: &Deposit, planets: &[u16], now: i64) -> bool {
+    let due = deposit.next_process == NEXT_PROCESS_WITHDRAW || now >= deposit.withdraw_at;
+    is_route_full(deposit) || is_route_exhausted(deposit, planets) || (due && is_route_complete(deposit, planets))
+}
+
+fn hopped<C: Cluster>(cluster: &C, address: &Pubkey, hops: u16) -> Result<bool, ClientError> {
+    Ok(cluster.account::<Deposit>(address)?.is_some_and(|deposit| deposit.hops > hops))
+}
+
+/// Closed, or one tranche of a staggered withdrawal released.
+fn withdrawn<C: Cluster>(cluster: &C, address: &Pubkey, tranches: u8) -> Result<bool, ClientError> {
+    Ok(cluster.account::<Deposit>(address)?.is_none_or(|deposit| deposit.tranches < tranches))
+}
+
+fn star_exists<C: Cluster>(cluster: &C, address: &Pubkey) -> Result<bool, ClientError> {
+    Ok(cluster.account::<Star>(address)?.is_some())
+}
+
+fn star_hop_start(manager: &Pubkey, address: &Pubkey, deposit: &Deposit, token: Option<&Pubkey>, stars: &[String]) -> Instruction {
+    let (from, mint) = (deposit.location, &deposit.mint);
+    match (stars, token) {
+        ([one, two], Some(program)) => instructions::token_star_hop_two_start(manager, address, mint, program, from, [one, two]),
+        ([one, two, three], Some(program)) => instructions::token_star_hop_three_start(manager, address, mint, program, from, [one, two, three]),
+        ([one, two], None) => instructions::star_hop_two_start(manager, address, from, [one, two]),
+        ([one, two, three], None) => instructions::star_hop_three_start(manager, address, from, [one, two, three]),
+        _ => unreachable!("star hops use two or three stars"),
+    }
+}
+
+fn star_hop_end(manager: &Pubkey, address: &Pubkey, deposit: &Deposit, token: Option<&Pubkey>, to: u16, stars: &[String]) -> Instruction {
+    let mint = &deposit.mint;
+    match (stars, token) {
+        ([one, two], Some(program)) => instructions::token_star_hop_two_end(manager, address, mint, program, to, [one, two]),
+        ([one, two, three], Some(program)) => instructions::token_star_hop_three_end(manager, address, mint, program, to, [one, two, three]),
+        ([one, two], None) => instructions::star_hop_two_end(manager, address, to, [one, two]),
+        ([one, two, three], None) => instructions::star_hop_three_end(manager, address, to, [one, two, three]),
+        _ => unreachable!("star hops use two or three stars"),
+    }
+}
+
+/// Random planet other than the one the deposit is on.
+pub fn pick_destination<R: RngCore>(rng: &mut R, planets: &[u16], location: u16) -> Result<u16, ClientError> {
+    let candidates: Vec<u16> = planets.iter().copied().filter(|id| *id != location).collect();
+    candidates.choose(rng)
+        .copied()
+        .ok_or(ClientError::NoDestination(location))
+}
+
+/// Star ids for the deposit's current hop. Deterministic so a crank restart
+/// finds the stars of an interrupted star hop; the two-star ids are a prefix
+/// of the three-star ids.
+pub fn star_ids(salt: &[u8], deposit: &Pubkey, hops: u16, count: usize) -> Vec<String> {
+    (0..count as u8)
+        .map(|index| {
+            let hash = hashv(&[salt, deposit.as_ref(), &hops.to_le_bytes(), &[index]]);
+            hash.to_bytes()[..STAR_ID_LENGTH / 2].iter().map(|byte| format!("{:02x}", byte)).collect()
+        })
+        .collect()
+}
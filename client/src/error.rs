@@ -0,0 +1,30 @@
+use anchor_lang::prelude::Pubkey;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("rpc error: {0}")]
+    Rpc(Box<solana_client::client_error::ClientError>),
+    #[error("transaction failed: {0}")]
+    Transaction(String),
+    #[error("account {0} not found")]
+    AccountNotFound(Pubkey),
+    #[error("account {0} could not be deserialized: {1}")]
+    Deserialize(Pubkey, anchor_lang::error::Error),
+    #[error("no planet to hop to from planet {0}")]
+    NoDestination(u16),
+    #[error("gave up after {attempts} attempts: {last}")]
+    RetriesExhausted { attempts: u32, last: Box<ClientError> },
+    #[error("invalid configuration: {0}")]
+    Config(String),
+    #[error("{0} lamports cannot be split into the universe buckets")]
+    NotBucketable(u64),
+    #[error("account {0} is not an address lookup table: {1}")]
+    LookupTable(Pubkey, String),
+}
+
+impl From<solana_client::client_error::ClientError> for ClientError {
+    fn from(err: solana_client::client_error::ClientError) -> Self {
+        ClientError::Rpc(Box::new(err))
+    }
+}
@@ -0,0 +1,869 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::ID as ASSOCIATED_TOKEN_PROGRAM_ID;
+use oridion::accounts_comet::{Deposit, DestinationSplit, RoutePolicy};
+use oridion::variables::MANAGER_PUBKEY;
+use oridion::{accounts, instruction};
+use crate::{pda, ClientError};
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: oridion::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+///-------------------------------------------------------------------///
+/// UNIVERSE
+///-------------------------------------------------------------------///
+pub fn bang(manager: &Pubkey) -> Instruction {
+    build(
+        accounts::BigBang {
+            universe: pda::universe(),
+            planet_index: pda::planet_index(),
+            creator: *manager,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::Bang {},
+    )
+}
+
+/// Planet serving `mints`; each mint needs a MintConfig.
+pub fn create_planet(manager: &Pubkey, id: u16, name: &str, mints: &[Pubkey]) -> Instruction {
+    let mut ix = build(
+        accounts::CreatePlanet {
+            planet: pda::planet(id),
+            planet_stats: pda::planet_stats(id),
+            planet_index: pda::planet_index(),
+            creator: *manager,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::CreatePlanet { id, name: name.to_string(), mints: mints.to_vec() },
+    );
+    ix.accounts.extend(mints.iter().map(|mint| AccountMeta::new(pda::mint_config(mint), false)));
+    ix
+}
+
+/// Planet serving `mints`, the same mints it was created with.
+pub fn delete_planet(manager: &Pubkey, id: u16, mints: &[Pubkey]) -> Instruction {
+    let mut ix = build(
+        accounts::DeletePlanet {
+            planet: pda::planet(id),
+            planet_stats: pda::planet_stats(id),
+            stats: pda::galaxy_stats(),
+            planet_index: pda::planet_index(),
+            universe: pda::universe(),
+            creator: *manager,
+        },
+        instruction::DeletePlanet {},
+    );
+    ix.accounts.extend(mints.iter().map(|mint| AccountMeta::new(pda::mint_config(mint), false)));
+    ix
+}
+
+/// Universe fees in lamports.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UniverseFees {
+    pub cfe: u32,
+    pub hpfe: u32,
+    pub hsfe2: u32,
+    pub hsfe3: u32,
+    pub wfe: u32,
+}
+
+pub fn update_fee(manager: &Pubkey, fees: UniverseFees) -> Instruction {
+    build(
+        accounts::UpdateUniverseFee {
+            universe: pda::universe(),
+            creator: *manager,
+        },
+        instruction::UpdateFee {
+            comet_fee: fees.cfe,
+            hop_planet_fee: fees.hpfe,
+            hop_star_fee2: fees.hsfe2,
+            hop_star_fee3: fees.hsfe3,
+            withdraw_fee: fees.wfe,
+        },
+    )
+}
+
+pub fn update_buckets(manager: &Pubkey, buckets: &[u64]) -> Instruction {
+    build(
+        accounts::UpdateUniverseBuckets {
+            universe: pda::universe(),
+            creator: *manager,
+        },
+        instruction::UpdateBuckets { buckets: buckets.to_vec() },
+    )
+}
+
+pub fn update_recent_window(manager: &Pubkey, window: u8) -> Instruction {
+    build(
+        accounts::UpdateUniverseRecentWindow { universe: pda::universe(), planet_index: pda::planet_index(), creator: *manager },
+        instruction::UpdateRecentWindow { window },
+    )
+}
+
+pub fn update_hop_destination_mode(manager: &Pubkey, mode: u8) -> Instruction {
+    build(
+        accounts::UpdateUniverseHopDestinationMode { universe: pda::universe(), creator: *manager },
+        instruction::UpdateHopDestinationMode { mode },
+    )
+}
+
+/// Records the canonical address lookup table on the universe.
+pub fn update_lookup_table(manager: &Pubkey, table: &Pubkey) -> Instruction {
+    build(
+        accounts::UpdateUniverseLookupTable {
+            universe: pda::universe(),
+            lookup_table: *table,
+            creator: *manager,
+        },
+        instruction::UpdateLookupTable {},
+    )
+}
+
+///-------------------------------------------------------------------///
+/// MIGRATIONS
+///-------------------------------------------------------------------///
+pub fn migrate_universe(manager: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateUniverse { universe: pda::universe(), planet_index: pda::planet_index(), manager: *manager, system_program: system_program::ID },
+        instruction::MigrateUniverse {},
+    )
+}
+
+/// Moves the v3.0.0 planet `name` to the PDA of `id`.
+pub fn migrate_planet(manager: &Pubkey, name: &str, id: u16) -> Instruction {
+    build(
+        accounts::MigratePlanet {
+            legacy_planet: pda::legacy_planet(name),
+            planet: pda::planet(id),
+            planet_stats: pda::planet_stats(id),
+            planet_index: pda::planet_index(),
+            manager: *manager,
+            system_program: system_program::ID,
+        },
+        instruction::MigratePlanet { id },
+    )
+}
+
+/// `planet` is the migrated planet the deposit sits on, `stars` the stars
+/// holding it when its v3.0.0 star hop has not ended yet.
+pub fn migrate_deposit(manager: &Pubkey, deposit: &Pubkey, planet: u16, stars: u8) -> Instruction {
+    build(
+        accounts::MigrateDeposit { deposit: *deposit, planet: pda::planet(planet), manager: *manager, system_program: system_program::ID },
+        instruction::MigrateDeposit { stars },
+    )
+}
+
+pub fn migrate_star(manager: &Pubkey, star: &str) -> Instruction {
+    build(
+        accounts::MigrateStar { star: pda::star(star), manager: *manager, system_program: system_program::ID },
+        instruction::MigrateStar {},
+    )
+}
+
+pub fn create_galaxy_stats(manager: &Pubkey) -> Instruction {
+    build(
+        accounts::CreateGalaxyStats {
+            stats: pda::galaxy_stats(),
+            manager: *manager,
+            system_program: system_program::ID,
+        },
+        instruction::CreateGalaxyStats {},
+    )
+}
+
+/// Folds the stats of `planets` into the galaxy stats. Needs no signer, any fee payer can send it.
+pub fn aggregate_stats(planets: &[u16]) -> Instruction {
+    let mut ix = build(accounts::AggregateStats { stats: pda::galaxy_stats() }, instruction::AggregateStats {});
+    ix.accounts.extend(planets.iter().map(|id| AccountMeta::new(pda::planet_stats(*id), false)));
+    ix
+}
+
+///-------------------------------------------------------------------///
+/// DEPOSIT
+///-------------------------------------------------------------------///
+pub fn create_deposit(
+    creator: &Pubkey,
+    planet: u16,
+    destination: &Pubkey,
+    deposit_lamports: u64,
+    mode: u8,
+    delay: u32,
+    withdraw_at: i64,
+) -> Instruction {
+    build(
+        accounts::CreateDeposit {
+            deposit: pda::deposit(creator),
+            creator: *creator,
+            planet: pda::planet(planet),
+            destination: *destination,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            stats: pda::planet_stats(planet),
+        },
+        instruction::CreateDeposit { deposit_lamports, mode, delay, withdraw_at },
+    )
+}
+
+/// Deposit that stores `commitment` (`oridion::shared::get_destination_commitment`)
+/// instead of the destination.
+pub fn create_committed_deposit(
+    creator: &Pubkey,
+    planet: u16,
+    deposit_lamports: u64,
+    mode: u8,
+    delay: u32,
+    withdraw_at: i64,
+    commitment: [u8; 32],
+) -> Instruction {
+    build(
+        accounts::CreateCommittedDeposit {
+            deposit: pda::deposit(creator),
+            creator: *creator,
+            planet: pda::planet(planet),
+            system_program: system_program::ID,
+            stats: pda::planet_stats(planet),
+        },
+        instruction::CreateCommittedDeposit { deposit_lamports, mode, delay, withdraw_at, commitment },
+    )
+}
+
+/// Deposit that stores `note` (`oridion::shared::get_note_hash` of a secret, the
+/// destination and the relayer fee) instead of the destination. Whoever holds the secret withdraws to it
+/// with `withdraw_note`.
+pub fn create_note_deposit(
+    creator: &Pubkey,
+    planet: u16,
+    deposit_lamports: u64,
+    mode: u8,
+    delay: u32,
+    withdraw_at: i64,
+    note: [u8; 32],
+) -> Instruction {
+    build(
+        accounts::CreateCommittedDeposit {
+            deposit: pda::deposit(creator),
+            creator: *creator,
+            planet: pda::planet(planet),
+            system_program: system_program::ID,
+            stats: pda::planet_stats(planet),
+        },
+        instruction::CreateNoteDeposit { deposit_lamports, mode, delay, withdraw_at, note },
+    )
+}
+
+/// Single bucket deposit at `pda::bucket_deposit(creator, nonce)`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_bucket_deposit(
+    creator: &Pubkey,
+    planet: u16,
+    destination: &Pubkey,
+    nonce: u8,
+    deposit_lamports: u64,
+    mode: u8,
+    delay: u32,
+    withdraw_at: i64,
+) -> Instruction {
+    build(
+        accounts::CreateBucketDeposit {
+            deposit: pda::bucket_deposit(creator, nonce),
+            creator: *creator,
+            universe: pda::universe(),
+            planet: pda::planet(planet),
+            destination: *destination,
+            system_program: system_program::ID,
+            stats: pda::planet_stats(planet),
+        },
+        instruction::CreateBucketDeposit { _nonce: nonce, deposit_lamports, mode, delay, withdraw_at },
+    )
+}
+
+/// Bucket deposits adding up to `lamports`, meant to be sent in one transaction.
+/// Nonces start at `first_nonce`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_bucket_deposits(
+    creator: &Pubkey,
+    planet: u16,
+    destination: &Pubkey,
+    lamports: u64,
+    buckets: &[u64],
+    first_nonce: u8,
+    mode: u8,
+    delay: u32,
+    withdraw_at: i64,
+) -> Result<Vec<Instruction>, ClientError> {
+    let amounts = split_into_buckets(lamports, buckets).ok_or(ClientError::NotBucketable(lamports))?;
+    amounts.into_iter()
+        .enumerate()
+        .map(|(index, amount)| {
+            let nonce = u8::try_from(index).ok()
+                .and_then(|index| first_nonce.checked_add(index))
+                .ok_or(ClientError::NotBucketable(lamports))?;
+            Ok(create_bucket_deposit(creator, planet, destination, nonce, amount, mode, delay, withdraw_at))
+        })
+        .collect()
+}
+
+/// Largest buckets first. `None` when the amount is not an exact sum of buckets
+/// reachable that way (greedy is exact for decimal buckets like 0.1, 1 and 10 SOL).
+pub fn split_into_buckets(lamports: u64, buckets: &[u64]) -> Option<Vec<u64>> {
+    let mut sorted: Vec<u64> = buckets.iter().copied().filter(|bucket| *bucket > 0).collect();
+    sorted.sort_unstable_by(|a, b| b.cmp(a));
+    let mut remaining = lamports;
+    let mut amounts = Vec::new();
+    for bucket in sorted {
+        while remaining >= bucket {
+            amounts.push(bucket);
+            remaining -= bucket;
+        }
+    }
+    (remaining == 0 && !amounts.is_empty()).then_some(amounts)
+}
+
+/// Split the deposit's withdrawal over `splits`. `nonce` is set for bucket deposits.
+pub fn set_deposit_splits(creator: &Pubkey, nonce: Option<u8>, mode: u8, splits: &[DestinationSplit]) -> Instruction {
+    let deposit = match nonce {
+        Some(nonce) => pda::bucket_deposit(creator, nonce),
+        None => pda::deposit(creator),
+    };
+    build(
+        accounts::SetDepositSplits { deposit, creator: *creator },
+        instruction::SetDepositSplits { nonce, mode, splits: splits.to_vec() },
+    )
+}
+
+/// Staggers a deposit's withdrawal over `tranches` tranches `spacing_min..=spacing_max`
+/// seconds apart. `nonce` is only set for bucket deposits.
+pub fn set_withdraw_schedule(creator: &Pubkey, nonce: Option<u8>, tranches: u8, spacing_min: u32, spacing_max: u32) -> Instruction {
+    let deposit = match nonce {
+        Some(nonce) => pda::bucket_deposit(creator, nonce),
+        None => pda::deposit(creator),
+    };
+    build(
+        accounts::SetWithdrawSchedule { deposit, creator: *creator },
+        instruction::SetWithdrawSchedule { nonce, tranches, spacing_min, spacing_max },
+    )
+}
+
+/// Route the deposit must follow before it can be withdrawn. `nonce` is only set for bucket deposits.
+pub fn set_route_policy(creator: &Pubkey, nonce: Option<u8>, policy: RoutePolicy) -> Instruction {
+    let deposit = match nonce {
+        Some(nonce) => pda::bucket_deposit(creator, nonce),
+        None => pda::deposit(creator),
+    };
+    build(
+        accounts::SetRoutePolicy { deposit, planet_index: pda::planet_index(), creator: *creator },
+        instruction::SetRoutePolicy { nonce, policy },
+    )
+}
+
+/// Appends the split destinations after the first as remaining accounts of a
+/// `withdraw` or `keeper_withdraw` instruction.
+pub fn add_split_destinations(ix: &mut Instruction, deposit: &Deposit) {
+    ix.accounts.extend(deposit.splits.iter().skip(1).map(|split| AccountMeta::new(split.destination, false)));
+}
+
+pub fn withdraw(manager: &Pubkey, deposit: &Pubkey, from: u16, destination: &Pubkey) -> Instruction {
+    build(
+        accounts::WithdrawAccounts {
+            deposit: *deposit,
+            from_planet: pda::planet(from),
+            destination: *destination,
+            manager: *manager,
+            planet_index: pda::planet_index(),
+            stats: pda::planet_stats(from),
+        },
+        instruction::Withdraw {},
+    )
+}
+
+/// Reveals a committed deposit's destination and salt and withdraws to it. `authority`
+/// is the manager or the creator; `nonce` is only set for a creator's bucket deposit.
+pub fn withdraw_committed(
+    authority: &Pubkey,
+    deposit: &Pubkey,
+    from: u16,
+    destination: &Pubkey,
+    nonce: Option<u8>,
+    salt: [u8; 32],
+) -> Instruction {
+    build(
+        accounts::WithdrawCommitted {
+            deposit: *deposit,
+            from_planet: pda::planet(from),
+            destination: *destination,
+            manager: MANAGER_PUBKEY,
+            authority: *authority,
+            planet_index: pda::planet_index(),
+            stats: pda::planet_stats(from),
+        },
+        instruction::WithdrawCommitted { nonce, salt },
+    )
+}
+
+/// Withdraws a note deposit to the `destination` its note commits to by presenting its
+/// secret and the `relay_fee` it commits to. No relayer is paid.
+pub fn withdraw_note(deposit: &Pubkey, from: u16, destination: &Pubkey, secret: [u8; 32], relay_fee: u64) -> Instruction {
+    build(
+        accounts::WithdrawNote {
+            deposit: *deposit,
+            from_planet: pda::planet(from),
+            destination: *destination,
+            manager: MANAGER_PUBKEY,
+            planet_index: pda::planet_index(),
+            stats: pda::planet_stats(from),
+        },
+        instruction::WithdrawNote { secret, relay_fee },
+    )
+}
+
+///-------------------------------------------------------------------///
+/// RELAYERS
+///-------------------------------------------------------------------///
+pub fn create_relayer_registry(manager: &Pubkey, max_fee: u64) -> Instruction {
+    build(
+        accounts::CreateRelayerRegistry {
+            registry: pda::relayer_registry(),
+            manager: *manager,
+            system_program: system_program::ID,
+        },
+        instruction::CreateRelayerRegistry { max_fee },
+    )
+}
+
+pub fn update_relayer_fee(manager: &Pubkey, max_fee: u64) -> Instruction {
+    build(
+        accounts::UpdateRelayerRegistry { registry: pda::relayer_registry(), manager: *manager },
+        instruction::UpdateRelayerFee { max_fee },
+    )
+}
+
+pub fn add_relayer(manager: &Pubkey, relayer: &Pubkey) -> Instruction {
+    build(
+        accounts::UpdateRelayerRegistry { registry: pda::relayer_registry(), manager: *manager },
+        instruction::AddRelayer { relayer: *relayer },
+    )
+}
+
+pub fn remove_relayer(manager: &Pubkey, relayer: &Pubkey) -> Instruction {
+    build(
+        accounts::UpdateRelayerRegistry { registry: pda::relayer_registry(), manager: *manager },
+        instruction::RemoveRelayer { relayer: *relayer },
+    )
+}
+
+/// Relayer-submitted withdraw of a committed deposit (`secret` is the salt) or a note
+/// deposit (`secret` is the preimage). The relayer keeps `fee` out of the withdrawal,
+/// for a note exactly the fee it commits to.
+pub fn relay_withdraw(relayer: &Pubkey, deposit: &Pubkey, from: u16, destination: &Pubkey, secret: [u8; 32], fee: u64) -> Instruction {
+    build(
+        accounts::RelayWithdraw {
+            deposit: *deposit,
+            from_planet: pda::planet(from),
+            destination: *destination,
+            registry: pda::relayer_registry(),
+            manager: MANAGER_PUBKEY,
+            relayer: *relayer,
+            planet_index: pda::planet_index(),
+            stats: pda::planet_stats(from),
+        },
+        instruction::RelayWithdraw { secret, fee },
+    )
+}
+
+///-------------------------------------------------------------------///
+/// HOPS
+///-------------------------------------------------------------------///
+pub fn planet_hop(manager: &Pubkey, deposit: &Pubkey, from: u16, to: u16) -> Instruction {
+    build(
+        accounts::PlanetHop {
+            deposit: *deposit,
+            to_planet: pda::planet(to),
+            from_planet: pda::planet(from),
+            manager: *manager,
+            universe: pda::universe(),
+            planet_index: pda::planet_index(),
+            stats: pda::planet_stats(to),
+        },
+        instruction::PlanetHop {},
+    )
+}
+
+/// Planet hops for many SOL deposits, one (deposit, from, to) per hop.
+pub fn batch_hop(manager: &Pubkey, hops: &[(Pubkey, u16, u16)]) -> Instruction {
+    let mut ix = build(
+        accounts::BatchHop { manager: *manager, universe: pda::universe(), planet_index: pda::planet_index() },
+        instruction::BatchHop {},
+    );
+    for (deposit, from, to) in hops {
+        ix.accounts.extend([
+            AccountMeta::new(*deposit, false),
+            AccountMeta::new(pda::planet(*from), false),
+            AccountMeta::new(pda::planet(*to), false),
+            AccountMeta::new(pda::planet_stats(*to), false),
+        ]);
+    }
+    ix
+}
+
+pub fn star_hop_two_start(manager: &Pubkey, deposit: &Pubkey, from: u16, stars: [&str; 2]) -> Instruction {
+    build(
+        accounts::StarHopTwoStart {
+            deposit: *deposit,
+            from_planet: pda::planet(from),
+            star_one: pda::star(stars[0]),
+            star_two: pda::star(stars[1]),
+            manager: *manager,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::StarHopTwoStart {
+            star_one: stars[0].to_string(),
+            star_two: stars[1].to_string(),
+        },
+    )
+}
+
+pub fn star_hop_two_end(manager: &Pubkey, deposit: &Pubkey, to: u16, stars: [&str; 2]) -> Instruction {
+    build(
+        accounts::StarHopTwoEnd {
+            deposit: *deposit,
+            to_planet: pda::planet(to),
+            star_one: pda::star(stars[0]),
+            star_two: pda::star(stars[1]),
+            manager: *manager,
+            universe: pda::universe(),
+            planet_index: pda::planet_index(),
+            stats: pda::planet_stats(to),
+        },
+        instruction::StarHopTwoEnd {},
+    )
+}
+
+pub fn star_hop_three_start(manager: &Pubkey, deposit: &Pubkey, from: u16, stars: [&str; 3]) -> Instruction {
+    build(
+        accounts::StarHopThreeStart {
+            deposit: *deposit,
+            from_planet: pda::planet(from),
+            star_one: pda::star(stars[0]),
+            star_two: pda::star(stars[1]),
+            star_three: pda::star(stars[2]),
+            manager: *manager,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::StarHopThreeStart {
+            star_one: stars[0].to_string(),
+            star_two: stars[1].to_string(),
+            star_three: stars[2].to_string(),
+        },
+    )
+}
+
+pub fn star_hop_three_end(manager: &Pubkey, deposit: &Pubkey, to: u16, stars: [&str; 3]) -> Instruction {
+    build(
+        accounts::StarHopThreeEnd {
+            deposit: *deposit,
+            to_planet: pda::planet(to),
+            star_one: pda::star(stars[0]),
+            star_two: pda::star(stars[1]),
+            star_three: pda::star(stars[2]),
+            manager: *manager,
+            universe: pda::universe(),
+            planet_index: pda::planet_index(),
+            stats: pda::planet_stats(to),
+        },
+        instruction::StarHopThreeEnd {},
+    )
+}
+
+///-------------------------------------------------------------------///
+/// KEEPER
+///-------------------------------------------------------------------///
+pub fn keeper_hop(keeper: &Pubkey, deposit: &Pubkey, from: u16, to: u16) -> Instruction {
+    build(
+        accounts::KeeperHop {
+            deposit: *deposit,
+            to_planet: pda::planet(to),
+            from_planet: pda::planet(from),
+            universe: pda::universe(),
+            planet_index: pda::planet_index(),
+            manager: MANAGER_PUBKEY,
+            keeper: *keeper,
+            stats: pda::planet_stats(to),
+        },
+        instruction::KeeperHop {},
+    )
+}
+
+pub fn keeper_withdraw(keeper: &Pubkey, deposit: &Pubkey, from: u16, destination: &Pubkey) -> Instruction {
+    build(
+        accounts::KeeperWithdraw {
+            deposit: *deposit,
+            from_planet: pda::planet(from),
+            destination: *destination,
+            universe: pda::universe(),
+            manager: MANAGER_PUBKEY,
+            keeper: *keeper,
+            planet_index: pda::planet_index(),
+            stats: pda::planet_stats(from),
+        },
+        instruction::KeeperWithdraw {},
+    )
+}
+
+///-------------------------------------------------------------------///
+/// TOKEN
+///-------------------------------------------------------------------///
+/// Hop and withdraw fees of a MintConfig in mint units, `None` charges none.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MintFees {
+    pub hpfe: Option<u64>,
+    pub wfe: Option<u64>,
+}
+
+pub fn create_mint_config(manager: &Pubkey, mint: &Pubkey, min: u64, max: u64, fees: MintFees) -> Instruction {
+    build(
+        accounts::CreateMintConfig {
+            mint_config: pda::mint_config(mint),
+            mint: *mint,
+            manager: *manager,
+            system_program: system_program::ID,
+        },
+        instruction::CreateMintConfig { min, max, hpfe: fees.hpfe, wfe: fees.wfe },
+    )
+}
+
+pub fn update_mint_config(manager: &Pubkey, mint: &Pubkey, min: u64, max: u64, fees: MintFees) -> Instruction {
+    build(
+        accounts::UpdateMintConfig {
+            mint_config: pda::mint_config(mint),
+            manager: *manager,
+        },
+        instruction::UpdateMintConfig { min, max, hpfe: fees.hpfe, wfe: fees.wfe },
+    )
+}
+
+pub fn delete_mint_config(manager: &Pubkey, mint: &Pubkey) -> Instruction {
+    build(
+        accounts::DeleteMintConfig {
+            mint_config: pda::mint_config(mint),
+            manager: *manager,
+        },
+        instruction::DeleteMintConfig {},
+    )
+}
+
+pub fn create_planet_token_account(manager: &Pubkey, planet: u16, mint: &Pubkey, token_program: &Pubkey) -> Instruction {
+    let planet = pda::planet(planet);
+    build(
+        accounts::CreatePlanetTokenAccount {
+            planet,
+            mint: *mint,
+            planet_token_account: pda::token_account(&planet, mint, token_program),
+            manager: *manager,
+            token_program: *token_program,
+            associated_token_program: ASSOCIATED_TOKEN_PROGRAM_ID,
+            system_program: system_program::ID,
+        },
+        instruction::CreatePlanetTokenAccount {},
+    )
+}
+
+/// Deposit from the creator's associated token account.
+#[allow(clippy::too_many_arguments)]
+pub fn create_token_deposit(
+    creator: &Pubkey,
+    planet: u16,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    destination: &Pubkey,
+    amount: u64,
+    mode: u8,
+    delay: u32,
+    withdraw_at: i64,
+) -> Instruction {
+    let stats = pda::planet_stats(planet);
+    let planet = pda::planet(planet);
+    build(
+        accounts::CreateTokenDeposit {
+            deposit: pda::deposit(creator),
+            creator: *creator,
+            creator_token_account: pda::token_account(creator, mint, token_program),
+            planet,
+            planet_token_account: pda::token_account(&planet, mint, token_program),
+            mint: *mint,
+            mint_config: pda::mint_config(mint),
+            destination: *destination,
+            token_program: *token_program,
+            system_program: system_program::ID,
+            stats,
+        },
+        instruction::CreateTokenDeposit { amount, mode, delay, withdraw_at },
+    )
+}
+
+pub fn token_planet_hop(manager: &Pubkey, deposit: &Pubkey, mint: &Pubkey, token_program: &Pubkey, from: u16, to: u16) -> Instruction {
+    let stats = pda::planet_stats(to);
+    let (from, to) = (pda::planet(from), pda::planet(to));
+    build(
+        accounts::TokenPlanetHop {
+            deposit: *deposit,
+            to_planet: to,
+            to_token_account: pda::token_account(&to, mint, token_program),
+            from_planet: from,
+            from_token_account: pda::token_account(&from, mint, token_program),
+            mint: *mint,
+            mint_config: pda::mint_config(mint),
+            manager: *manager,
+            manager_token_account: pda::token_account(manager, mint, token_program),
+            token_program: *token_program,
+            associated_token_program: ASSOCIATED_TOKEN_PROGRAM_ID,
+            system_program: system_program::ID,
+            universe: pda::universe(),
+            stats,
+        },
+        instruction::TokenPlanetHop {},
+    )
+}
+
+pub fn token_withdraw(manager: &Pubkey, deposit: &Pubkey, mint: &Pubkey, token_program: &Pubkey, from: u16, destination: &Pubkey) -> Instruction {
+    let stats = pda::planet_stats(from);
+    let from = pda::planet(from);
+    build(
+        accounts::TokenWithdraw {
+            deposit: *deposit,
+            from_planet: from,
+            from_token_account: pda::token_account(&from, mint, token_program),
+            destination: *destination,
+            destination_token_account: pda::token_account(destination, mint, token_program),
+            mint: *mint,
+            mint_config: pda::mint_config(mint),
+            manager: *manager,
+            manager_token_account: pda::token_account(manager, mint, token_program),
+            token_program: *token_program,
+            associated_token_program: ASSOCIATED_TOKEN_PROGRAM_ID,
+            system_program: system_program::ID,
+            stats,
+        },
+        instruction::TokenWithdraw {},
+    )
+}
+
+pub fn token_star_hop_two_start(manager: &Pubkey, deposit: &Pubkey, mint: &Pubkey, token_program: &Pubkey, from: u16, stars: [&str; 2]) -> Instruction {
+    let from = pda::planet(from);
+    let (star_one, star_two) = (pda::star(stars[0]), pda::star(stars[1]));
+    build(
+        accounts::TokenStarHopTwoStart {
+            deposit: *deposit,
+            from_planet: from,
+            from_token_account: pda::token_account(&from, mint, token_program),
+            star_one,
+            star_one_token_account: pda::token_account(&star_one, mint, token_program),
+            star_two,
+            star_two_token_account: pda::token_account(&star_two, mint, token_program),
+            mint: *mint,
+            manager: *manager,
+            token_program: *token_program,
+            associated_token_program: ASSOCIATED_TOKEN_PROGRAM_ID,
+            system_program: system_program::ID,
+        },
+        instruction::TokenStarHopTwoStart {
+            star_one: stars[0].to_string(),
+            star_two: stars[1].to_string(),
+        },
+    )
+}
+
+pub fn token_star_hop_two_end(manager: &Pubkey, deposit: &Pubkey, mint: &Pubkey, token_program: &Pubkey, to: u16, stars: [&str; 2]) -> Instruction {
+    let stats = pda::planet_stats(to);
+    let to = pda::planet(to);
+    let (star_one, star_two) = (pda::star(stars[0]), pda::star(stars[1]));
+    build(
+        accounts::TokenStarHopTwoEnd {
+            deposit: *deposit,
+            to_planet: to,
+            to_token_account: pda::token_account(&to, mint, token_program),
+            star_one,
+            star_one_token_account: pda::token_account(&star_one, mint, token_program),
+            star_two,
+            star_two_token_account: pda::token_account(&star_two, mint, token_program),
+            mint: *mint,
+            mint_config: pda::mint_config(mint),
+            manager: *manager,
+            token_program: *token_program,
+            universe: pda::universe(),
+            stats,
+        },
+        instruction::TokenStarHopTwoEnd {
+            star_one: stars[0].to_string(),
+            star_two: stars[1].to_string(),
+        },
+    )
+}
+
+pub fn token_star_hop_three_start(manager: &Pubkey, deposit: &Pubkey, mint: &Pubkey, token_program: &Pubkey, from: u16, stars: [&str; 3]) -> Instruction {
+    let from = pda::planet(from);
+    let (star_one, star_two, star_three) = (pda::star(stars[0]), pda::star(stars[1]), pda::star(stars[2]));
+    build(
+        accounts::TokenStarHopThreeStart {
+            deposit: *deposit,
+            from_planet: from,
+            from_token_account: pda::token_account(&from, mint, token_program),
+            star_one,
+            star_one_token_account: pda::token_account(&star_one, mint, token_program),
+            star_two,
+            star_two_token_account: pda::token_account(&star_two, mint, token_program),
+            star_three,
+            star_three_token_account: pda::token_account(&star_three, mint, token_program),
+            mint: *mint,
+            manager: *manager,
+            token_program: *token_program,
+            associated_token_program: ASSOCIATED_TOKEN_PROGRAM_ID,
+            system_program: system_program::ID,
+        },
+        instruction::TokenStarHopThreeStart {
+            star_one: stars[0].to_string(),
+            star_two: stars[1].to_string(),
+            star_three: stars[2].to_string(),
+        },
+    )
+}
+
+pub fn token_star_hop_three_end(manager: &Pubkey, deposit: &Pubkey, mint: &Pubkey, token_program: &Pubkey, to: u16, stars: [&str; 3]) -> Instruction {
+    let stats = pda::planet_stats(to);
+    let to = pda::planet(to);
+    let (star_one, star_two, star_three) = (pda::star(stars[0]), pda::star(stars[1]), pda::star(stars[2]));
+    build(
+        accounts::TokenStarHopThreeEnd {
+            deposit: *deposit,
+            to_planet: to,
+            to_token_account: pda::token_account(&to, mint, token_program),
+            star_one,
+            star_one_token_account: pda::token_account(&star_one, mint, token_program),
+            star_two,
+            star_two_token_account: pda::token_account(&star_two, mint, token_program),
+            star_three,
+            star_three_token_account: pda::token_account(&star_three, mint, token_program),
+            mint: *mint,
+            mint_config: pda::mint_config(mint),
+            manager: *manager,
+            token_program: *token_program,
+            universe: pda::universe(),
+            stats,
+        },
+        instruction::TokenStarHopThreeEnd {
+            star_one: stars[0].to_string(),
+            star_two: stars[1].to_string(),
+            star_three: stars[2].to_string(),
+        },
+    )
+}
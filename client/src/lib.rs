@@ -0,0 +1,14 @@
+//! Off-chain side of Oridion: PDA helpers, instruction builders and the
+//! crank that drives deposits through the galaxy.
+pub mod cluster;
+pub mod crank;
+pub mod error;
+pub mod instructions;
+pub mod lookup_table;
+pub mod pda;
+#[cfg(feature = "svm")]
+pub mod svm;
+
+pub use cluster::{Cluster, RpcCluster};
+pub use crank::{Action, Crank, CrankConfig};
+pub use error::ClientError;
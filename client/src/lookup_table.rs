@@ -0,0 +1,79 @@
+//! Address lookup table of the accounts most instructions share, so star hops
+//! fit a v0 transaction. The manager records its address on the universe and
+//! every client builds against that one.
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::address_lookup_table::instruction::{create_lookup_table, extend_lookup_table};
+use anchor_lang::solana_program::address_lookup_table::AddressLookupTableAccount;
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::message::{v0, VersionedMessage};
+use anchor_lang::solana_program::system_program;
+use oridion::accounts_universe::Universe;
+use crate::{instructions, pda, Cluster, ClientError};
+
+/// Addresses added per extend instruction, so each fits a transaction.
+pub const EXTEND_CHUNK: usize = 20;
+
+/// Universe, planet index, system program, then every planet with its stats.
+pub fn galaxy_addresses(planets: &[u16]) -> Vec<Pubkey> {
+    let mut addresses = vec![pda::universe(), pda::planet_index(), system_program::ID];
+    addresses.extend(planets.iter().flat_map(|id| [pda::planet(*id), pda::planet_stats(*id)]));
+    addresses
+}
+
+/// New table owned by `authority` and its address.
+pub fn create(authority: &Pubkey, payer: &Pubkey, recent_slot: u64) -> (Instruction, Pubkey) {
+    create_lookup_table(*authority, *payer, recent_slot)
+}
+
+/// Appends `addresses` to the table, `EXTEND_CHUNK` per instruction.
+pub fn extend(table: &Pubkey, authority: &Pubkey, payer: &Pubkey, addresses: &[Pubkey]) -> Vec<Instruction> {
+    addresses.chunks(EXTEND_CHUNK)
+        .map(|chunk| extend_lookup_table(*table, *authority, Some(*payer), chunk.to_vec()))
+        .collect()
+}
+
+/// Galaxy addresses the table does not hold yet.
+pub fn missing(table: &AddressLookupTableAccount, planets: &[u16]) -> Vec<Pubkey> {
+    galaxy_addresses(planets).into_iter().filter(|address| !table.addresses.contains(address)).collect()
+}
+
+pub fn v0_message(payer: &Pubkey, instructions: &[Instruction], tables: &[AddressLookupTableAccount], blockhash: Hash) -> Result<VersionedMessage, ClientError> {
+    let message = v0::Message::try_compile(payer, instructions, tables, blockhash)
+        .map_err(|err| ClientError::Transaction(err.to_string()))?;
+    Ok(VersionedMessage::V0(message))
+}
+
+/// Table recorded on the universe, `None` until the manager sets one.
+pub fn universe_table<C: Cluster>(cluster: &C) -> Result<Option<AddressLookupTableAccount>, ClientError> {
+    let universe: Universe = cluster.account(&pda::universe())?
+        .ok_or(ClientError::AccountNotFound(pda::universe()))?;
+    recorded_table(cluster, &universe)
+}
+
+/// Table recorded on an already loaded universe.
+pub fn recorded_table<C: Cluster>(cluster: &C, universe: &Universe) -> Result<Option<AddressLookupTableAccount>, ClientError> {
+    if universe.lut == Pubkey::default() {
+        return Ok(None);
+    }
+    cluster.lookup_table(&universe.lut)
+}
+
+/// Creates the universe's table if it has none and adds the galaxy addresses
+/// it is missing. Sent by the manager, who also becomes the table authority.
+/// New addresses can be used from the next slot.
+pub fn sync<C: Cluster>(cluster: &mut C, planets: &[u16]) -> Result<AddressLookupTableAccount, ClientError> {
+    let manager = cluster.payer();
+    let table = match universe_table(cluster)? {
+        Some(table) => table,
+        None => {
+            let (create, address) = create(&manager, &manager, cluster.slot()?);
+            cluster.send(&[create, instructions::update_lookup_table(&manager, &address)])?;
+            cluster.lookup_table(&address)?.ok_or(ClientError::AccountNotFound(address))?
+        }
+    };
+    for ix in extend(&table.key, &manager, &manager, &missing(&table, planets)) {
+        cluster.send(&[ix])?;
+    }
+    cluster.lookup_table(&table.key)?.ok_or(ClientError::AccountNotFound(table.key))
+}
@@ -0,0 +1,80 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use oridion::variables::*;
+
+pub fn universe() -> Pubkey {
+    Pubkey::find_program_address(&[UNIVERSE_PDA_SEED], &oridion::ID).0
+}
+
+pub fn planet_index() -> Pubkey {
+    Pubkey::find_program_address(&[PLANET_INDEX_SEED], &oridion::ID).0
+}
+
+pub fn planet(id: u16) -> Pubkey {
+    Pubkey::find_program_address(&[
+        PLANET_PDA_SEED_PRE,
+        &id.to_le_bytes(),
+        PLANET_PDA_SEED_POST
+    ], &oridion::ID).0
+}
+
+/// v3.0.0 planets were keyed by name.
+pub fn legacy_planet(name: &str) -> Pubkey {
+    Pubkey::find_program_address(&[
+        PLANET_PDA_SEED_PRE,
+        name.as_bytes(),
+        PLANET_PDA_SEED_POST
+    ], &oridion::ID).0
+}
+
+pub fn deposit(creator: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[
+        DEPOSIT_PDA_SEED_PRE,
+        creator.as_ref(),
+        DEPOSIT_PDA_SEED_POST
+    ], &oridion::ID).0
+}
+
+/// Bucket deposits add a nonce so one creator can hold several.
+pub fn bucket_deposit(creator: &Pubkey, nonce: u8) -> Pubkey {
+    Pubkey::find_program_address(&[
+        DEPOSIT_PDA_SEED_PRE,
+        creator.as_ref(),
+        &[nonce],
+        DEPOSIT_PDA_SEED_POST
+    ], &oridion::ID).0
+}
+
+pub fn star(id: &str) -> Pubkey {
+    Pubkey::find_program_address(&[
+        STAR_SEED_PRE,
+        id.as_bytes(),
+        STAR_SEED_POST
+    ], &oridion::ID).0
+}
+
+pub fn mint_config(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[
+        MINT_CONFIG_SEED_PRE,
+        mint.as_ref(),
+        MINT_CONFIG_SEED_POST
+    ], &oridion::ID).0
+}
+
+pub fn galaxy_stats() -> Pubkey {
+    Pubkey::find_program_address(&[GALAXY_STATS_SEED], &oridion::ID).0
+}
+
+pub fn planet_stats(id: u16) -> Pubkey {
+    Pubkey::find_program_address(&[PLANET_STATS_SEED, &id.to_le_bytes()], &oridion::ID).0
+}
+
+pub fn relayer_registry() -> Pubkey {
+    Pubkey::find_program_address(&[RELAYER_REGISTRY_SEED], &oridion::ID).0
+}
+
+/// Associated token account of a wallet, planet or star. `token_program` is the
+/// mint's owner, SPL Token or Token-2022.
+pub fn token_account(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, token_program)
+}
@@ -0,0 +1,330 @@
+//! LiteSVM backed cluster for local tests. Signature verification is off so
+//! transactions can be sent as `MANAGER_PUBKEY` or any creator without keys.
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::address_lookup_table::{self, AddressLookupTableAccount};
+use anchor_lang::solana_program::clock::Clock;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token_2022::spl_token_2022::extension::default_account_state::DefaultAccountState;
+use anchor_spl::token_2022::spl_token_2022::extension::mint_close_authority::MintCloseAuthority;
+use anchor_spl::token_2022::spl_token_2022::extension::non_transferable::{NonTransferable, NonTransferableAccount};
+use anchor_spl::token_2022::spl_token_2022::extension::permanent_delegate::PermanentDelegate;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::{TransferFee, TransferFeeAmount, TransferFeeConfig};
+use anchor_spl::token_2022::spl_token_2022::extension::immutable_owner::ImmutableOwner;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    BaseState, BaseStateWithExtensions, BaseStateWithExtensionsMut, ExtensionType, StateWithExtensions, StateWithExtensionsMut,
+};
+use anchor_spl::token_2022::spl_token_2022::state::{Account as TokenAccount, AccountState, Mint};
+use litesvm::types::TransactionMetadata;
+use litesvm::LiteSVM;
+use oridion::variables::MANAGER_PUBKEY;
+use solana_sdk::account::Account;
+use solana_sdk::message::Message;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+use crate::cluster::{deserialize, deserialize_lookup_table};
+use crate::{lookup_table, pda, Cluster, ClientError};
+
+/// Environment variable overriding where the compiled program is loaded from.
+pub const PROGRAM_PATH_ENV: &str = "ORIDION_PROGRAM_SO";
+
+/// Token-2022 mint extensions `create_token_2022_mint` can write.
+pub enum MintExtension {
+    TransferFee { basis_points: u16, maximum_fee: u64 },
+    PermanentDelegate(Pubkey),
+    NonTransferable,
+    MintCloseAuthority(Pubkey),
+    /// New token accounts start frozen.
+    DefaultAccountState,
+}
+
+impl MintExtension {
+    fn extension_type(&self) -> ExtensionType {
+        match self {
+            MintExtension::TransferFee { .. } => ExtensionType::TransferFeeConfig,
+            MintExtension::PermanentDelegate(_) => ExtensionType::PermanentDelegate,
+            MintExtension::NonTransferable => ExtensionType::NonTransferable,
+            MintExtension::MintCloseAuthority(_) => ExtensionType::MintCloseAuthority,
+            MintExtension::DefaultAccountState => ExtensionType::DefaultAccountState,
+        }
+    }
+}
+
+pub struct SvmCluster {
+    svm: LiteSVM,
+    payer: Pubkey,
+    /// Every account seen in a sent transaction. LiteSVM cannot list program
+    /// accounts, so `program_accounts` searches these instead.
+    touched: BTreeSet<Pubkey>,
+}
+
+impl SvmCluster {
+    /// Path of the `anchor build` output, unless overridden.
+    pub fn program_path() -> PathBuf {
+        std::env::var(PROGRAM_PATH_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/../target/deploy/oridion.so")))
+    }
+
+    /// Cluster with the program deployed, or `None` when it has not been built.
+    pub fn load() -> Option<Self> {
+        std::fs::read(Self::program_path()).ok().map(|program| Self::new(&program))
+    }
+
+    pub fn new(program: &[u8]) -> Self {
+        let mut svm = LiteSVM::new().with_sigverify(false);
+        svm.add_program(oridion::ID, program);
+        let mut cluster = SvmCluster { svm, payer: MANAGER_PUBKEY, touched: BTreeSet::new() };
+        cluster.airdrop(&MANAGER_PUBKEY, 100_000_000_000);
+        cluster
+    }
+
+    /// Sign `send` as someone other than the manager, e.g. a keeper.
+    pub fn set_payer(&mut self, payer: Pubkey) {
+        self.payer = payer;
+    }
+
+    pub fn svm(&mut self) -> &mut LiteSVM {
+        &mut self.svm
+    }
+
+    pub fn airdrop(&mut self, to: &Pubkey, lamports: u64) {
+        self.svm.airdrop(to, lamports).expect("airdrop");
+        self.touched.insert(*to);
+    }
+
+    pub fn balance(&self, address: &Pubkey) -> u64 {
+        self.svm.get_balance(address).unwrap_or(0)
+    }
+
+    /// Data size of an account, `None` if it does not exist.
+    pub fn data_len(&self, address: &Pubkey) -> Option<usize> {
+        self.svm.get_account(address).map(|account| account.data.len())
+    }
+
+    /// Write an initialized SPL token mint without a mint authority.
+    pub fn create_mint(&mut self, mint: &Pubkey, decimals: u8) {
+        self.set_token_state(mint, &spl_token::ID, &[], |state: &mut StateWithExtensionsMut<Mint>| {
+            state.base = Mint { supply: u64::MAX, decimals, is_initialized: true, ..Mint::default() };
+        });
+    }
+
+    /// Write an initialized Token-2022 mint without a mint authority.
+    pub fn create_token_2022_mint(&mut self, mint: &Pubkey, decimals: u8, extensions: &[MintExtension]) {
+        let types: Vec<ExtensionType> = extensions.iter().map(MintExtension::extension_type).collect();
+        self.set_token_state(mint, &spl_token_2022::ID, &types, |state: &mut StateWithExtensionsMut<Mint>| {
+            for extension in extensions {
+                match extension {
+                    MintExtension::TransferFee { basis_points, maximum_fee } => {
+                        let fee = TransferFee {
+                            epoch: 0.into(),
+                            maximum_fee: (*maximum_fee).into(),
+                            transfer_fee_basis_points: (*basis_points).into(),
+                        };
+                        let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+                        config.older_transfer_fee = fee;
+                        config.newer_transfer_fee = fee;
+                    }
+                    MintExtension::PermanentDelegate(delegate) => {
+                        state.init_extension::<PermanentDelegate>(true).unwrap().delegate = Some(*delegate).try_into().unwrap();
+                    }
+                    MintExtension::NonTransferable => {
+                        state.init_extension::<NonTransferable>(true).unwrap();
+                    }
+                    MintExtension::MintCloseAuthority(authority) => {
+                        state.init_extension::<MintCloseAuthority>(true).unwrap().close_authority = Some(*authority).try_into().unwrap();
+                    }
+                    MintExtension::DefaultAccountState => {
+                        state.init_extension::<DefaultAccountState>(true).unwrap().state = AccountState::Frozen as u8;
+                    }
+                }
+            }
+            state.base = Mint { supply: u64::MAX, decimals, is_initialized: true, ..Mint::default() };
+        });
+    }
+
+    /// Token program owning `mint`.
+    pub fn token_program(&self, mint: &Pubkey) -> Pubkey {
+        self.svm.get_account(mint).expect("mint").owner
+    }
+
+    /// Write `owner`'s associated token account holding `amount` tokens, with the
+    /// account extensions a Token-2022 mint requires.
+    pub fn mint_to(&mut self, owner: &Pubkey, mint: &Pubkey, amount: u64) {
+        let mint_account = self.svm.get_account(mint).expect("mint");
+        let program = mint_account.owner;
+        let mut types = Vec::new();
+        if program == spl_token_2022::ID {
+            let mint_types = StateWithExtensions::<Mint>::unpack(&mint_account.data).unwrap().get_extension_types().unwrap();
+            types = ExtensionType::get_required_init_account_extensions(&mint_types);
+            if !types.contains(&ExtensionType::ImmutableOwner) {
+                types.push(ExtensionType::ImmutableOwner);
+            }
+        }
+        let address = pda::token_account(owner, mint, &program);
+        self.set_token_state(&address, &program, &types, |state: &mut StateWithExtensionsMut<TokenAccount>| {
+            for extension in &types {
+                match extension {
+                    ExtensionType::TransferFeeAmount => { state.init_extension::<TransferFeeAmount>(true).unwrap(); }
+                    ExtensionType::NonTransferableAccount => { state.init_extension::<NonTransferableAccount>(true).unwrap(); }
+                    ExtensionType::ImmutableOwner => { state.init_extension::<ImmutableOwner>(true).unwrap(); }
+                    _ => {}
+                }
+            }
+            state.base = TokenAccount {
+                mint: *mint,
+                owner: *owner,
+                amount,
+                state: AccountState::Initialized,
+                ..TokenAccount::default()
+            };
+        });
+    }
+
+    /// Balance of `owner`'s associated token account, 0 if it does not exist.
+    pub fn token_balance(&self, owner: &Pubkey, mint: &Pubkey) -> u64 {
+        let address = pda::token_account(owner, mint, &self.token_program(mint));
+        self.svm.get_account(&address)
+            .and_then(|account| StateWithExtensions::<TokenAccount>::unpack(&account.data).map(|state| state.base).ok())
+            .map_or(0, |account| account.amount)
+    }
+
+    /// Fees withheld in `owner`'s associated token account of a transfer-fee mint.
+    pub fn withheld_fees(&self, owner: &Pubkey, mint: &Pubkey) -> u64 {
+        let address = pda::token_account(owner, mint, &self.token_program(mint));
+        self.svm.get_account(&address)
+            .and_then(|account| {
+                let state = StateWithExtensions::<TokenAccount>::unpack(&account.data).ok()?;
+                state.get_extension::<TransferFeeAmount>().ok().map(|fees| u64::from(fees.withheld_amount))
+            })
+            .unwrap_or(0)
+    }
+
+    /// Write a mint or token account with room for `extensions`; `init` fills it in.
+    fn set_token_state<S, F>(&mut self, address: &Pubkey, program: &Pubkey, extensions: &[ExtensionType], init: F)
+    where
+        S: BaseState + Pack,
+        F: FnOnce(&mut StateWithExtensionsMut<S>),
+    {
+        let len = if extensions.is_empty() { S::LEN } else { ExtensionType::try_calculate_account_len::<S>(extensions).unwrap() };
+        let mut data = vec![0; len];
+        let mut state = StateWithExtensionsMut::<S>::unpack_uninitialized(&mut data).unwrap();
+        init(&mut state);
+        state.pack_base();
+        if !extensions.is_empty() {
+            state.init_account_type().unwrap();
+        }
+        let lamports = self.svm.minimum_balance_for_rent_exemption(len);
+        let account = Account { lamports, data, owner: *program, executable: false, rent_epoch: 0 };
+        self.svm.set_account(*address, account).expect("set token account");
+        self.touched.insert(*address);
+    }
+
+    /// Give an existing mint of either token program a freeze authority.
+    pub fn set_freeze_authority(&mut self, mint: &Pubkey, authority: &Pubkey) {
+        let mut account = self.svm.get_account(mint).expect("mint");
+        let mut state = StateWithExtensionsMut::<Mint>::unpack(&mut account.data).unwrap();
+        state.base.freeze_authority = COption::Some(*authority);
+        state.pack_base();
+        self.svm.set_account(*mint, account).expect("set mint");
+    }
+
+    /// Write raw program account data, e.g. an account in an old layout.
+    pub fn set_program_account(&mut self, address: &Pubkey, data: Vec<u8>) {
+        let lamports = self.svm.minimum_balance_for_rent_exemption(data.len());
+        let account = Account { lamports, data, owner: oridion::ID, executable: false, rent_epoch: 0 };
+        self.svm.set_account(*address, account).expect("set program account");
+        self.touched.insert(*address);
+    }
+
+    pub fn set_unix_timestamp(&mut self, unix_timestamp: i64) {
+        let mut clock: Clock = self.svm.get_sysvar();
+        clock.unix_timestamp = unix_timestamp;
+        self.svm.set_sysvar(&clock);
+    }
+
+    /// Send with any fee payer. Every required signature is filled with a unique
+    /// placeholder so repeated transactions are not rejected as duplicates.
+    pub fn send_as(&mut self, payer: &Pubkey, instructions: &[Instruction]) -> Result<TransactionMetadata, ClientError> {
+        let message = Message::new_with_blockhash(instructions, Some(payer), &self.svm.latest_blockhash());
+        self.touched.extend(message.account_keys.iter().copied());
+        let mut transaction = Transaction::new_unsigned(message);
+        transaction.signatures = (0..transaction.message.header.num_required_signatures)
+            .map(|_| Signature::new_unique())
+            .collect();
+        self.svm.send_transaction(transaction)
+            .map_err(|failed| ClientError::Transaction(format!("{:?}\n{}", failed.err, failed.meta.logs.join("\n"))))
+    }
+
+    /// `send_as` for a v0 transaction resolving accounts through `tables`.
+    pub fn send_v0_as(&mut self, payer: &Pubkey, instructions: &[Instruction], tables: &[AddressLookupTableAccount]) -> Result<TransactionMetadata, ClientError> {
+        let message = lookup_table::v0_message(payer, instructions, tables, self.svm.latest_blockhash())?;
+        self.touched.extend(instructions.iter().flat_map(|ix| ix.accounts.iter().map(|meta| meta.pubkey)));
+        let signatures = (0..message.header().num_required_signatures)
+            .map(|_| Signature::new_unique())
+            .collect();
+        self.svm.send_transaction(VersionedTransaction { signatures, message })
+            .map_err(|failed| ClientError::Transaction(format!("{:?}\n{}", failed.err, failed.meta.logs.join("\n"))))
+    }
+
+    /// Moves to `slot`. Lookup table addresses can be used from the slot after they were added.
+    pub fn warp_to_slot(&mut self, slot: u64) {
+        self.svm.warp_to_slot(slot);
+    }
+}
+
+impl Cluster for SvmCluster {
+    fn payer(&self) -> Pubkey {
+        self.payer
+    }
+
+    fn unix_timestamp(&self) -> Result<i64, ClientError> {
+        Ok(self.svm.get_sysvar::<Clock>().unix_timestamp)
+    }
+
+    fn slot(&self) -> Result<u64, ClientError> {
+        Ok(self.svm.get_sysvar::<Clock>().slot)
+    }
+
+    fn account<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<Option<T>, ClientError> {
+        self.svm.get_account(address)
+            .filter(|account| account.lamports > 0)
+            .map(|account| deserialize(address, &account.data))
+            .transpose()
+    }
+
+    fn owner(&self, address: &Pubkey) -> Result<Option<Pubkey>, ClientError> {
+        Ok(self.svm.get_account(address).filter(|account| account.lamports > 0).map(|account| account.owner))
+    }
+
+    fn lookup_table(&self, address: &Pubkey) -> Result<Option<AddressLookupTableAccount>, ClientError> {
+        self.svm.get_account(address)
+            .filter(|account| account.owner == address_lookup_table::program::ID)
+            .map(|account| deserialize_lookup_table(address, &account.data))
+            .transpose()
+    }
+
+    fn program_accounts<T: AccountDeserialize + Discriminator>(&self) -> Result<Vec<(Pubkey, T)>, ClientError> {
+        self.touched.iter()
+            .filter_map(|address| self.svm.get_account(address).map(|account| (address, account)))
+            .filter(|(_, account)| account.owner == oridion::ID && account.data.starts_with(&T::DISCRIMINATOR))
+            .map(|(address, account)| Ok((*address, deserialize(address, &account.data)?)))
+            .collect()
+    }
+
+    fn send(&mut self, instructions: &[Instruction]) -> Result<Signature, ClientError> {
+        let payer = self.payer;
+        self.send_as(&payer, instructions).map(|meta| meta.signature)
+    }
+
+    fn send_v0(&mut self, instructions: &[Instruction], tables: &[AddressLookupTableAccount]) -> Result<Signature, ClientError> {
+        let payer = self.payer;
+        self.send_v0_as(&payer, instructions, tables).map(|meta| meta.signature)
+    }
+}
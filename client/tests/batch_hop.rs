@@ -0,0 +1,179 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use oridion::accounts_comet::Deposit;
+use oridion::accounts_stats::PlanetStats;
+use oridion::shared::select_next_planet;
+use oridion::variables::{BATCH_HOP_ACCOUNTS, MANAGER_PUBKEY};
+use oridion_client::crank::{HOP_DESTINATION_PROGRAM, MODE_DELAY, MODE_MANUAL};
+use oridion_client::svm::SvmCluster;
+use oridion_client::{instructions, pda, Cluster, Crank, CrankConfig};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use common::{galaxy_with, FOUR_PLANETS as PLANETS, SOL, START};
+
+/// `count` deposits of one SOL, spread over the planets.
+fn deposits(cluster: &mut SvmCluster, count: usize, mode: u8, withdraw_at: i64) -> Vec<Pubkey> {
+    (0..count)
+        .map(|n| {
+            let creator = Pubkey::new_unique();
+            cluster.airdrop(&creator, 2 * SOL);
+            let planet = PLANETS[n % PLANETS.len()];
+            let ix = instructions::create_deposit(&creator, planet, &Pubkey::new_unique(), SOL, mode, 0, withdraw_at);
+            cluster.send_as(&creator, &[ix]).unwrap();
+            pda::deposit(&creator)
+        })
+        .collect()
+}
+
+fn read(cluster: &SvmCluster, address: &Pubkey) -> Deposit {
+    cluster.account(address).unwrap().unwrap()
+}
+
+/// (deposit, from, to) moving every deposit one planet along.
+fn next_hops(cluster: &SvmCluster, addresses: &[Pubkey]) -> Vec<(Pubkey, u16, u16)> {
+    addresses
+        .iter()
+        .map(|address| {
+            let from = read(cluster, address).location;
+            (*address, from, from % PLANETS.len() as u16 + 1)
+        })
+        .collect()
+}
+
+fn planet_balances(cluster: &SvmCluster) -> Vec<u64> {
+    PLANETS.iter().map(|id| cluster.balance(&pda::planet(*id))).collect()
+}
+
+#[test]
+fn entries_are_four_writable_accounts() {
+    let deposit = Pubkey::new_unique();
+    let ix = instructions::batch_hop(&MANAGER_PUBKEY, &[(deposit, 1, 2), (deposit, 2, 3)]);
+    let entries = &ix.accounts[3..];
+    assert_eq!(entries.len(), 2 * BATCH_HOP_ACCOUNTS);
+    assert!(entries.iter().all(|meta| meta.is_writable && !meta.is_signer));
+    assert_eq!(entries[4].pubkey, deposit);
+    assert_eq!(entries[7].pubkey, pda::planet_stats(3));
+}
+
+#[test]
+fn dozens_of_deposits_hop_in_a_few_transactions() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    let addresses = deposits(&mut cluster, 40, MODE_MANUAL, START);
+    let before = planet_balances(&cluster);
+
+    let hops = next_hops(&cluster, &addresses);
+    for chunk in hops.chunks(5) {
+        cluster.send(&[instructions::batch_hop(&MANAGER_PUBKEY, chunk)]).unwrap();
+    }
+
+    for (address, _, to) in &hops {
+        let deposit = read(&cluster, address);
+        assert_eq!(deposit.location, *to);
+        assert_eq!(deposit.hops, 3);
+    }
+    //Ten deposits start on every planet and ten land on every planet
+    assert_eq!(planet_balances(&cluster), before);
+    for id in PLANETS {
+        let stats: PlanetStats = cluster.account(&pda::planet_stats(id)).unwrap().unwrap();
+        assert_eq!(stats.planet_hops, 10);
+    }
+}
+
+#[test]
+fn planets_may_repeat_across_entries() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    let addresses = deposits(&mut cluster, 2, MODE_MANUAL, START);
+    let first = read(&cluster, &addresses[0]).location;
+    let second = read(&cluster, &addresses[1]).location;
+
+    //The first deposit lands where the second one leaves from, then hops on again
+    let hops = [(addresses[0], first, second), (addresses[1], second, first), (addresses[0], second, 3)];
+    cluster.send(&[instructions::batch_hop(&MANAGER_PUBKEY, &hops)]).unwrap();
+    assert_eq!(read(&cluster, &addresses[0]).location, 3);
+    assert_eq!(read(&cluster, &addresses[1]).location, first);
+    assert_eq!(cluster.balance(&pda::planet(3)) - cluster.balance(&pda::planet(4)), SOL);
+}
+
+#[test]
+fn one_bad_entry_fails_the_batch() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    let addresses = deposits(&mut cluster, 3, MODE_MANUAL, START);
+    let hops = next_hops(&cluster, &addresses);
+    let (deposit, from, to) = hops[2];
+    let unmoved = |cluster: &SvmCluster| hops.iter().all(|(address, from, _)| read(cluster, address).location == *from);
+
+    let wrong_from = [hops[0], hops[1], (deposit, to, from)];
+    assert!(cluster.send(&[instructions::batch_hop(&MANAGER_PUBKEY, &wrong_from)]).is_err());
+    let same_planet = [hops[0], hops[1], (deposit, from, from)];
+    assert!(cluster.send(&[instructions::batch_hop(&MANAGER_PUBKEY, &same_planet)]).is_err());
+
+    //Stats of another planet than the one landed on
+    let mut ix = instructions::batch_hop(&MANAGER_PUBKEY, &hops);
+    let last = ix.accounts.len() - 1;
+    ix.accounts[last].pubkey = pda::planet_stats(from);
+    assert!(cluster.send(&[ix]).is_err());
+
+    //Read-only entry
+    let mut ix = instructions::batch_hop(&MANAGER_PUBKEY, &hops);
+    ix.accounts[3].is_writable = false;
+    assert!(cluster.send(&[ix]).is_err());
+
+    //Incomplete entry
+    let mut ix = instructions::batch_hop(&MANAGER_PUBKEY, &hops);
+    ix.accounts.pop();
+    assert!(cluster.send(&[ix]).is_err());
+    assert!(cluster.send(&[instructions::batch_hop(&MANAGER_PUBKEY, &[])]).is_err());
+
+    assert!(unmoved(&cluster));
+    cluster.send(&[instructions::batch_hop(&MANAGER_PUBKEY, &hops)]).unwrap();
+}
+
+#[test]
+fn only_the_manager_batches() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    let addresses = deposits(&mut cluster, 2, MODE_MANUAL, START);
+    let stranger = Pubkey::new_unique();
+    cluster.airdrop(&stranger, SOL);
+    let hops = next_hops(&cluster, &addresses);
+    assert!(cluster.send_as(&stranger, &[instructions::batch_hop(&stranger, &hops)]).is_err());
+}
+
+#[test]
+fn entries_follow_program_selected_destinations() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    cluster.send(&[instructions::update_hop_destination_mode(&MANAGER_PUBKEY, HOP_DESTINATION_PROGRAM)]).unwrap();
+    let addresses = deposits(&mut cluster, 4, MODE_MANUAL, START);
+    let selected: Vec<(Pubkey, u16, u16)> = addresses
+        .iter()
+        .map(|address| {
+            let deposit = read(&cluster, address);
+            (*address, deposit.location, select_next_planet(&PLANETS, address, &deposit).unwrap())
+        })
+        .collect();
+
+    let mut wrong = selected.clone();
+    let (address, from, to) = wrong[0];
+    wrong[0] = (address, from, PLANETS.into_iter().find(|id| *id != from && *id != to).unwrap());
+    assert!(cluster.send(&[instructions::batch_hop(&MANAGER_PUBKEY, &wrong)]).is_err());
+    cluster.send(&[instructions::batch_hop(&MANAGER_PUBKEY, &selected)]).unwrap();
+}
+
+#[test]
+fn crank_batches_planet_hops() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    let addresses = deposits(&mut cluster, 24, MODE_DELAY, START + 1_000);
+    let before: u64 = planet_balances(&cluster).iter().sum();
+
+    let config = CrankConfig { salt: b"test salt".to_vec(), ..CrankConfig::default() };
+    let mut crank = Crank::new(cluster, StdRng::seed_from_u64(11), config);
+    let mut now = START;
+    while addresses.iter().any(|address| crank.cluster().account::<Deposit>(address).unwrap().is_some()) {
+        now += 200;
+        crank.cluster_mut().set_unix_timestamp(now);
+        for (_, result) in crank.tick().unwrap() {
+            result.unwrap();
+        }
+    }
+    assert_eq!(planet_balances(crank.cluster()).iter().sum::<u64>(), before - 24 * SOL);
+}
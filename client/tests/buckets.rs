@@ -0,0 +1,88 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use oridion::accounts_comet::Deposit;
+use oridion::accounts_universe::Universe;
+use oridion::variables::MANAGER_PUBKEY;
+use oridion_client::crank::MODE_DELAY;
+use oridion_client::instructions::split_into_buckets;
+use oridion_client::svm::SvmCluster;
+use oridion_client::{instructions, pda, ClientError, Cluster, Crank, CrankConfig};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use common::{PLANETS, SOL, START};
+
+const BUCKETS: [u64; 3] = [SOL / 10, SOL, 10 * SOL];
+
+/// Universe with three planets and the 0.1, 1 and 10 SOL buckets.
+fn galaxy() -> Option<SvmCluster> {
+    let mut cluster = common::galaxy()?;
+    cluster.send(&[instructions::update_buckets(&MANAGER_PUBKEY, &BUCKETS)]).unwrap();
+    Some(cluster)
+}
+
+#[test]
+fn amounts_split_into_the_largest_buckets_first() {
+    assert_eq!(split_into_buckets(12 * SOL + SOL / 10, &BUCKETS), Some(vec![10 * SOL, SOL, SOL, SOL / 10]));
+    assert_eq!(split_into_buckets(SOL, &BUCKETS), Some(vec![SOL]));
+    assert_eq!(split_into_buckets(SOL + 1, &BUCKETS), None);
+    assert_eq!(split_into_buckets(0, &BUCKETS), None);
+    assert_eq!(split_into_buckets(SOL, &[]), None);
+}
+
+#[test]
+fn larger_amounts_become_several_bucket_deposits_in_one_transaction() {
+    let Some(mut cluster) = galaxy() else { return };
+    let creator = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    let lamports = 12 * SOL + SOL / 10;
+    cluster.airdrop(&creator, lamports + SOL);
+
+    let ixs = instructions::create_bucket_deposits(&creator, PLANETS[0], &destination, lamports, &BUCKETS, 0, MODE_DELAY, 0, START + 1_000).unwrap();
+    assert_eq!(ixs.len(), 4);
+    cluster.send_as(&creator, &ixs).unwrap();
+    let amounts: Vec<u64> = (0..4)
+        .map(|nonce| cluster.account::<Deposit>(&pda::bucket_deposit(&creator, nonce)).unwrap().unwrap().lamports)
+        .collect();
+    assert_eq!(amounts, vec![10 * SOL, SOL, SOL, SOL / 10]);
+
+    //Every bucket deposit withdraws on its own, the destination gets the full amount
+    let config = CrankConfig { salt: b"test salt".to_vec(), ..CrankConfig::default() };
+    let mut crank = Crank::new(cluster, StdRng::seed_from_u64(6), config);
+    let mut now = START;
+    while (0..4).any(|nonce| crank.cluster().account::<Deposit>(&pda::bucket_deposit(&creator, nonce)).unwrap().is_some()) {
+        now += 200;
+        assert!(now < START + 5_000, "bucket deposits were never withdrawn");
+        crank.cluster_mut().set_unix_timestamp(now);
+        crank.tick().unwrap().into_iter().for_each(|(_, result)| { result.unwrap(); });
+    }
+    assert_eq!(crank.cluster().balance(&destination), lamports);
+}
+
+#[test]
+fn bucket_deposits_must_match_a_bucket() {
+    let Some(mut cluster) = galaxy() else { return };
+    let creator = Pubkey::new_unique();
+    cluster.airdrop(&creator, 10 * SOL);
+    let ix = instructions::create_bucket_deposit(&creator, PLANETS[0], &Pubkey::new_unique(), 0, SOL + 1, MODE_DELAY, 0, START + 1_000);
+    assert!(cluster.send_as(&creator, &[ix]).is_err());
+    assert!(matches!(
+        instructions::create_bucket_deposits(&creator, PLANETS[0], &Pubkey::new_unique(), SOL + 1, &BUCKETS, 0, MODE_DELAY, 0, START + 1_000),
+        Err(ClientError::NotBucketable(_))
+    ));
+
+    //Turning buckets off rejects every bucket deposit
+    cluster.send(&[instructions::update_buckets(&MANAGER_PUBKEY, &[])]).unwrap();
+    let ix = instructions::create_bucket_deposit(&creator, PLANETS[0], &Pubkey::new_unique(), 0, SOL, MODE_DELAY, 0, START + 1_000);
+    assert!(cluster.send_as(&creator, &[ix]).is_err());
+}
+
+#[test]
+fn buckets_must_be_unique_and_non_zero() {
+    let Some(mut cluster) = galaxy() else { return };
+    assert!(cluster.send(&[instructions::update_buckets(&MANAGER_PUBKEY, &[SOL, SOL])]).is_err());
+    assert!(cluster.send(&[instructions::update_buckets(&MANAGER_PUBKEY, &[0, SOL])]).is_err());
+    assert!(cluster.send(&[instructions::update_buckets(&MANAGER_PUBKEY, &[1; 9])]).is_err());
+    let universe: Universe = cluster.account(&pda::universe()).unwrap().unwrap();
+    assert_eq!(universe.buckets(), BUCKETS);
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use oridion::accounts_comet::{Deposit, DestinationSplit};
+use oridion::shared::get_destination_commitment;
+use oridion::variables::MANAGER_PUBKEY;
+use oridion_client::crank::{DESTINATION_COMMITTED, MODE_DELAY, MODE_MANUAL};
+use oridion_client::svm::SvmCluster;
+use oridion_client::{instructions, pda, Cluster, Crank, CrankConfig};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use common::{galaxy, PLANETS, SOL, START};
+
+const SALT: [u8; 32] = [7; 32];
+
+/// Committed deposit of `lamports` to a fresh destination, returned with its creator.
+fn committed_deposit(cluster: &mut SvmCluster, lamports: u64, mode: u8) -> (Pubkey, Pubkey, Pubkey) {
+    let creator = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    cluster.airdrop(&creator, lamports + SOL);
+    let commitment = get_destination_commitment(&destination, &SALT);
+    let ix = instructions::create_committed_deposit(&creator, PLANETS[0], lamports, mode, 0, START + 1_000, commitment);
+    cluster.send_as(&creator, &[ix]).unwrap();
+    (pda::deposit(&creator), destination, creator)
+}
+
+#[test]
+fn committed_deposit_withdraws_only_to_the_revealed_destination() {
+    let Some(mut cluster) = galaxy() else { return };
+    let lamports = SOL;
+    let (address, destination, _) = committed_deposit(&mut cluster, lamports, MODE_MANUAL);
+
+    //Nothing on chain names the destination
+    let deposit: Deposit = cluster.account(&address).unwrap().unwrap();
+    assert_eq!(deposit.dm, DESTINATION_COMMITTED);
+    assert_eq!(deposit.destination, Pubkey::default());
+    assert_eq!(deposit.commitment, get_destination_commitment(&destination, &SALT));
+
+    //Plain withdraw, wrong salt and wrong destination are all refused
+    let plain = instructions::withdraw(&MANAGER_PUBKEY, &address, PLANETS[0], &deposit.destination);
+    assert!(cluster.send(&[plain]).is_err());
+    let wrong_salt = instructions::withdraw_committed(&MANAGER_PUBKEY, &address, PLANETS[0], &destination, None, [8; 32]);
+    assert!(cluster.send(&[wrong_salt]).is_err());
+    let thief = Pubkey::new_unique();
+    let wrong_destination = instructions::withdraw_committed(&MANAGER_PUBKEY, &address, PLANETS[0], &thief, None, SALT);
+    assert!(cluster.send(&[wrong_destination]).is_err());
+
+    let reveal = instructions::withdraw_committed(&MANAGER_PUBKEY, &address, PLANETS[0], &destination, None, SALT);
+    cluster.send(&[reveal]).unwrap();
+    assert_eq!(cluster.balance(&destination), lamports);
+    assert!(cluster.account::<Deposit>(&address).unwrap().is_none());
+}
+
+#[test]
+fn creator_reveals_once_the_deposit_is_due() {
+    let Some(mut cluster) = galaxy() else { return };
+    let (address, destination, creator) = committed_deposit(&mut cluster, SOL, MODE_MANUAL);
+    let stranger = Pubkey::new_unique();
+    cluster.airdrop(&stranger, SOL);
+
+    let early = instructions::withdraw_committed(&creator, &address, PLANETS[0], &destination, None, SALT);
+    assert!(cluster.send_as(&creator, &[early]).is_err());
+
+    cluster.set_unix_timestamp(START + 1_000);
+    let stolen = instructions::withdraw_committed(&stranger, &address, PLANETS[0], &destination, None, SALT);
+    assert!(cluster.send_as(&stranger, &[stolen]).is_err());
+    let reveal = instructions::withdraw_committed(&creator, &address, PLANETS[0], &destination, None, SALT);
+    cluster.send_as(&creator, &[reveal]).unwrap();
+    assert_eq!(cluster.balance(&destination), SOL);
+}
+
+#[test]
+fn committed_deposits_cannot_name_split_destinations() {
+    let Some(mut cluster) = galaxy() else { return };
+    let (_, _, creator) = committed_deposit(&mut cluster, SOL, MODE_MANUAL);
+    let splits = [
+        DestinationSplit { destination: Pubkey::new_unique(), bps: 5_000, min: 0 },
+        DestinationSplit { destination: Pubkey::new_unique(), bps: 5_000, min: 0 },
+    ];
+    let ix = instructions::set_deposit_splits(&creator, None, 1, &splits);
+    assert!(cluster.send_as(&creator, &[ix]).is_err());
+}
+
+#[test]
+fn crank_hops_committed_deposits_and_leaves_the_withdraw_to_the_owner() {
+    let Some(mut cluster) = galaxy() else { return };
+    let (address, destination, _) = committed_deposit(&mut cluster, SOL, MODE_DELAY);
+    let config = CrankConfig { salt: b"test salt".to_vec(), ..CrankConfig::default() };
+    let mut crank = Crank::new(cluster, StdRng::seed_from_u64(3), config);
+
+    let mut now = START;
+    while now < START + 2_000 {
+        now += 200;
+        crank.cluster_mut().set_unix_timestamp(now);
+        crank.tick().unwrap().into_iter().for_each(|(_, result)| { result.unwrap(); });
+    }
+    let deposit: Deposit = crank.cluster().account(&address).unwrap().unwrap();
+    assert!(deposit.hops > 2);
+
+    let reveal = instructions::withdraw_committed(&MANAGER_PUBKEY, &address, deposit.location, &destination, None, SALT);
+    crank.cluster_mut().send(&[reveal]).unwrap();
+    assert_eq!(crank.cluster().balance(&destination), deposit.lamports);
+}
@@ -0,0 +1,52 @@
+//! Fixtures shared by the LiteSVM tests. Every test crate uses a part of them.
+#![allow(dead_code)]
+
+use anchor_lang::prelude::Pubkey;
+use oridion::variables::MANAGER_PUBKEY;
+use oridion_client::svm::SvmCluster;
+use oridion_client::{instructions, Cluster};
+
+pub const PLANETS: [u16; 3] = [1, 2, 3];
+pub const FOUR_PLANETS: [u16; 4] = [1, 2, 3, 4];
+pub const START: i64 = 1_700_000_000;
+pub const SOL: u64 = 1_000_000_000;
+
+/// Program loaded with the clock at START, or `None` when `oridion.so` has not been built.
+/// Under CI (the `CI` variable is set) a missing program fails the test instead.
+pub fn load() -> Option<SvmCluster> {
+    let Some(mut cluster) = SvmCluster::load() else {
+        if std::env::var_os("CI").is_some() {
+            panic!("{} not found, CI must run `anchor build` or set ORIDION_PROGRAM_SO", SvmCluster::program_path().display());
+        }
+        eprintln!("skipping: {} not found, run `anchor build` first", SvmCluster::program_path().display());
+        return None;
+    };
+    cluster.set_unix_timestamp(START);
+    Some(cluster)
+}
+
+/// Universe with the manager set up and no planets.
+pub fn universe() -> Option<SvmCluster> {
+    let mut cluster = load()?;
+    cluster.send(&[instructions::bang(&MANAGER_PUBKEY), instructions::create_galaxy_stats(&MANAGER_PUBKEY)]).unwrap();
+    Some(cluster)
+}
+
+/// Universe with three SOL planets.
+pub fn galaxy() -> Option<SvmCluster> {
+    galaxy_with(&PLANETS)
+}
+
+/// Universe with a SOL planet for each id in `planets`.
+pub fn galaxy_with(planets: &[u16]) -> Option<SvmCluster> {
+    let mut cluster = universe()?;
+    create_planets(&mut cluster, planets, &[]);
+    Some(cluster)
+}
+
+/// Planets named after their id, serving `mints` besides SOL.
+pub fn create_planets(cluster: &mut SvmCluster, planets: &[u16], mints: &[Pubkey]) {
+    for id in planets {
+        cluster.send(&[instructions::create_planet(&MANAGER_PUBKEY, *id, &format!("planet {id}"), mints)]).unwrap();
+    }
+}
@@ -0,0 +1,268 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::spl_token;
+use oridion::accounts_comet::{Deposit, RoutePolicy};
+use oridion::shared::{get_keeper_reward, select_next_planet};
+use oridion::variables::{ACCOUNT_VERSION, MANAGER_PUBKEY, MAX_RECENT_PLANETS, NATIVE_SOL};
+use oridion_client::crank::{pick_destination, star_ids, MODE_DELAY};
+use oridion_client::svm::SvmCluster;
+use oridion_client::instructions::MintFees;
+use oridion_client::{instructions, pda, Action, Cluster, ClientError, Crank, CrankConfig};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use common::{create_planets, galaxy, universe, PLANETS, START};
+
+fn planets() -> Vec<u16> {
+    PLANETS.to_vec()
+}
+
+/// Universe whose planets serve the SPL token `mints`.
+fn galaxy_serving(mints: &[Pubkey]) -> Option<SvmCluster> {
+    let mut cluster = universe()?;
+    for mint in mints {
+        cluster.create_mint(mint, 6);
+        cluster.send(&[instructions::create_mint_config(&MANAGER_PUBKEY, mint, 1, u64::MAX, MintFees::default())]).unwrap();
+    }
+    create_planets(&mut cluster, &PLANETS, mints);
+    Some(cluster)
+}
+
+fn deposit(cluster: &mut SvmCluster, lamports: u64, withdraw_at: i64) -> (Pubkey, Pubkey) {
+    let creator = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    cluster.airdrop(&creator, lamports + 1_000_000_000);
+    let ix = instructions::create_deposit(&creator, PLANETS[0], &destination, lamports, MODE_DELAY, 0, withdraw_at);
+    cluster.send_as(&creator, &[ix]).unwrap();
+    (pda::deposit(&creator), destination)
+}
+
+fn crank(cluster: SvmCluster, seed: u64) -> Crank<SvmCluster, StdRng> {
+    crank_with(cluster, seed, false)
+}
+
+fn crank_with(cluster: SvmCluster, seed: u64, keeper: bool) -> Crank<SvmCluster, StdRng> {
+    let config = CrankConfig { salt: b"test salt".to_vec(), keeper, ..CrankConfig::default() };
+    Crank::new(cluster, StdRng::seed_from_u64(seed), config)
+}
+
+#[test]
+fn star_ids_are_stable_and_fit_in_a_seed() {
+    let deposit = Pubkey::new_unique();
+    let three = star_ids(b"salt", &deposit, 4, 3);
+    assert_eq!(three, star_ids(b"salt", &deposit, 4, 3));
+    assert_eq!(star_ids(b"salt", &deposit, 4, 2), three[..2]);
+    assert_ne!(three, star_ids(b"salt", &deposit, 5, 3));
+    assert_ne!(three, star_ids(b"other", &deposit, 4, 3));
+    assert!(three.iter().all(|id| id.len() == 16));
+    assert!(three[0] != three[1] && three[1] != three[2] && three[0] != three[2]);
+}
+
+#[test]
+fn destination_is_never_the_current_planet() {
+    let mut rng = StdRng::seed_from_u64(7);
+    for _ in 0..100 {
+        assert_ne!(pick_destination(&mut rng, &planets(), PLANETS[1]).unwrap(), PLANETS[1]);
+    }
+    let lonely = vec![PLANETS[1]];
+    assert!(matches!(pick_destination(&mut rng, &lonely, PLANETS[1]), Err(ClientError::NoDestination(_))));
+}
+
+#[test]
+fn crank_drives_delayed_deposit_to_withdrawal() {
+    let Some(mut cluster) = galaxy() else { return };
+    let lamports = 2_000_000_000;
+    let (address, destination) = deposit(&mut cluster, lamports, START + 1_000);
+    let mut crank = crank(cluster, 1);
+
+    //Nothing is due until the first hop time
+    assert!(crank.tick().unwrap().is_empty());
+
+    let mut now = START;
+    let mut hops = 0;
+    while crank.cluster().account::<Deposit>(&address).unwrap().is_some() {
+        now += 200;
+        assert!(now < START + 5_000, "deposit was never withdrawn");
+        crank.cluster_mut().set_unix_timestamp(now);
+        for (_, result) in crank.tick().unwrap() {
+            if result.unwrap() != Action::Withdraw {
+                hops += 1;
+            }
+        }
+    }
+    assert!(hops >= 4);
+    assert_eq!(crank.cluster().balance(&destination), lamports);
+}
+
+#[test]
+fn crank_drives_token_deposit_to_withdrawal() {
+    let mint = Pubkey::new_unique();
+    let Some(mut cluster) = galaxy_serving(&[mint]) else { return };
+    for id in PLANETS {
+        cluster.send(&[instructions::create_planet_token_account(&MANAGER_PUBKEY, id, &mint, &spl_token::ID)]).unwrap();
+    }
+    let creator = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    let amount = 5_000_000;
+    cluster.airdrop(&creator, 1_000_000_000);
+    cluster.mint_to(&creator, &mint, amount);
+    let ix = instructions::create_token_deposit(&creator, PLANETS[0], &mint, &spl_token::ID, &destination, amount, MODE_DELAY, 0, START + 1_000);
+    cluster.send_as(&creator, &[ix]).unwrap();
+    let address = pda::deposit(&creator);
+    assert_eq!(cluster.token_balance(&pda::planet(PLANETS[0]), &mint), amount);
+
+    let mut crank = crank(cluster, 4);
+    let mut now = START;
+    while crank.cluster().account::<Deposit>(&address).unwrap().is_some() {
+        now += 200;
+        assert!(now < START + 5_000, "deposit was never withdrawn");
+        crank.cluster_mut().set_unix_timestamp(now);
+        for (_, result) in crank.tick().unwrap() {
+            result.unwrap();
+        }
+    }
+    assert_eq!(crank.cluster().token_balance(&destination, &mint), amount);
+    assert_eq!(crank.cluster().token_balance(&creator, &mint), 0);
+}
+
+#[test]
+fn crank_finishes_an_interrupted_star_hop() {
+    let Some(mut cluster) = galaxy() else { return };
+    let lamports = 1_000_000_000;
+    let (address, _) = deposit(&mut cluster, lamports, START + 10_000);
+
+    //A previous run started a star hop and died before landing it
+    let stars = star_ids(b"test salt", &address, 2, 3);
+    let ix = instructions::star_hop_three_start(&MANAGER_PUBKEY, &address, PLANETS[0], [&stars[0], &stars[1], &stars[2]]);
+    cluster.send(&[ix]).unwrap();
+
+    let mut crank = crank(cluster, 2);
+    crank.cluster_mut().set_unix_timestamp(START + 200);
+    let outcomes = crank.tick().unwrap();
+    assert_eq!(outcomes.len(), 1);
+    assert!(matches!(outcomes[0].1.as_ref().unwrap(), Action::StarHopEnd { stars: landed, .. } if *landed == stars));
+
+    let deposit: Deposit = crank.cluster().account(&address).unwrap().unwrap();
+    assert_eq!(deposit.hops, 3);
+    assert_ne!(deposit.location, PLANETS[0]);
+    assert_eq!(crank.cluster().balance(&pda::star(&stars[0])), 0);
+}
+
+#[test]
+fn program_selected_planet_is_stable_and_never_the_current_one() {
+    let address = Pubkey::new_unique();
+    let mut deposit = Deposit {
+        version: ACCOUNT_VERSION,
+        mode: MODE_DELAY,
+        next_process_at: START,
+        next_process: 0,
+        last_process: 1,
+        location: PLANETS[1],
+        delay: 0,
+        hops: 2,
+        withdraw_at: START + 1_000,
+        created_at: START,
+        last_process_at: START,
+        lamports: 1_000_000_000,
+        destination: Pubkey::new_unique(),
+        mint: NATIVE_SOL,
+        sm: 0,
+        splits: Vec::new(),
+        tranches: 0,
+        spacing_min: 0,
+        spacing_max: 0,
+        dm: 0,
+        commitment: [0; 32],
+        route: RoutePolicy::default(),
+        star_hops: 0,
+        fees: 0,
+        visited: 0,
+        recent: [0; MAX_RECENT_PLANETS],
+        rn: 0,
+        stars: 0,
+    };
+    let planets = planets();
+    let selected = select_next_planet(&planets, &address, &deposit).unwrap();
+    assert_ne!(selected, PLANETS[1]);
+    assert_eq!(select_next_planet(&planets, &address, &deposit), Some(selected));
+
+    deposit.location = PLANETS[0];
+    let lonely = vec![PLANETS[0]];
+    assert_eq!(select_next_planet(&lonely, &address, &deposit), None);
+}
+
+#[test]
+fn keeper_hops_to_the_program_selected_planet_for_a_reward() {
+    let Some(mut cluster) = galaxy() else { return };
+    let lamports = 1_000_000_000;
+    let (address, _) = deposit(&mut cluster, lamports, START + 10_000);
+    let keeper = Pubkey::new_unique();
+    cluster.airdrop(&keeper, 1_000_000_000);
+    cluster.set_payer(keeper);
+
+    let mut crank = crank_with(cluster, 3, true);
+    crank.cluster_mut().set_unix_timestamp(START + 200);
+    let before: Deposit = crank.cluster().account(&address).unwrap().unwrap();
+    let expected = select_next_planet(&planets(), &address, &before).unwrap();
+    let keeper_before = crank.cluster().balance(&keeper);
+
+    let outcomes = crank.tick().unwrap();
+    assert_eq!(outcomes[0].1.as_ref().unwrap(), &Action::PlanetHop { to: expected });
+
+    let after: Deposit = crank.cluster().account(&address).unwrap().unwrap();
+    let fee = 1_000_000; //Default hop planet fee
+    assert_eq!(after.location, expected);
+    assert_eq!(after.lamports, lamports - fee);
+    assert_eq!(crank.cluster().balance(&keeper), keeper_before + get_keeper_reward(fee) - 5_000);
+}
+
+#[test]
+fn keeper_cannot_choose_the_destination_or_hop_early() {
+    let Some(mut cluster) = galaxy() else { return };
+    let (address, _) = deposit(&mut cluster, 1_000_000_000, START + 10_000);
+    let keeper = Pubkey::new_unique();
+    cluster.airdrop(&keeper, 1_000_000_000);
+
+    let deposit: Deposit = cluster.account(&address).unwrap().unwrap();
+    let selected = select_next_planet(&planets(), &address, &deposit).unwrap();
+    let other = PLANETS.into_iter().find(|id| *id != selected && *id != PLANETS[0]).unwrap();
+
+    //Not due yet
+    let ix = instructions::keeper_hop(&keeper, &address, PLANETS[0], selected);
+    assert!(cluster.send_as(&keeper, std::slice::from_ref(&ix)).is_err());
+
+    cluster.set_unix_timestamp(START + 200);
+    let wrong = instructions::keeper_hop(&keeper, &address, PLANETS[0], other);
+    assert!(cluster.send_as(&keeper, &[wrong]).is_err());
+    cluster.send_as(&keeper, &[ix]).unwrap();
+}
+
+#[test]
+fn deposits_in_stars_cannot_be_hopped_or_withdrawn() {
+    let Some(mut cluster) = galaxy() else { return };
+    let (address, destination) = deposit(&mut cluster, 1_000_000_000, START + 10_000);
+    let keeper = Pubkey::new_unique();
+    cluster.airdrop(&keeper, 1_000_000_000);
+    let before: Deposit = cluster.account(&address).unwrap().unwrap();
+    let selected = select_next_planet(&planets(), &address, &before).unwrap();
+
+    let stars = star_ids(b"test salt", &address, before.hops, 2);
+    cluster.send(&[instructions::star_hop_two_start(&MANAGER_PUBKEY, &address, PLANETS[0], [&stars[0], &stars[1]])]).unwrap();
+    let in_stars: Deposit = cluster.account(&address).unwrap().unwrap();
+    assert_eq!(in_stars.stars, 2);
+
+    //Due for both, but the funds sit in the stars rather than on the planet
+    cluster.set_unix_timestamp(START + 20_000);
+    assert!(cluster.send_as(&keeper, &[instructions::keeper_hop(&keeper, &address, PLANETS[0], selected)]).is_err());
+    assert!(cluster.send_as(&keeper, &[instructions::keeper_withdraw(&keeper, &address, PLANETS[0], &destination)]).is_err());
+    assert!(cluster.send(&[instructions::planet_hop(&MANAGER_PUBKEY, &address, PLANETS[0], selected)]).is_err());
+    assert!(cluster.send(&[instructions::batch_hop(&MANAGER_PUBKEY, &[(address, PLANETS[0], selected)])]).is_err());
+    assert!(cluster.send(&[instructions::withdraw(&MANAGER_PUBKEY, &address, PLANETS[0], &destination)]).is_err());
+    let again = star_ids(b"other salt", &address, before.hops, 2);
+    assert!(cluster.send(&[instructions::star_hop_two_start(&MANAGER_PUBKEY, &address, PLANETS[0], [&again[0], &again[1]])]).is_err());
+
+    cluster.send(&[instructions::star_hop_two_end(&MANAGER_PUBKEY, &address, PLANETS[1], [&stars[0], &stars[1]])]).unwrap();
+    let landed: Deposit = cluster.account(&address).unwrap().unwrap();
+    assert_eq!(landed.stars, 0);
+    cluster.send_as(&keeper, &[instructions::keeper_withdraw(&keeper, &address, PLANETS[1], &destination)]).unwrap();
+}
@@ -0,0 +1,397 @@
+//! Random sequences of deposits, hops, batch hops, star hops, keeper hops and
+//! withdrawals, valid or not, some of them split over several destinations or
+//! staggered in tranches. After every transaction the lamports of all accounts
+//! involved must add up to the same total, every planet must hold exactly the
+//! deposits located on it, and every withdrawn deposit must have paid its
+//! destinations in full, less the keeper fees it was charged. Between a star
+//! hop start and end, anything but the end must be rejected.
+//!
+//! `ORIDION_FUZZ_RUNS` sets how many seeds run (default 8), `ORIDION_FUZZ_SEED`
+//! replays a single seed.
+mod common;
+
+use std::collections::BTreeSet;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use oridion::accounts_comet::{Deposit, DestinationSplit};
+use oridion::shared::select_next_planet;
+use oridion::variables::{BPS_DENOMINATOR, MANAGER_PUBKEY, MAX_DESTINATIONS, MIN_TRANCHE_SPACING};
+use oridion_client::crank::{star_ids, MODE_DELAY, MODE_MANUAL};
+use oridion_client::instructions::UniverseFees;
+use oridion_client::svm::SvmCluster;
+use oridion_client::{instructions, pda, Cluster};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use common::{galaxy_with, FOUR_PLANETS as PLANETS, SOL, START};
+
+const MISSING_PLANET: u16 = 99;
+const HOP_FEE: u64 = 1_000_000;
+const WITHDRAW_FEE: u64 = 500_000;
+const USERS: usize = 6;
+const STEPS: usize = 200;
+
+/// Deposit the fuzzer expects to find on chain.
+struct Open {
+    address: Pubkey,
+    lamports: u64,
+    destinations: Vec<Pubkey>, //The first one is Deposit.destination
+    owed: u64, //What the destinations get in total once it is withdrawn
+    stars: Option<Vec<String>>, //Set between a star hop start and end
+}
+
+struct User {
+    creator: Pubkey,
+    open: Option<Open>,
+}
+
+struct Fuzz {
+    cluster: SvmCluster,
+    rng: StdRng,
+    payer: Pubkey,
+    keeper: Pubkey,
+    now: i64,
+    users: Vec<User>,
+    //Every account lamports can move between, the fee payer excluded
+    tracked: BTreeSet<Pubkey>,
+    total: u64,
+    //Destinations of withdrawn deposits and what they were owed
+    paid: Vec<(Vec<Pubkey>, u64)>,
+}
+
+impl Fuzz {
+    fn new(seed: u64) -> Option<Self> {
+        let mut cluster = galaxy_with(&PLANETS)?;
+        let fees = UniverseFees { hpfe: HOP_FEE as u32, wfe: WITHDRAW_FEE as u32, ..UniverseFees::default() };
+        cluster.send(&[instructions::update_fee(&MANAGER_PUBKEY, fees)]).unwrap();
+
+        //A separate fee payer keeps transaction fees out of the lamport total
+        let payer = Pubkey::new_unique();
+        cluster.airdrop(&payer, 1_000 * SOL);
+        let keeper = Pubkey::new_unique();
+        cluster.airdrop(&keeper, SOL);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let users: Vec<User> = (0..USERS)
+            .map(|_| {
+                let creator = Pubkey::new_unique();
+                cluster.airdrop(&creator, rng.gen_range(1..20) * SOL);
+                User { creator, open: None }
+            })
+            .collect();
+
+        let mut tracked: BTreeSet<Pubkey> = [MANAGER_PUBKEY, keeper, pda::universe(), pda::planet_index(), pda::galaxy_stats()].into();
+        tracked.extend(PLANETS.map(pda::planet));
+        tracked.extend(PLANETS.map(pda::planet_stats));
+        tracked.extend(users.iter().map(|user| user.creator));
+        let mut fuzz = Fuzz { cluster, rng, payer, keeper, now: START, users, tracked, total: 0, paid: Vec::new() };
+        fuzz.total = fuzz.tracked_lamports();
+        Some(fuzz)
+    }
+
+    fn tracked_lamports(&self) -> u64 {
+        self.tracked.iter().map(|address| self.cluster.balance(address)).sum()
+    }
+
+    fn rent(&mut self, address: &Pubkey) -> u64 {
+        let len = self.cluster.data_len(address).unwrap_or(0);
+        self.cluster.svm().minimum_balance_for_rent_exemption(len)
+    }
+
+    fn deposit(&self, address: &Pubkey) -> Option<Deposit> {
+        self.cluster.account(address).unwrap()
+    }
+
+    fn send(&mut self, ix: Instruction) -> bool {
+        let payer = self.payer;
+        self.tracked.extend(ix.accounts.iter().map(|meta| meta.pubkey).filter(|key| *key != payer));
+        self.cluster.send_as(&payer, &[ix]).is_ok()
+    }
+
+    /// Mostly the right planet, sometimes another or one that does not exist.
+    fn planet(&mut self, right: u16) -> u16 {
+        match self.rng.gen_range(0..8) {
+            0 => MISSING_PLANET,
+            1 | 2 => PLANETS[self.rng.gen_range(0..PLANETS.len())],
+            _ => right,
+        }
+    }
+
+    fn any_planet(&mut self) -> u16 {
+        let id = PLANETS[self.rng.gen_range(0..PLANETS.len())];
+        self.planet(id)
+    }
+
+    /// Mostly the program-selected planet a keeper must hop to, sometimes any other.
+    fn keeper_planet(&mut self, address: &Pubkey, deposit: &Deposit) -> u16 {
+        match select_next_planet(&PLANETS, address, deposit) {
+            Some(selected) if self.rng.gen_bool(0.8) => selected,
+            _ => self.any_planet(),
+        }
+    }
+
+    /// Withdraw by the manager or a keeper, mostly with every split destination attached.
+    fn withdraw_ix(&mut self, address: &Pubkey, deposit: &Deposit, from: u16, destination: &Pubkey, keeper: bool) -> Instruction {
+        let mut ix = match keeper {
+            true => instructions::keeper_withdraw(&self.keeper, address, from, destination),
+            false => instructions::withdraw(&MANAGER_PUBKEY, address, from, destination),
+        };
+        if self.rng.gen_bool(0.9) {
+            instructions::add_split_destinations(&mut ix, deposit);
+        }
+        ix
+    }
+
+    fn step(&mut self) {
+        self.now += self.rng.gen_range(0..=120);
+        self.cluster.set_unix_timestamp(self.now);
+        if self.rng.gen_range(0..12) == 0 {
+            return self.batch();
+        }
+
+        let index = self.rng.gen_range(0..self.users.len());
+        let creator = self.users[index].creator;
+        let Some(open) = self.users[index].open.as_ref() else {
+            return self.create(index);
+        };
+        let address = open.address;
+        let destination = open.destinations[0];
+        let stars = open.stars.clone();
+        let deposit = self.deposit(&address).expect("open deposit exists");
+        let from = self.planet(deposit.location);
+
+        if let Some(stars) = stars {
+            //Only the end may touch a deposit whose funds sit in stars
+            let rejected = match self.rng.gen_range(0..8) {
+                0..=2 => {
+                    let to = self.any_planet();
+                    let ix = match stars.len() {
+                        2 => instructions::star_hop_two_end(&MANAGER_PUBKEY, &address, to, [&stars[0], &stars[1]]),
+                        _ => instructions::star_hop_three_end(&MANAGER_PUBKEY, &address, to, [&stars[0], &stars[1], &stars[2]]),
+                    };
+                    if self.send(ix) {
+                        self.users[index].open.as_mut().unwrap().stars = None;
+                    }
+                    return;
+                }
+                3 => instructions::planet_hop(&MANAGER_PUBKEY, &address, from, self.any_planet()),
+                4 => {
+                    let to = self.keeper_planet(&address, &deposit);
+                    instructions::keeper_hop(&self.keeper, &address, from, to)
+                }
+                5 => self.withdraw_ix(&address, &deposit, from, &destination, false),
+                6 => self.withdraw_ix(&address, &deposit, from, &destination, true),
+                _ => {
+                    let again = star_ids(b"fuzz again", &address, deposit.hops, 2);
+                    instructions::star_hop_two_start(&MANAGER_PUBKEY, &address, from, [&again[0], &again[1]])
+                }
+            };
+            assert!(!self.send(rejected), "deposit {address} moved while in stars");
+            return;
+        }
+
+        match self.rng.gen_range(0..14) {
+            0..=2 => {
+                let to = self.any_planet();
+                self.send(instructions::planet_hop(&MANAGER_PUBKEY, &address, from, to));
+            }
+            3 => {
+                //Only the manager hops
+                let to = PLANETS[self.rng.gen_range(0..PLANETS.len())];
+                assert!(!self.send(instructions::planet_hop(&creator, &address, deposit.location, to)));
+            }
+            4..=5 => {
+                let to = self.keeper_planet(&address, &deposit);
+                if self.send(instructions::keeper_hop(&self.keeper, &address, from, to)) {
+                    let open = self.users[index].open.as_mut().unwrap();
+                    open.lamports -= HOP_FEE;
+                    open.owed -= HOP_FEE;
+                }
+            }
+            6..=8 => {
+                let count = self.rng.gen_range(2..=3);
+                let mut stars = star_ids(b"fuzz", &address, deposit.hops, count);
+                if self.rng.gen_bool(0.1) {
+                    stars[1] = stars[0].clone();
+                }
+                let ix = match count {
+                    2 => instructions::star_hop_two_start(&MANAGER_PUBKEY, &address, from, [&stars[0], &stars[1]]),
+                    _ => instructions::star_hop_three_start(&MANAGER_PUBKEY, &address, from, [&stars[0], &stars[1], &stars[2]]),
+                };
+                if self.send(ix) {
+                    self.users[index].open.as_mut().unwrap().stars = Some(stars);
+                }
+            }
+            9 => self.withdraw(index, &deposit, from, true),
+            _ => self.withdraw(index, &deposit, from, false),
+        }
+    }
+
+    /// One withdrawal, all at once or the next tranche. Its destinations must
+    /// get exactly what left the deposit, less the keeper's fee.
+    fn withdraw(&mut self, index: usize, deposit: &Deposit, from: u16, keeper: bool) {
+        let open = self.users[index].open.as_ref().unwrap();
+        let (address, lamports, destinations) = (open.address, open.lamports, open.destinations.clone());
+        let destination = if self.rng.gen_bool(0.1) { Pubkey::new_unique() } else { destinations[0] };
+        let ix = self.withdraw_ix(&address, deposit, from, &destination, keeper);
+        let before: u64 = destinations.iter().map(|destination| self.cluster.balance(destination)).sum();
+        if !self.send(ix) {
+            return;
+        }
+        let received: u64 = destinations.iter().map(|destination| self.cluster.balance(destination)).sum::<u64>() - before;
+        let fee: u64 = if keeper { WITHDRAW_FEE } else { 0 };
+        let left: u64 = self.deposit(&address).map_or(0, |deposit| deposit.lamports);
+        assert_eq!(received + fee, lamports - left, "withdrawal of {address} did not pay what it released");
+
+        let open = self.users[index].open.as_mut().unwrap();
+        open.owed -= fee;
+        open.lamports = left;
+        if left == 0 {
+            let open = self.users[index].open.take().unwrap();
+            self.paid.push((open.destinations, open.owed));
+        }
+    }
+
+    /// Planet hops of several deposits at once. Any deposit in stars fails the batch.
+    fn batch(&mut self) {
+        let opens: Vec<(Pubkey, bool)> = self.users.iter()
+            .filter_map(|user| user.open.as_ref())
+            .map(|open| (open.address, open.stars.is_some()))
+            .collect();
+        let mut hops = Vec::new();
+        let mut in_stars = false;
+        for (address, stars) in opens {
+            if self.rng.gen_bool(0.5) {
+                let location = self.deposit(&address).unwrap().location;
+                let from = self.planet(location);
+                let to = self.any_planet();
+                hops.push((address, from, to));
+                in_stars |= stars;
+            }
+        }
+        let sent = self.send(instructions::batch_hop(&MANAGER_PUBKEY, &hops));
+        assert!(!(sent && in_stars), "batch hop moved a deposit in stars");
+    }
+
+    fn create(&mut self, index: usize) {
+        let creator = self.users[index].creator;
+        let planet = self.any_planet();
+        //Sometimes more than the creator holds
+        let lamports = self.rng.gen_range(SOL / 100..8 * SOL);
+        let destination = Pubkey::new_unique();
+        let mode = if self.rng.gen_bool(0.5) { MODE_DELAY } else { MODE_MANUAL };
+        let withdraw_at = self.now + self.rng.gen_range(0..2_000);
+        let ix = instructions::create_deposit(&creator, planet, &destination, lamports, mode, 0, withdraw_at);
+        if !self.send(ix) {
+            return;
+        }
+        let address = pda::deposit(&creator);
+        let mut destinations = vec![destination];
+
+        //Before the first hop the creator may split it or stagger it
+        if self.rng.gen_bool(0.3) {
+            let count = self.rng.gen_range(2..=MAX_DESTINATIONS);
+            let mut splits: Vec<DestinationSplit> = (0..count)
+                .map(|_| DestinationSplit { destination: Pubkey::new_unique(), bps: (BPS_DENOMINATOR / count as u64) as u16, min: 0 })
+                .collect();
+            splits[0].bps += (BPS_DENOMINATOR % count as u64) as u16;
+            let mode = if self.rng.gen_bool(0.5) { 1 } else { 2 };
+            if mode == 2 {
+                splits.iter_mut().for_each(|split| split.min = lamports / (2 * count as u64));
+            }
+            if self.send(instructions::set_deposit_splits(&creator, None, mode, &splits)) {
+                destinations = splits.iter().map(|split| split.destination).collect();
+            }
+        }
+        if self.rng.gen_bool(0.3) {
+            let tranches = self.rng.gen_range(2..=4);
+            self.send(instructions::set_withdraw_schedule(&creator, None, tranches, MIN_TRANCHE_SPACING, 2 * MIN_TRANCHE_SPACING));
+        }
+        self.users[index].open = Some(Open { address, lamports, destinations, owed: lamports, stars: None });
+    }
+
+    fn check(&mut self, seed: u64, step: usize) {
+        let context = format!("seed {seed}, step {step}");
+        assert_eq!(self.tracked_lamports(), self.total, "lamports created or destroyed ({context})");
+
+        let opens: Vec<(Pubkey, u64, bool)> = self.users.iter()
+            .filter_map(|user| user.open.as_ref())
+            .map(|open| (open.address, open.lamports, open.stars.is_some()))
+            .collect();
+        let mut located = vec![0u64; PLANETS.len()];
+        for (address, lamports, in_flight) in opens {
+            let deposit = self.deposit(&address).unwrap_or_else(|| panic!("deposit {address} vanished ({context})"));
+            assert_eq!(deposit.lamports, lamports, "deposit {address} changed amount ({context})");
+            assert_eq!(deposit.stars > 0, in_flight, "deposit {address} star hop state ({context})");
+            if !in_flight {
+                let slot = PLANETS.iter().position(|id| *id == deposit.location).expect("deposit on a known planet");
+                located[slot] += lamports;
+            }
+        }
+        for (slot, id) in PLANETS.into_iter().enumerate() {
+            let planet = pda::planet(id);
+            let held = self.cluster.balance(&planet) - self.rent(&planet);
+            assert_eq!(held, located[slot], "planet {id} does not hold its deposits ({context})");
+        }
+
+        let flights: Vec<(Pubkey, u64, Vec<Pubkey>)> = self.users.iter()
+            .filter_map(|user| user.open.as_ref())
+            .filter_map(|open| Some((open.address, open.lamports, open.stars.as_ref()?.iter().map(|star| pda::star(star)).collect())))
+            .collect();
+        for (address, lamports, stars) in flights {
+            let held: u64 = stars.iter().map(|star| self.cluster.balance(star) - self.rent(star)).sum();
+            assert_eq!(held, lamports, "stars of {address} do not hold the deposit ({context})");
+        }
+        for (destinations, owed) in &self.paid {
+            let received: u64 = destinations.iter().map(|destination| self.cluster.balance(destination)).sum();
+            assert_eq!(received, *owed, "destinations {destinations:?} not paid in full ({context})");
+        }
+    }
+
+    /// Lands every star hop and withdraws everything left, tranche by tranche.
+    fn drain(&mut self) {
+        for index in 0..self.users.len() {
+            let Some(open) = self.users[index].open.as_mut() else { continue };
+            let address = open.address;
+            if let Some(stars) = open.stars.take() {
+                let ix = match stars.len() {
+                    2 => instructions::star_hop_two_end(&MANAGER_PUBKEY, &address, PLANETS[0], [&stars[0], &stars[1]]),
+                    _ => instructions::star_hop_three_end(&MANAGER_PUBKEY, &address, PLANETS[0], [&stars[0], &stars[1], &stars[2]]),
+                };
+                assert!(self.send(ix));
+            }
+            while let Some(deposit) = self.deposit(&address) {
+                self.now = self.now.max(deposit.next_process_at);
+                self.cluster.set_unix_timestamp(self.now);
+                let mut ix = instructions::withdraw(&MANAGER_PUBKEY, &address, deposit.location, &deposit.destination);
+                instructions::add_split_destinations(&mut ix, &deposit);
+                assert!(self.send(ix));
+            }
+            let open = self.users[index].open.take().unwrap();
+            self.paid.push((open.destinations, open.owed));
+        }
+    }
+}
+
+fn seeds() -> Vec<u64> {
+    if let Some(seed) = std::env::var("ORIDION_FUZZ_SEED").ok().and_then(|seed| seed.parse().ok()) {
+        return vec![seed];
+    }
+    let runs: u64 = std::env::var("ORIDION_FUZZ_RUNS").ok().and_then(|runs| runs.parse().ok()).unwrap_or(8);
+    (0..runs).collect()
+}
+
+#[test]
+fn random_sequences_conserve_lamports_and_pay_every_destination() {
+    for seed in seeds() {
+        let Some(mut fuzz) = Fuzz::new(seed) else { return };
+        for step in 0..STEPS {
+            fuzz.step();
+            fuzz.check(seed, step);
+        }
+        fuzz.drain();
+        fuzz.check(seed, STEPS);
+        for id in PLANETS {
+            let planet = pda::planet(id);
+            assert_eq!(fuzz.cluster.balance(&planet), fuzz.rent(&planet), "planet {id} kept lamports (seed {seed})");
+        }
+    }
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use oridion::accounts_comet::Deposit;
+use oridion::accounts_universe::Universe;
+use oridion::shared::select_next_planet;
+use oridion::variables::MANAGER_PUBKEY;
+use oridion_client::crank::{star_ids, HOP_DESTINATION_PROGRAM, MODE_DELAY, MODE_MANUAL};
+use oridion_client::svm::SvmCluster;
+use oridion_client::{instructions, pda, Cluster, Crank, CrankConfig};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use common::{galaxy_with, FOUR_PLANETS as PLANETS, SOL, START};
+
+/// Universe with four planets selecting hop destinations itself.
+fn galaxy() -> Option<SvmCluster> {
+    let mut cluster = galaxy_with(&PLANETS)?;
+    cluster.send(&[instructions::update_hop_destination_mode(&MANAGER_PUBKEY, HOP_DESTINATION_PROGRAM)]).unwrap();
+    Some(cluster)
+}
+
+/// Deposit on the first planet.
+fn deposit(cluster: &mut SvmCluster, mode: u8, withdraw_at: i64) -> (Pubkey, Pubkey) {
+    let creator = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    cluster.airdrop(&creator, 2 * SOL);
+    let ix = instructions::create_deposit(&creator, PLANETS[0], &destination, SOL, mode, 0, withdraw_at);
+    cluster.send_as(&creator, &[ix]).unwrap();
+    (pda::deposit(&creator), destination)
+}
+
+fn read(cluster: &SvmCluster, address: &Pubkey) -> Deposit {
+    cluster.account(address).unwrap().unwrap()
+}
+
+/// Program-selected planet for the deposit's next hop and one it must not land on.
+fn destinations(cluster: &SvmCluster, address: &Pubkey) -> (u16, u16) {
+    let deposit = read(cluster, address);
+    let selected = select_next_planet(&PLANETS, address, &deposit).unwrap();
+    let other = PLANETS.into_iter().find(|id| *id != selected && *id != deposit.location).unwrap();
+    (selected, other)
+}
+
+#[test]
+fn planet_hops_land_on_the_selected_planet() {
+    let Some(mut cluster) = galaxy() else { return };
+    let universe: Universe = cluster.account(&pda::universe()).unwrap().unwrap();
+    assert_eq!(universe.hdm, HOP_DESTINATION_PROGRAM);
+    let (address, _) = deposit(&mut cluster, MODE_MANUAL, START);
+
+    let (selected, other) = destinations(&cluster, &address);
+    assert!(cluster.send(&[instructions::planet_hop(&MANAGER_PUBKEY, &address, PLANETS[0], other)]).is_err());
+    cluster.send(&[instructions::planet_hop(&MANAGER_PUBKEY, &address, PLANETS[0], selected)]).unwrap();
+    assert_eq!(read(&cluster, &address).location, selected);
+}
+
+#[test]
+fn star_hops_land_on_the_selected_planet() {
+    let Some(mut cluster) = galaxy() else { return };
+    let (address, _) = deposit(&mut cluster, MODE_MANUAL, START);
+    let (selected, other) = destinations(&cluster, &address);
+
+    let stars = star_ids(b"salt", &address, read(&cluster, &address).hops, 3);
+    let stars = [stars[0].as_str(), stars[1].as_str(), stars[2].as_str()];
+    cluster.send(&[instructions::star_hop_three_start(&MANAGER_PUBKEY, &address, PLANETS[0], stars)]).unwrap();
+    assert!(cluster.send(&[instructions::star_hop_three_end(&MANAGER_PUBKEY, &address, other, stars)]).is_err());
+    cluster.send(&[instructions::star_hop_three_end(&MANAGER_PUBKEY, &address, selected, stars)]).unwrap();
+    assert_eq!(read(&cluster, &address).location, selected);
+}
+
+#[test]
+fn manager_selects_again_once_switched_back() {
+    let Some(mut cluster) = galaxy() else { return };
+    let (address, _) = deposit(&mut cluster, MODE_MANUAL, START);
+    let (_, other) = destinations(&cluster, &address);
+    cluster.send(&[instructions::update_hop_destination_mode(&MANAGER_PUBKEY, 0)]).unwrap();
+    cluster.send(&[instructions::planet_hop(&MANAGER_PUBKEY, &address, PLANETS[0], other)]).unwrap();
+}
+
+#[test]
+fn only_the_manager_sets_a_known_mode() {
+    let Some(mut cluster) = galaxy() else { return };
+    let stranger = Pubkey::new_unique();
+    cluster.airdrop(&stranger, SOL);
+    assert!(cluster.send_as(&stranger, &[instructions::update_hop_destination_mode(&stranger, 0)]).is_err());
+    assert!(cluster.send(&[instructions::update_hop_destination_mode(&MANAGER_PUBKEY, 2)]).is_err());
+}
+
+#[test]
+fn crank_hops_to_the_selected_planets() {
+    let Some(mut cluster) = galaxy() else { return };
+    let (address, destination) = deposit(&mut cluster, MODE_DELAY, START + 1_000);
+
+    let config = CrankConfig { salt: b"test salt".to_vec(), ..CrankConfig::default() };
+    let mut crank = Crank::new(cluster, StdRng::seed_from_u64(9), config);
+    let mut now = START;
+    while let Some(before) = crank.cluster().account::<Deposit>(&address).unwrap() {
+        let expected = select_next_planet(&PLANETS, &address, &before);
+        now += 200;
+        crank.cluster_mut().set_unix_timestamp(now);
+        crank.tick().unwrap().into_iter().for_each(|(_, result)| { result.unwrap(); });
+        if let Some(after) = crank.cluster().account::<Deposit>(&address).unwrap() {
+            if after.hops != before.hops {
+                assert_eq!(Some(after.location), expected);
+            }
+        }
+    }
+    assert_eq!(crank.cluster().balance(&destination), SOL);
+}
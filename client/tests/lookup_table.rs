@@ -0,0 +1,86 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::address_lookup_table::AddressLookupTableAccount;
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::message::Message;
+use oridion::accounts_comet::Deposit;
+use oridion::accounts_universe::Universe;
+use oridion::variables::MANAGER_PUBKEY;
+use oridion_client::crank::{star_ids, MODE_DELAY};
+use oridion_client::lookup_table::{self, EXTEND_CHUNK};
+use oridion_client::{instructions, pda, Cluster, Crank, CrankConfig};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use common::{galaxy, PLANETS, SOL, START};
+
+#[test]
+fn star_hops_are_smaller_as_v0_messages() {
+    let deposit = pda::deposit(&Pubkey::new_unique());
+    let stars = star_ids(b"salt", &deposit, 2, 3);
+    let ix = instructions::star_hop_three_end(&MANAGER_PUBKEY, &deposit, PLANETS[1], [&stars[0], &stars[1], &stars[2]]);
+    let table = AddressLookupTableAccount { key: Pubkey::new_unique(), addresses: lookup_table::galaxy_addresses(&PLANETS) };
+
+    let legacy = Message::new_with_blockhash(std::slice::from_ref(&ix), Some(&MANAGER_PUBKEY), &Hash::default()).serialize();
+    let v0 = lookup_table::v0_message(&MANAGER_PUBKEY, &[ix], &[table], Hash::default()).unwrap().serialize();
+    assert!(v0.len() < legacy.len(), "v0 {} bytes, legacy {} bytes", v0.len(), legacy.len());
+}
+
+#[test]
+fn extends_fit_a_transaction_each() {
+    let planets: Vec<u16> = (1..=20).collect();
+    let addresses = lookup_table::galaxy_addresses(&planets);
+    let ixs = lookup_table::extend(&Pubkey::new_unique(), &MANAGER_PUBKEY, &MANAGER_PUBKEY, &addresses);
+    assert_eq!(ixs.len(), addresses.len().div_ceil(EXTEND_CHUNK));
+    let table = AddressLookupTableAccount { key: Pubkey::new_unique(), addresses: addresses[..5].to_vec() };
+    assert_eq!(lookup_table::missing(&table, &planets), addresses[5..].to_vec());
+}
+
+#[test]
+fn sync_records_the_table_on_the_universe() {
+    let Some(mut cluster) = galaxy() else { return };
+    let table = lookup_table::sync(&mut cluster, &PLANETS).unwrap();
+    let universe: Universe = cluster.account(&pda::universe()).unwrap().unwrap();
+    assert_eq!(universe.lut, table.key);
+    assert_eq!(table.addresses, lookup_table::galaxy_addresses(&PLANETS));
+
+    //A new planet is added, nothing else changes
+    cluster.send(&[instructions::create_planet(&MANAGER_PUBKEY, 4, "planet 4", &[])]).unwrap();
+    let synced = lookup_table::sync(&mut cluster, &[1, 2, 3, 4]).unwrap();
+    assert_eq!(synced.key, table.key);
+    assert_eq!(synced.addresses, lookup_table::galaxy_addresses(&[1, 2, 3, 4]));
+}
+
+#[test]
+fn only_lookup_tables_are_recorded() {
+    let Some(mut cluster) = galaxy() else { return };
+    assert!(cluster.send(&[instructions::update_lookup_table(&MANAGER_PUBKEY, &pda::planet(PLANETS[0]))]).is_err());
+    let (create, table) = lookup_table::create(&MANAGER_PUBKEY, &MANAGER_PUBKEY, cluster.slot().unwrap());
+    cluster.send(&[create]).unwrap();
+    let creator = Pubkey::new_unique();
+    cluster.airdrop(&creator, SOL);
+    assert!(cluster.send_as(&creator, &[instructions::update_lookup_table(&creator, &table)]).is_err());
+}
+
+#[test]
+fn crank_sends_v0_transactions_through_the_table() {
+    let Some(mut cluster) = galaxy() else { return };
+    lookup_table::sync(&mut cluster, &PLANETS).unwrap();
+    //Addresses added to a table can be used from the next slot
+    cluster.warp_to_slot(1);
+
+    let creator = Pubkey::new_unique();
+    cluster.airdrop(&creator, 2 * SOL);
+    let ix = instructions::create_deposit(&creator, PLANETS[0], &Pubkey::new_unique(), SOL, MODE_DELAY, 0, START + 10_000);
+    cluster.send_as(&creator, &[ix]).unwrap();
+    let address = pda::deposit(&creator);
+
+    let config = CrankConfig { salt: b"test salt".to_vec(), ..CrankConfig::default() };
+    let mut crank = Crank::new(cluster, StdRng::seed_from_u64(3), config);
+    crank.cluster_mut().set_unix_timestamp(START + 200);
+    for (_, result) in crank.tick().unwrap() {
+        result.unwrap();
+    }
+    let deposit: Deposit = crank.cluster().account(&address).unwrap().unwrap();
+    assert!(deposit.hops > 2);
+}
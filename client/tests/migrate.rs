@@ -0,0 +1,203 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AnchorSerialize, Discriminator};
+use oridion::accounts_comet::{Deposit, Star};
+use oridion::accounts_migrate::{DepositV3, PlanetV3, StarV3, UniverseV3};
+use oridion::accounts_planet::Planet;
+use oridion::accounts_universe::{PlanetIndex, Universe};
+use oridion::variables::{ACCOUNT_VERSION, MANAGER_PUBKEY, NATIVE_SOL};
+use oridion_client::crank::MODE_DELAY;
+use oridion_client::svm::SvmCluster;
+use oridion_client::{instructions, pda, Cluster};
+use common::{load, universe, PLANETS, SOL, START};
+
+const NAMES: [&str; 3] = ["mercury", "venus", "earth"];
+
+/// Universe with three planets named as in v3.0.0.
+fn galaxy() -> Option<SvmCluster> {
+    let mut cluster = universe()?;
+    for (id, name) in PLANETS.into_iter().zip(NAMES) {
+        cluster.send(&[instructions::create_planet(&MANAGER_PUBKEY, id, name, &[])]).unwrap();
+    }
+    Some(cluster)
+}
+
+/// Account data as v3.0.0 wrote it: discriminator, fields, zero padding.
+fn legacy<T: Discriminator>(value: &impl AnchorSerialize, len: usize) -> Vec<u8> {
+    let mut data = T::DISCRIMINATOR.to_vec();
+    value.serialize(&mut data).unwrap();
+    assert!(data.len() <= len);
+    data.resize(len, 0);
+    data
+}
+
+fn universe_v3() -> UniverseV3 {
+    UniverseV3 {
+        pda: pda::universe(),
+        p: NAMES.iter().map(|name| name.to_string()).collect(),
+        st: START,
+        up: START,
+        bp: 255,
+        cfe: 1,
+        hpfe: 2,
+        hsfe2: 3,
+        hsfe3: 4,
+        wfe: 5,
+    }
+}
+
+fn deposit_v3(location: &str, destination: Pubkey, lamports: u64) -> DepositV3 {
+    DepositV3 {
+        mode: MODE_DELAY,
+        next_process_at: START,
+        next_process: 0,
+        last_process: 1,
+        location: location.to_string(),
+        delay: 0,
+        hops: 3,
+        withdraw_at: START + 1_000,
+        created_at: START - 1_000,
+        last_process_at: START - 100,
+        lamports,
+        destination,
+    }
+}
+
+#[test]
+fn v3_layouts_fit_their_lengths() {
+    let long = "x".repeat(oridion::variables::MAX_PLANET_TITLE_LENGTH);
+    legacy::<Universe>(&universe_v3(), UniverseV3::LEN);
+    legacy::<Deposit>(&deposit_v3(&long, Pubkey::new_unique(), SOL), DepositV3::LEN);
+    let planet = PlanetV3 { name: long, pda: Pubkey::new_unique(), created: START, bump: 255, visits: 9 };
+    legacy::<Planet>(&planet, PlanetV3::LEN);
+    legacy::<Star>(&StarV3 { amount: SOL, manager: MANAGER_PUBKEY }, StarV3::LEN);
+}
+
+#[test]
+fn migrations_keep_fields_and_set_defaults() {
+    let universe = universe_v3().migrate();
+    assert_eq!(universe.version, ACCOUNT_VERSION);
+    assert_eq!((universe.cfe, universe.hpfe, universe.hsfe2, universe.hsfe3, universe.wfe), (1, 2, 3, 4, 5));
+    assert!(universe.buckets().is_empty());
+
+    let pda = Pubkey::new_unique();
+    let planet = PlanetV3 { name: "venus".to_string(), pda: Pubkey::new_unique(), created: START, bump: 254, visits: 9 }.migrate(2, pda, 253);
+    assert_eq!(planet.version, ACCOUNT_VERSION);
+    assert_eq!((planet.id, planet.name.as_str(), planet.visits), (2, "venus", 9));
+    assert_eq!((planet.pda, planet.bump), (pda, 253));
+    assert!(planet.mints.is_empty());
+
+    let destination = Pubkey::new_unique();
+    let deposit = deposit_v3("venus", destination, SOL).migrate(2, 0);
+    assert_eq!(deposit.version, ACCOUNT_VERSION);
+    assert_eq!((deposit.location, deposit.hops, deposit.lamports), (2, 3, SOL));
+    assert_eq!(deposit.destination, destination);
+    assert_eq!(deposit.mint, NATIVE_SOL);
+    assert_eq!((deposit.sm, deposit.tranches, deposit.dm, deposit.stars), (0, 0, 0, 0));
+    assert_eq!(deposit_v3("venus", destination, SOL).migrate(2, 3).stars, 3);
+
+    let star = StarV3 { amount: SOL, manager: MANAGER_PUBKEY }.migrate();
+    assert_eq!((star.version, star.amount, star.manager), (ACCOUNT_VERSION, SOL, MANAGER_PUBKEY));
+}
+
+#[test]
+fn migrated_universe_and_planets_read_in_the_current_layout() {
+    //A v3.0.0 galaxy, written as the old program left it
+    let Some(mut cluster) = load() else { return };
+    cluster.set_program_account(&pda::universe(), legacy::<Universe>(&universe_v3(), UniverseV3::LEN));
+    for name in NAMES {
+        let planet = PlanetV3 { name: name.to_string(), pda: pda::legacy_planet(name), created: START, bump: 255, visits: 7 };
+        cluster.set_program_account(&pda::legacy_planet(name), legacy::<Planet>(&planet, PlanetV3::LEN));
+    }
+    cluster.airdrop(&pda::legacy_planet(NAMES[1]), 3 * SOL);
+
+    cluster.send(&[instructions::migrate_universe(&MANAGER_PUBKEY)]).unwrap();
+    let universe: Universe = cluster.account(&pda::universe()).unwrap().unwrap();
+    assert_eq!(universe.version, ACCOUNT_VERSION);
+    assert_eq!(universe.wfe, 5);
+
+    //Planets move from their name PDA to their id PDA, with their funds
+    let manager_before = cluster.balance(&MANAGER_PUBKEY);
+    cluster.send(&[instructions::migrate_planet(&MANAGER_PUBKEY, NAMES[1], PLANETS[1])]).unwrap();
+    let planet: Planet = cluster.account(&pda::planet(PLANETS[1])).unwrap().unwrap();
+    assert_eq!((planet.version, planet.id, planet.name.as_str(), planet.visits), (ACCOUNT_VERSION, PLANETS[1], NAMES[1], 7));
+    assert_eq!(planet.pda, pda::planet(PLANETS[1]));
+    assert_eq!(cluster.balance(&pda::legacy_planet(NAMES[1])), 0);
+    let rent = cluster.balance(&pda::planet(PLANETS[1])) - 3 * SOL;
+    assert!(cluster.balance(&MANAGER_PUBKEY) + rent + 5_000 > manager_before);
+    let index: PlanetIndex = cluster.account(&pda::planet_index()).unwrap().unwrap();
+    assert_eq!(index.p, vec![PLANETS[1]]);
+
+    //Only v3.0.0 accounts can be migrated
+    assert!(cluster.send(&[instructions::migrate_universe(&MANAGER_PUBKEY)]).is_err());
+    assert!(cluster.send(&[instructions::migrate_planet(&MANAGER_PUBKEY, NAMES[1], PLANETS[2])]).is_err());
+}
+
+#[test]
+fn migrated_deposit_hops_and_withdraws() {
+    let Some(mut cluster) = galaxy() else { return };
+    let lamports = 2 * SOL;
+    let address = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    cluster.airdrop(&pda::planet(PLANETS[0]), lamports);
+    cluster.set_program_account(&address, legacy::<Deposit>(&deposit_v3(NAMES[0], destination, lamports), DepositV3::LEN));
+
+    //The current program cannot read it before the migration
+    assert!(cluster.send(&[instructions::planet_hop(&MANAGER_PUBKEY, &address, PLANETS[0], PLANETS[1])]).is_err());
+
+    //It must be migrated onto the planet it sits on
+    assert!(cluster.send(&[instructions::migrate_deposit(&MANAGER_PUBKEY, &address, PLANETS[1], 0)]).is_err());
+    let ix = instructions::migrate_deposit(&MANAGER_PUBKEY, &address, PLANETS[0], 0);
+    cluster.send(std::slice::from_ref(&ix)).unwrap();
+    assert!(cluster.send(&[ix]).is_err());
+
+    cluster.send(&[instructions::planet_hop(&MANAGER_PUBKEY, &address, PLANETS[0], PLANETS[1])]).unwrap();
+    let deposit: Deposit = cluster.account(&address).unwrap().unwrap();
+    assert_eq!((deposit.version, deposit.location, deposit.hops), (ACCOUNT_VERSION, PLANETS[1], 4));
+
+    cluster.set_unix_timestamp(START + 1_000);
+    cluster.send(&[instructions::withdraw(&MANAGER_PUBKEY, &address, PLANETS[1], &destination)]).unwrap();
+    assert!(cluster.account::<Deposit>(&address).unwrap().is_none());
+    assert!(cluster.balance(&destination) > 0);
+}
+
+#[test]
+fn star_hops_started_before_the_migration_end_after_it() {
+    let Some(mut cluster) = galaxy() else { return };
+    let lamports = 2 * SOL;
+    let address = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    cluster.set_program_account(&address, legacy::<Deposit>(&deposit_v3(NAMES[0], destination, lamports), DepositV3::LEN));
+
+    //v3.0.0 moved the deposit's funds from its planet into two stars
+    let stars = ["legacy star one", "legacy star two"];
+    for star in stars {
+        let data = legacy::<Star>(&StarV3 { amount: SOL, manager: MANAGER_PUBKEY }, StarV3::LEN);
+        cluster.set_program_account(&pda::star(star), data);
+        cluster.airdrop(&pda::star(star), SOL);
+    }
+    let end = instructions::star_hop_two_end(&MANAGER_PUBKEY, &address, PLANETS[1], stars);
+    assert!(cluster.send(std::slice::from_ref(&end)).is_err());
+
+    //Only 0, 2 or 3 stars can hold a deposit
+    assert!(cluster.send(&[instructions::migrate_deposit(&MANAGER_PUBKEY, &address, PLANETS[0], 1)]).is_err());
+    cluster.send(&[instructions::migrate_deposit(&MANAGER_PUBKEY, &address, PLANETS[0], 2)]).unwrap();
+    let deposit: Deposit = cluster.account(&address).unwrap().unwrap();
+    assert_eq!(deposit.stars, 2);
+    assert!(cluster.send(std::slice::from_ref(&end)).is_err());
+
+    for star in stars {
+        let ix = instructions::migrate_star(&MANAGER_PUBKEY, star);
+        cluster.send(std::slice::from_ref(&ix)).unwrap();
+        assert!(cluster.send(&[ix]).is_err());
+        let migrated: Star = cluster.account(&pda::star(star)).unwrap().unwrap();
+        assert_eq!((migrated.version, migrated.amount), (ACCOUNT_VERSION, SOL));
+    }
+    let planet_before = cluster.balance(&pda::planet(PLANETS[1]));
+    cluster.send(&[end]).unwrap();
+    let deposit: Deposit = cluster.account(&address).unwrap().unwrap();
+    assert_eq!((deposit.location, deposit.stars), (PLANETS[1], 0));
+    assert_eq!(cluster.balance(&pda::planet(PLANETS[1])), planet_before + lamports);
+    assert!(cluster.account::<Star>(&pda::star(stars[0])).unwrap().is_none());
+}
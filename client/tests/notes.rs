@@ -0,0 +1,81 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use oridion::accounts_comet::Deposit;
+use oridion::shared::get_note_hash;
+use oridion::variables::MANAGER_PUBKEY;
+use oridion_client::crank::{DESTINATION_NOTE, MODE_MANUAL};
+use oridion_client::svm::SvmCluster;
+use oridion_client::{instructions, pda, Cluster};
+use common::{galaxy, PLANETS, SOL, START};
+
+const SECRET: [u8; 32] = [42; 32];
+
+/// Note deposit of `lamports` committing to a fresh destination.
+fn note_deposit(cluster: &mut SvmCluster, lamports: u64) -> (Pubkey, Pubkey) {
+    let creator = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    cluster.airdrop(&creator, lamports + SOL);
+    let ix = instructions::create_note_deposit(&creator, PLANETS[0], lamports, MODE_MANUAL, 0, START + 1_000, get_note_hash(&SECRET, &destination, 0));
+    cluster.send_as(&creator, &[ix]).unwrap();
+    (pda::deposit(&creator), destination)
+}
+
+#[test]
+fn anyone_with_the_secret_withdraws_to_the_committed_destination() {
+    let Some(mut cluster) = galaxy() else { return };
+    let (address, destination) = note_deposit(&mut cluster, SOL);
+    let deposit: Deposit = cluster.account(&address).unwrap().unwrap();
+    assert_eq!(deposit.dm, DESTINATION_NOTE);
+    assert_eq!(deposit.destination, Pubkey::default());
+
+    //A fresh wallet the creator never touched redeems the note, paying its own fee
+    let holder = Pubkey::new_unique();
+    cluster.airdrop(&holder, SOL);
+    cluster.set_unix_timestamp(START + 1_000);
+    let ix = instructions::withdraw_note(&address, PLANETS[0], &destination, SECRET, 0);
+    cluster.send_as(&holder, &[ix]).unwrap();
+    assert_eq!(cluster.balance(&destination), SOL);
+    assert!(cluster.account::<Deposit>(&address).unwrap().is_none());
+}
+
+#[test]
+fn front_runners_cannot_redirect_a_note() {
+    let Some(mut cluster) = galaxy() else { return };
+    let (address, destination) = note_deposit(&mut cluster, SOL);
+    cluster.set_unix_timestamp(START + 1_000);
+
+    //The secret seen in a pending withdrawal is useless with another destination
+    let thief = Pubkey::new_unique();
+    let stolen = instructions::withdraw_note(&address, PLANETS[0], &thief, SECRET, 0);
+    assert!(cluster.send(&[stolen]).is_err());
+    assert_eq!(cluster.balance(&thief), 0);
+
+    let ix = instructions::withdraw_note(&address, PLANETS[0], &destination, SECRET, 0);
+    cluster.send(&[ix]).unwrap();
+    assert_eq!(cluster.balance(&destination), SOL);
+}
+
+#[test]
+fn note_needs_the_preimage_and_a_due_deposit() {
+    let Some(mut cluster) = galaxy() else { return };
+    let (address, destination) = note_deposit(&mut cluster, SOL);
+
+    let early = instructions::withdraw_note(&address, PLANETS[0], &destination, SECRET, 0);
+    assert!(cluster.send(&[early]).is_err());
+
+    cluster.set_unix_timestamp(START + 1_000);
+    let hash_itself = instructions::withdraw_note(&address, PLANETS[0], &destination, get_note_hash(&SECRET, &destination, 0), 0);
+    assert!(cluster.send(&[hash_itself]).is_err());
+    let wrong = instructions::withdraw_note(&address, PLANETS[0], &destination, [43; 32], 0);
+    assert!(cluster.send(&[wrong]).is_err());
+
+    //Neither the plain nor the committed withdraw accept a note deposit
+    let plain = instructions::withdraw(&MANAGER_PUBKEY, &address, PLANETS[0], &Pubkey::default());
+    assert!(cluster.send(&[plain]).is_err());
+    let committed = instructions::withdraw_committed(&MANAGER_PUBKEY, &address, PLANETS[0], &destination, None, SECRET);
+    assert!(cluster.send(&[committed]).is_err());
+
+    let ix = instructions::withdraw_note(&address, PLANETS[0], &destination, SECRET, 0);
+    cluster.send(&[ix]).unwrap();
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use oridion::accounts_comet::{Deposit, RoutePolicy};
+use oridion::accounts_universe::Universe;
+use oridion::shared::{check_recent_planets, get_recent_planets, get_route_candidates, push_recent_planet};
+use oridion::variables::{ACCOUNT_VERSION, MANAGER_PUBKEY, MAX_RECENT_PLANETS, NATIVE_SOL};
+use oridion_client::crank::{star_ids, MODE_MANUAL};
+use oridion_client::svm::SvmCluster;
+use oridion_client::{instructions, pda, Cluster};
+use common::{galaxy_with, FOUR_PLANETS as PLANETS, SOL, START};
+
+/// Manual deposit on the first planet.
+fn deposit(cluster: &mut SvmCluster) -> Pubkey {
+    let creator = Pubkey::new_unique();
+    cluster.airdrop(&creator, 2 * SOL);
+    let ix = instructions::create_deposit(&creator, PLANETS[0], &Pubkey::new_unique(), SOL, MODE_MANUAL, 0, START);
+    cluster.send_as(&creator, &[ix]).unwrap();
+    pda::deposit(&creator)
+}
+
+fn hop(cluster: &mut SvmCluster, address: &Pubkey, to: u16) -> bool {
+    let deposit: Deposit = cluster.account(address).unwrap().unwrap();
+    cluster.send(&[instructions::planet_hop(&MANAGER_PUBKEY, address, deposit.location, to)]).is_ok()
+}
+
+#[test]
+fn recent_planets_keep_the_window_newest_first() {
+    let mut deposit = Deposit {
+        version: ACCOUNT_VERSION,
+        mode: MODE_MANUAL,
+        next_process_at: START,
+        next_process: 0,
+        last_process: 1,
+        location: 1,
+        delay: 0,
+        hops: 2,
+        withdraw_at: START,
+        created_at: START,
+        last_process_at: START,
+        lamports: SOL,
+        destination: Pubkey::new_unique(),
+        mint: NATIVE_SOL,
+        sm: 0,
+        splits: Vec::new(),
+        tranches: 0,
+        spacing_min: 0,
+        spacing_max: 0,
+        dm: 0,
+        commitment: [0; 32],
+        route: RoutePolicy::default(),
+        star_hops: 0,
+        fees: 0,
+        visited: 0,
+        recent: [0; MAX_RECENT_PLANETS],
+        rn: 0,
+        stars: 0,
+    };
+    for to in [2, 3, 4] {
+        push_recent_planet(&mut deposit, 2);
+        deposit.location = to;
+    }
+    assert_eq!(get_recent_planets(&deposit), &[3, 2]);
+    assert_eq!(get_route_candidates(&PLANETS, &deposit), vec![1]);
+    assert!(check_recent_planets(&deposit, 3, 2).is_err());
+    assert!(check_recent_planets(&deposit, 2, 1).is_ok());
+    assert!(check_recent_planets(&deposit, 3, 0).is_ok());
+
+    //A wider window only counts hops made since it was set
+    push_recent_planet(&mut deposit, MAX_RECENT_PLANETS as u8);
+    assert_eq!(get_recent_planets(&deposit), &[4, 3, 2]);
+}
+
+#[test]
+fn hops_cannot_return_within_the_window() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    let address = deposit(&mut cluster);
+    cluster.send(&[instructions::update_recent_window(&MANAGER_PUBKEY, 1)]).unwrap();
+    let universe: Universe = cluster.account(&pda::universe()).unwrap().unwrap();
+    assert_eq!(universe.rw, 1);
+
+    assert!(hop(&mut cluster, &address, PLANETS[1]));
+    assert!(!hop(&mut cluster, &address, PLANETS[0]));
+    assert!(hop(&mut cluster, &address, PLANETS[2]));
+    assert!(hop(&mut cluster, &address, PLANETS[0]));
+
+    //Star hops land under the same window
+    let deposit: Deposit = cluster.account(&address).unwrap().unwrap();
+    let stars = star_ids(b"salt", &address, deposit.hops, 2);
+    let start = instructions::star_hop_two_start(&MANAGER_PUBKEY, &address, PLANETS[0], [&stars[0], &stars[1]]);
+    cluster.send(&[start]).unwrap();
+    let back = instructions::star_hop_two_end(&MANAGER_PUBKEY, &address, PLANETS[2], [&stars[0], &stars[1]]);
+    assert!(cluster.send(&[back]).is_err());
+    let end = instructions::star_hop_two_end(&MANAGER_PUBKEY, &address, PLANETS[3], [&stars[0], &stars[1]]);
+    cluster.send(&[end]).unwrap();
+}
+
+#[test]
+fn no_window_allows_bouncing_back() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    let address = deposit(&mut cluster);
+    assert!(hop(&mut cluster, &address, PLANETS[1]));
+    assert!(hop(&mut cluster, &address, PLANETS[0]));
+}
+
+#[test]
+fn only_the_manager_sets_a_window_within_bounds() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    let stranger = Pubkey::new_unique();
+    cluster.airdrop(&stranger, SOL);
+    assert!(cluster.send_as(&stranger, &[instructions::update_recent_window(&stranger, 1)]).is_err());
+    let wide = instructions::update_recent_window(&MANAGER_PUBKEY, MAX_RECENT_PLANETS as u8 + 1);
+    assert!(cluster.send(&[wide]).is_err());
+
+    //With four planets a window of three would leave a deposit nowhere to go
+    assert!(cluster.send(&[instructions::update_recent_window(&MANAGER_PUBKEY, 3)]).is_err());
+    cluster.send(&[instructions::update_recent_window(&MANAGER_PUBKEY, 2)]).unwrap();
+}
+
+#[test]
+fn planets_cannot_be_deleted_below_the_window() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    cluster.send(&[instructions::update_recent_window(&MANAGER_PUBKEY, 2)]).unwrap();
+
+    //Three planets would leave a deposit nowhere to go
+    assert!(cluster.send(&[instructions::delete_planet(&MANAGER_PUBKEY, PLANETS[3], &[])]).is_err());
+    cluster.send(&[instructions::update_recent_window(&MANAGER_PUBKEY, 1)]).unwrap();
+    cluster.send(&[instructions::delete_planet(&MANAGER_PUBKEY, PLANETS[3], &[])]).unwrap();
+}
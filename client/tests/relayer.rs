@@ -0,0 +1,142 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use oridion::accounts_comet::Deposit;
+use oridion::accounts_relayer::RelayerRegistry;
+use oridion::shared::{get_destination_commitment, get_note_hash};
+use oridion::variables::MANAGER_PUBKEY;
+use oridion_client::crank::MODE_MANUAL;
+use oridion_client::svm::SvmCluster;
+use oridion_client::{instructions, pda, Cluster};
+use common::{PLANETS, SOL, START};
+
+const MAX_FEE: u64 = 10_000_000;
+const SECRET: [u8; 32] = [9; 32];
+
+/// Universe with three planets and one registered relayer.
+fn galaxy(relayer: &Pubkey) -> Option<SvmCluster> {
+    let mut cluster = common::galaxy()?;
+    cluster.send(&[instructions::create_relayer_registry(&MANAGER_PUBKEY, MAX_FEE)]).unwrap();
+    cluster.send(&[instructions::add_relayer(&MANAGER_PUBKEY, relayer)]).unwrap();
+    cluster.airdrop(relayer, SOL);
+    cluster.set_unix_timestamp(START + 1_000);
+    Some(cluster)
+}
+
+/// Note deposit of `lamports` committing to a fresh destination and a MAX_FEE relay.
+fn note_deposit(cluster: &mut SvmCluster, lamports: u64) -> (Pubkey, Pubkey) {
+    let creator = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    cluster.airdrop(&creator, lamports + SOL);
+    let ix = instructions::create_note_deposit(&creator, PLANETS[0], lamports, MODE_MANUAL, 0, START, get_note_hash(&SECRET, &destination, MAX_FEE));
+    cluster.send_as(&creator, &[ix]).unwrap();
+    (pda::deposit(&creator), destination)
+}
+
+#[test]
+fn relayer_is_paid_out_of_a_note_withdrawal() {
+    let relayer = Pubkey::new_unique();
+    let Some(mut cluster) = galaxy(&relayer) else { return };
+    let (address, destination) = note_deposit(&mut cluster, SOL);
+    let relayer_before = cluster.balance(&relayer);
+
+    let ix = instructions::relay_withdraw(&relayer, &address, PLANETS[0], &destination, SECRET, MAX_FEE);
+    cluster.send_as(&relayer, &[ix]).unwrap();
+    assert_eq!(cluster.balance(&destination), SOL - MAX_FEE);
+    assert_eq!(cluster.balance(&relayer), relayer_before + MAX_FEE - 5_000);
+    assert!(cluster.account::<Deposit>(&address).unwrap().is_none());
+}
+
+#[test]
+fn relayers_take_the_note_fee_to_the_note_destination() {
+    let relayer = Pubkey::new_unique();
+    let Some(mut cluster) = galaxy(&relayer) else { return };
+    let (address, destination) = note_deposit(&mut cluster, SOL);
+
+    //Neither another destination nor another fee opens the note, even below the cap
+    let elsewhere = instructions::relay_withdraw(&relayer, &address, PLANETS[0], &Pubkey::new_unique(), SECRET, MAX_FEE);
+    assert!(cluster.send_as(&relayer, &[elsewhere]).is_err());
+    let cheaper = instructions::relay_withdraw(&relayer, &address, PLANETS[0], &destination, SECRET, MAX_FEE - 1);
+    assert!(cluster.send_as(&relayer, &[cheaper]).is_err());
+
+    //A note committing to a smaller fee caps the relayer below the registry
+    let (address, destination) = {
+        let creator = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        cluster.airdrop(&creator, 2 * SOL);
+        let ix = instructions::create_note_deposit(&creator, PLANETS[0], SOL, MODE_MANUAL, 0, START, get_note_hash(&SECRET, &destination, 1_000));
+        cluster.send_as(&creator, &[ix]).unwrap();
+        (pda::deposit(&creator), destination)
+    };
+    let greedy = instructions::relay_withdraw(&relayer, &address, PLANETS[0], &destination, SECRET, MAX_FEE);
+    assert!(cluster.send_as(&relayer, &[greedy]).is_err());
+    let ix = instructions::relay_withdraw(&relayer, &address, PLANETS[0], &destination, SECRET, 1_000);
+    cluster.send_as(&relayer, &[ix]).unwrap();
+    assert_eq!(cluster.balance(&destination), SOL - 1_000);
+}
+
+#[test]
+fn relayer_reveals_a_committed_destination() {
+    let relayer = Pubkey::new_unique();
+    let Some(mut cluster) = galaxy(&relayer) else { return };
+    let creator = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    cluster.airdrop(&creator, 2 * SOL);
+    let commitment = get_destination_commitment(&destination, &SECRET);
+    let ix = instructions::create_committed_deposit(&creator, PLANETS[0], SOL, MODE_MANUAL, 0, START, commitment);
+    cluster.send_as(&creator, &[ix]).unwrap();
+    let address = pda::deposit(&creator);
+
+    let elsewhere = instructions::relay_withdraw(&relayer, &address, PLANETS[0], &Pubkey::new_unique(), SECRET, 0);
+    assert!(cluster.send_as(&relayer, &[elsewhere]).is_err());
+    let ix = instructions::relay_withdraw(&relayer, &address, PLANETS[0], &destination, SECRET, 1_000);
+    cluster.send_as(&relayer, &[ix]).unwrap();
+    assert_eq!(cluster.balance(&destination), SOL - 1_000);
+}
+
+#[test]
+fn only_registered_relayers_within_the_fee_cap() {
+    let relayer = Pubkey::new_unique();
+    let Some(mut cluster) = galaxy(&relayer) else { return };
+    let (address, destination) = note_deposit(&mut cluster, SOL);
+
+    let greedy = instructions::relay_withdraw(&relayer, &address, PLANETS[0], &destination, SECRET, MAX_FEE + 1);
+    assert!(cluster.send_as(&relayer, &[greedy]).is_err());
+
+    let stranger = Pubkey::new_unique();
+    cluster.airdrop(&stranger, SOL);
+    let unregistered = instructions::relay_withdraw(&stranger, &address, PLANETS[0], &destination, SECRET, MAX_FEE);
+    assert!(cluster.send_as(&stranger, &[unregistered]).is_err());
+
+    //Plain deposits are never relayed
+    let creator = Pubkey::new_unique();
+    cluster.airdrop(&creator, 2 * SOL);
+    let plain = instructions::create_deposit(&creator, PLANETS[1], &destination, SOL, MODE_MANUAL, 0, START);
+    cluster.send_as(&creator, &[plain]).unwrap();
+    let ix = instructions::relay_withdraw(&relayer, &pda::deposit(&creator), PLANETS[1], &destination, SECRET, 0);
+    assert!(cluster.send_as(&relayer, &[ix]).is_err());
+
+    //Removed relayers are refused
+    cluster.send(&[instructions::remove_relayer(&MANAGER_PUBKEY, &relayer)]).unwrap();
+    let registry: RelayerRegistry = cluster.account(&pda::relayer_registry()).unwrap().unwrap();
+    assert!(registry.relayers.is_empty());
+    let removed = instructions::relay_withdraw(&relayer, &address, PLANETS[0], &destination, SECRET, MAX_FEE);
+    assert!(cluster.send_as(&relayer, &[removed]).is_err());
+}
+
+#[test]
+fn registry_is_curated_by_the_manager_only() {
+    let relayer = Pubkey::new_unique();
+    let Some(mut cluster) = galaxy(&relayer) else { return };
+    assert!(cluster.send(&[instructions::add_relayer(&MANAGER_PUBKEY, &relayer)]).is_err());
+
+    let outsider = Pubkey::new_unique();
+    cluster.airdrop(&outsider, SOL);
+    assert!(cluster.send_as(&outsider, &[instructions::add_relayer(&outsider, &outsider)]).is_err());
+    assert!(cluster.send_as(&outsider, &[instructions::update_relayer_fee(&outsider, u64::MAX)]).is_err());
+
+    cluster.send(&[instructions::update_relayer_fee(&MANAGER_PUBKEY, 1)]).unwrap();
+    let registry: RelayerRegistry = cluster.account(&pda::relayer_registry()).unwrap().unwrap();
+    assert_eq!(registry.max_fee, 1);
+    assert_eq!(registry.relayers, vec![relayer]);
+}
@@ -0,0 +1,243 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use oridion::accounts_comet::{Deposit, RoutePolicy};
+use oridion::shared::{get_route_candidates, get_route_hops, is_route_complete, push_visited_planet, select_next_planet, validate_route_policy};
+use oridion::variables::{ACCOUNT_VERSION, MANAGER_PUBKEY, MAX_PLANETS, MAX_RECENT_PLANETS, NATIVE_SOL};
+use oridion_client::crank::{star_ids, MODE_DELAY, MODE_MANUAL};
+use oridion_client::svm::SvmCluster;
+use oridion_client::{instructions, pda, Cluster, Crank, CrankConfig};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use common::{galaxy_with, FOUR_PLANETS as PLANETS, SOL, START};
+
+/// Deposit on the first planet following `policy`, with its destination.
+fn deposit(cluster: &mut SvmCluster, mode: u8, withdraw_at: i64, policy: RoutePolicy) -> (Pubkey, Pubkey) {
+    let creator = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    cluster.airdrop(&creator, 2 * SOL);
+    let ixs = [
+        instructions::create_deposit(&creator, PLANETS[0], &destination, SOL, mode, 0, withdraw_at),
+        instructions::set_route_policy(&creator, None, policy),
+    ];
+    cluster.send_as(&creator, &ixs).unwrap();
+    (pda::deposit(&creator), destination)
+}
+
+fn read(cluster: &SvmCluster, address: &Pubkey) -> Deposit {
+    cluster.account(address).unwrap().unwrap()
+}
+
+fn hop(cluster: &mut SvmCluster, address: &Pubkey, to: u16) -> bool {
+    let from = read(cluster, address).location;
+    cluster.send(&[instructions::planet_hop(&MANAGER_PUBKEY, address, from, to)]).is_ok()
+}
+
+#[test]
+fn policies_must_be_reachable() {
+    let planets = PLANETS.len();
+    assert!(validate_route_policy(&RoutePolicy::default(), planets).is_ok());
+    assert!(validate_route_policy(&RoutePolicy { min_hops: 5, max_hops: 8, min_stars: 2, ..RoutePolicy::default() }, planets).is_ok());
+    assert!(validate_route_policy(&RoutePolicy { min_hops: 5, max_hops: 4, ..RoutePolicy::default() }, planets).is_err());
+    assert!(validate_route_policy(&RoutePolicy { min_stars: 3, max_hops: 2, ..RoutePolicy::default() }, planets).is_err());
+
+    //Without revisits every hop needs a planet it has not been on yet
+    let policy = |min_hops: u16| RoutePolicy { min_hops, avoid_revisit: true, ..RoutePolicy::default() };
+    assert!(validate_route_policy(&policy(3), planets).is_ok());
+    assert!(validate_route_policy(&policy(4), planets).is_err());
+    assert!(validate_route_policy(&policy(5), planets).is_err());
+    assert!(validate_route_policy(&policy(5), MAX_PLANETS).is_ok());
+}
+
+fn landed_on(location: u16, visited: &[u16]) -> Deposit {
+    let mut deposit = Deposit {
+        version: ACCOUNT_VERSION,
+        mode: MODE_MANUAL,
+        next_process_at: START,
+        next_process: 0,
+        last_process: 1,
+        location,
+        delay: 0,
+        hops: 2 + visited.len() as u16,
+        withdraw_at: START,
+        created_at: START,
+        last_process_at: START,
+        lamports: SOL,
+        destination: Pubkey::new_unique(),
+        mint: NATIVE_SOL,
+        sm: 0,
+        splits: Vec::new(),
+        tranches: 0,
+        spacing_min: 0,
+        spacing_max: 0,
+        dm: 0,
+        commitment: [0; 32],
+        route: RoutePolicy::default(),
+        star_hops: 0,
+        fees: 0,
+        visited: 0,
+        recent: [0; MAX_RECENT_PLANETS],
+        rn: 0,
+        stars: 0,
+    };
+    for id in visited.iter().chain([&location]) {
+        push_visited_planet(&mut deposit, *id);
+    }
+    deposit
+}
+
+#[test]
+fn visited_planets_are_no_candidates() {
+    let mut deposit = landed_on(2, &[1]);
+    assert_eq!(get_route_candidates(&PLANETS, &deposit), vec![1, 3, 4]);
+    deposit.route.avoid_revisit = true;
+    assert_eq!(get_route_candidates(&PLANETS, &deposit), vec![3, 4]);
+
+    //The visited bitmap has a bit for every planet id, the lowest and highest included
+    let last = MAX_PLANETS as u16 - 1;
+    let mut deposit = landed_on(0, &[last]);
+    deposit.route.avoid_revisit = true;
+    assert_eq!(get_route_candidates(&[0, 1, last - 1, last], &deposit), vec![1, last - 1]);
+}
+
+#[test]
+fn running_out_of_planets_completes_the_route() {
+    let mut deposit = landed_on(3, &[1, 2]);
+    deposit.route = RoutePolicy { min_hops: 3, avoid_revisit: true, ..RoutePolicy::default() };
+    assert!(!is_route_complete(&deposit, &PLANETS));
+    assert!(is_route_complete(&deposit, &PLANETS[..3]));
+
+    //Revisits allowed, the minimum still counts
+    deposit.route.avoid_revisit = false;
+    assert!(!is_route_complete(&deposit, &PLANETS[..3]));
+}
+
+#[test]
+fn withdraw_waits_for_the_minimum_hops() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    let policy = RoutePolicy { min_hops: 2, ..RoutePolicy::default() };
+    let (address, destination) = deposit(&mut cluster, MODE_MANUAL, START, policy);
+    let withdraw = |cluster: &mut SvmCluster| {
+        let from = read(cluster, &address).location;
+        cluster.send(&[instructions::withdraw(&MANAGER_PUBKEY, &address, from, &destination)]).is_ok()
+    };
+
+    assert!(!withdraw(&mut cluster));
+    assert!(hop(&mut cluster, &address, PLANETS[1]));
+    assert!(!withdraw(&mut cluster));
+    assert!(hop(&mut cluster, &address, PLANETS[2]));
+    assert!(withdraw(&mut cluster));
+    assert_eq!(cluster.balance(&destination), SOL);
+}
+
+#[test]
+fn hops_stop_at_the_maximum() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    let policy = RoutePolicy { max_hops: 1, ..RoutePolicy::default() };
+    let (address, _) = deposit(&mut cluster, MODE_MANUAL, START, policy);
+    assert!(hop(&mut cluster, &address, PLANETS[1]));
+    assert!(!hop(&mut cluster, &address, PLANETS[2]));
+
+    //Star hops cannot start either, they could not land
+    let stars = star_ids(b"salt", &address, read(&cluster, &address).hops, 2);
+    let ix = instructions::star_hop_two_start(&MANAGER_PUBKEY, &address, PLANETS[1], [&stars[0], &stars[1]]);
+    assert!(cluster.send(&[ix]).is_err());
+}
+
+#[test]
+fn planets_are_not_revisited() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    let policy = RoutePolicy { avoid_revisit: true, ..RoutePolicy::default() };
+    let (address, _) = deposit(&mut cluster, MODE_MANUAL, START, policy);
+    assert!(hop(&mut cluster, &address, PLANETS[1]));
+    assert!(!hop(&mut cluster, &address, PLANETS[0]));
+    assert!(hop(&mut cluster, &address, PLANETS[2]));
+    assert!(!hop(&mut cluster, &address, PLANETS[1]));
+}
+
+#[test]
+fn withdraw_waits_for_the_minimum_star_hops() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    let policy = RoutePolicy { min_stars: 1, ..RoutePolicy::default() };
+    let (address, destination) = deposit(&mut cluster, MODE_MANUAL, START, policy);
+    assert!(hop(&mut cluster, &address, PLANETS[1]));
+    assert!(cluster.send(&[instructions::withdraw(&MANAGER_PUBKEY, &address, PLANETS[1], &destination)]).is_err());
+
+    let stars = star_ids(b"salt", &address, read(&cluster, &address).hops, 2);
+    cluster.send(&[instructions::star_hop_two_start(&MANAGER_PUBKEY, &address, PLANETS[1], [&stars[0], &stars[1]])]).unwrap();
+    cluster.send(&[instructions::star_hop_two_end(&MANAGER_PUBKEY, &address, PLANETS[2], [&stars[0], &stars[1]])]).unwrap();
+    assert_eq!(read(&cluster, &address).star_hops, 1);
+    cluster.send(&[instructions::withdraw(&MANAGER_PUBKEY, &address, PLANETS[2], &destination)]).unwrap();
+}
+
+#[test]
+fn keeper_hops_stay_within_the_fee_budget() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    let fee = 1_000_000; //Default hop planet fee
+    let policy = RoutePolicy { max_fees: fee, ..RoutePolicy::default() };
+    let (address, _) = deposit(&mut cluster, MODE_DELAY, START + 10_000, policy);
+    let keeper = Pubkey::new_unique();
+    cluster.airdrop(&keeper, SOL);
+
+    let mut now = START;
+    for hops in [1, 1] {
+        now += 200;
+        cluster.set_unix_timestamp(now);
+        let deposit = read(&cluster, &address);
+        let to = select_next_planet(&PLANETS, &address, &deposit).unwrap();
+        let _ = cluster.send_as(&keeper, &[instructions::keeper_hop(&keeper, &address, deposit.location, to)]);
+        assert_eq!(get_route_hops(&read(&cluster, &address)), hops);
+    }
+    assert_eq!(read(&cluster, &address).fees, fee);
+}
+
+#[test]
+fn crank_follows_the_route_policy() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    let policy = RoutePolicy { min_hops: 4, max_hops: 6, min_stars: 2, ..RoutePolicy::default() };
+    let (address, destination) = deposit(&mut cluster, MODE_DELAY, START + 100, policy);
+
+    let config = CrankConfig { salt: b"test salt".to_vec(), ..CrankConfig::default() };
+    let mut crank = Crank::new(cluster, StdRng::seed_from_u64(5), config);
+    let mut now = START;
+    let mut last = None;
+    while let Some(deposit) = crank.cluster().account::<Deposit>(&address).unwrap() {
+        last = Some(deposit);
+        now += 200;
+        crank.cluster_mut().set_unix_timestamp(now);
+        crank.tick().unwrap().into_iter().for_each(|(_, result)| { result.unwrap(); });
+    }
+    let last = last.unwrap();
+    assert!(get_route_hops(&last) >= 4);
+    assert!(last.star_hops >= 2);
+    assert_eq!(crank.cluster().balance(&destination), SOL);
+}
+
+#[test]
+fn policies_past_the_planet_count_are_rejected() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    let creator = Pubkey::new_unique();
+    cluster.airdrop(&creator, 2 * SOL);
+    let ix = instructions::create_deposit(&creator, PLANETS[0], &Pubkey::new_unique(), SOL, MODE_MANUAL, 0, START);
+    cluster.send_as(&creator, &[ix]).unwrap();
+
+    let policy = |min_hops: u16| RoutePolicy { min_hops, avoid_revisit: true, ..RoutePolicy::default() };
+    assert!(cluster.send_as(&creator, &[instructions::set_route_policy(&creator, None, policy(5))]).is_err());
+    assert!(cluster.send_as(&creator, &[instructions::set_route_policy(&creator, None, policy(4))]).is_err());
+    cluster.send_as(&creator, &[instructions::set_route_policy(&creator, None, policy(3))]).unwrap();
+}
+
+#[test]
+fn deposits_out_of_unvisited_planets_can_withdraw() {
+    let Some(mut cluster) = galaxy_with(&PLANETS) else { return };
+    let policy = RoutePolicy { min_hops: 3, avoid_revisit: true, ..RoutePolicy::default() };
+    let (address, destination) = deposit(&mut cluster, MODE_MANUAL, START, policy);
+    assert!(hop(&mut cluster, &address, PLANETS[1]));
+    assert!(hop(&mut cluster, &address, PLANETS[2]));
+    assert!(cluster.send(&[instructions::withdraw(&MANAGER_PUBKEY, &address, PLANETS[2], &destination)]).is_err());
+
+    //The last unvisited planet goes away before the third hop
+    cluster.send(&[instructions::delete_planet(&MANAGER_PUBKEY, PLANETS[3], &[])]).unwrap();
+    cluster.send(&[instructions::withdraw(&MANAGER_PUBKEY, &address, PLANETS[2], &destination)]).unwrap();
+    assert_eq!(cluster.balance(&destination), SOL);
+}
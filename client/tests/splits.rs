@@ -0,0 +1,106 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use oridion::accounts_comet::{Deposit, DestinationSplit};
+use oridion::variables::MANAGER_PUBKEY;
+use oridion_client::crank::MODE_MANUAL;
+use oridion_client::svm::SvmCluster;
+use oridion_client::{instructions, pda, Cluster};
+use common::{galaxy, PLANETS, SOL, START};
+
+fn split(bps: u16, min: u64) -> DestinationSplit {
+    DestinationSplit { destination: Pubkey::new_unique(), bps, min }
+}
+
+/// Manual deposit of `lamports` whose withdrawal is split `mode` over `splits`.
+fn split_deposit(cluster: &mut SvmCluster, lamports: u64, mode: u8, splits: &[DestinationSplit]) -> Pubkey {
+    let creator = Pubkey::new_unique();
+    cluster.airdrop(&creator, lamports + SOL);
+    let create = instructions::create_deposit(&creator, PLANETS[0], &Pubkey::new_unique(), lamports, MODE_MANUAL, 0, START + 1_000);
+    let set = instructions::set_deposit_splits(&creator, None, mode, splits);
+    cluster.send_as(&creator, &[create, set]).unwrap();
+    pda::deposit(&creator)
+}
+
+fn withdraw(cluster: &mut SvmCluster, address: &Pubkey) -> Result<(), oridion_client::ClientError> {
+    let deposit: Deposit = cluster.account(address).unwrap().unwrap();
+    let mut ix = instructions::withdraw(&MANAGER_PUBKEY, address, deposit.location, &deposit.destination);
+    instructions::add_split_destinations(&mut ix, &deposit);
+    cluster.send(&[ix]).map(|_| ())
+}
+
+#[test]
+fn basis_point_splits_pay_every_destination_its_share() {
+    let Some(mut cluster) = galaxy() else { return };
+    let splits = [split(5_000, 0), split(3_000, 0), split(2_000, 0)];
+    let address = split_deposit(&mut cluster, 3 * SOL, 1, &splits);
+    let deposit: Deposit = cluster.account(&address).unwrap().unwrap();
+    assert_eq!(deposit.destination, splits[0].destination);
+
+    withdraw(&mut cluster, &address).unwrap();
+    let paid: Vec<u64> = splits.iter().map(|split| cluster.balance(&split.destination)).collect();
+    assert_eq!(paid, vec![1_500_000_000, 900_000_000, 600_000_000]);
+}
+
+#[test]
+fn random_splits_respect_minimums_and_add_up() {
+    let Some(mut cluster) = galaxy() else { return };
+    let splits = [split(0, SOL / 2), split(0, SOL / 4), split(0, 0), split(0, SOL)];
+    let lamports = 5 * SOL;
+    let address = split_deposit(&mut cluster, lamports, 2, &splits);
+
+    withdraw(&mut cluster, &address).unwrap();
+    let paid: Vec<u64> = splits.iter().map(|split| cluster.balance(&split.destination)).collect();
+    assert!(paid.iter().zip(&splits).all(|(paid, split)| *paid >= split.min));
+    assert_eq!(paid.iter().sum::<u64>(), lamports);
+}
+
+#[test]
+fn withdraw_needs_every_split_destination() {
+    let Some(mut cluster) = galaxy() else { return };
+    let splits = [split(5_000, 0), split(5_000, 0)];
+    let address = split_deposit(&mut cluster, SOL, 1, &splits);
+    let deposit: Deposit = cluster.account(&address).unwrap().unwrap();
+
+    let missing = instructions::withdraw(&MANAGER_PUBKEY, &address, deposit.location, &deposit.destination);
+    assert!(cluster.send(&[missing]).is_err());
+
+    let mut wrong = instructions::withdraw(&MANAGER_PUBKEY, &address, deposit.location, &deposit.destination);
+    let mut impostor = deposit.clone();
+    impostor.splits[1].destination = Pubkey::new_unique();
+    instructions::add_split_destinations(&mut wrong, &impostor);
+    assert!(cluster.send(&[wrong]).is_err());
+    withdraw(&mut cluster, &address).unwrap();
+}
+
+#[test]
+fn invalid_splits_are_rejected() {
+    let Some(mut cluster) = galaxy() else { return };
+    let creator = Pubkey::new_unique();
+    cluster.airdrop(&creator, 3 * SOL);
+    let create = instructions::create_deposit(&creator, PLANETS[0], &Pubkey::new_unique(), SOL, MODE_MANUAL, 0, START + 1_000);
+    cluster.send_as(&creator, &[create]).unwrap();
+
+    let invalid: [(u8, Vec<DestinationSplit>); 5] = [
+        (1, vec![split(5_000, 0), split(4_000, 0)]), //Does not add up to 100%
+        (1, vec![split(10_000, 0)]), //Single destination
+        (1, (0..5).map(|_| split(2_000, 0)).collect()), //Too many destinations
+        (2, vec![split(0, SOL), split(0, 1)]), //Minimums above the deposit
+        (3, vec![split(5_000, 0), split(5_000, 0)]), //Unknown mode
+    ];
+    for (mode, splits) in invalid {
+        assert!(cluster.send_as(&creator, &[instructions::set_deposit_splits(&creator, None, mode, &splits)]).is_err());
+    }
+
+    //Only the creator, and only before the first hop
+    let other = Pubkey::new_unique();
+    cluster.airdrop(&other, SOL);
+    let splits = [split(5_000, 0), split(5_000, 0)];
+    let mut stolen = instructions::set_deposit_splits(&other, None, 1, &splits);
+    stolen.accounts[0].pubkey = pda::deposit(&creator);
+    assert!(cluster.send_as(&other, &[stolen]).is_err());
+
+    let hop = instructions::planet_hop(&MANAGER_PUBKEY, &pda::deposit(&creator), PLANETS[0], PLANETS[1]);
+    cluster.send(&[hop]).unwrap();
+    assert!(cluster.send_as(&creator, &[instructions::set_deposit_splits(&creator, None, 1, &splits)]).is_err());
+}
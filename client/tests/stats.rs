@@ -0,0 +1,133 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use oridion::accounts_comet::Deposit;
+use oridion::accounts_stats::GalaxyStats;
+use oridion::shared::get_keeper_reward;
+use oridion::variables::{MANAGER_PUBKEY, SECONDS_PER_DAY, STATS_DAYS};
+use oridion_client::crank::{MODE_DELAY, MODE_MANUAL};
+use oridion_client::svm::SvmCluster;
+use oridion_client::{instructions, pda, Cluster, Crank, CrankConfig};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use common::{galaxy, PLANETS, SOL, START};
+
+fn deposit(cluster: &mut SvmCluster, lamports: u64, mode: u8, withdraw_at: i64) -> Pubkey {
+    let creator = Pubkey::new_unique();
+    cluster.airdrop(&creator, lamports + SOL);
+    let ix = instructions::create_deposit(&creator, PLANETS[0], &Pubkey::new_unique(), lamports, mode, 0, withdraw_at);
+    cluster.send_as(&creator, &[ix]).unwrap();
+    pda::deposit(&creator)
+}
+
+/// Galaxy stats once the planet stats are folded in.
+fn stats<C: Cluster>(cluster: &mut C) -> GalaxyStats {
+    cluster.send(&[instructions::aggregate_stats(&PLANETS)]).unwrap();
+    cluster.account(&pda::galaxy_stats()).unwrap().unwrap()
+}
+
+fn day(unix_timestamp: i64) -> u32 {
+    (unix_timestamp / SECONDS_PER_DAY) as u32
+}
+
+#[test]
+fn stats_follow_a_deposit_from_creation_to_withdrawal() {
+    let Some(mut cluster) = galaxy() else { return };
+    let lamports = 2 * SOL;
+    let address = deposit(&mut cluster, lamports, MODE_DELAY, START + 1_000);
+    let created = stats(&mut cluster);
+    assert_eq!((created.deposits, created.volume, created.active), (1, lamports, 1));
+
+    let config = CrankConfig { salt: b"test salt".to_vec(), ..CrankConfig::default() };
+    let mut crank = Crank::new(cluster, StdRng::seed_from_u64(2), config);
+    let mut now = START;
+    let mut hops = 0;
+    while let Some(deposit) = crank.cluster().account::<Deposit>(&address).unwrap() {
+        hops = deposit.hops - 2;
+        now += 200;
+        crank.cluster_mut().set_unix_timestamp(now);
+        crank.tick().unwrap().into_iter().for_each(|(_, result)| { result.unwrap(); });
+    }
+
+    let done = stats(crank.cluster_mut());
+    assert_eq!(done.active, 0);
+    assert_eq!(done.withdrawals, 1);
+    assert_eq!((done.planet_hops + done.star_hops_two + done.star_hops_three) as u16, hops);
+    let today = done.days[day(START) as usize % STATS_DAYS];
+    assert_eq!(today.day, day(START));
+    assert_eq!((today.deposits, today.volume, today.withdrawals), (1, lamports, 1));
+    assert_eq!(today.planet_hops, done.planet_hops);
+    assert_eq!(today.star_hops_two, done.star_hops_two);
+    assert_eq!(today.star_hops_three, done.star_hops_three);
+}
+
+#[test]
+fn stats_are_bucketed_by_day() {
+    let Some(mut cluster) = galaxy() else { return };
+    deposit(&mut cluster, SOL, MODE_MANUAL, START + 1_000);
+    cluster.set_unix_timestamp(START + SECONDS_PER_DAY);
+    deposit(&mut cluster, 3 * SOL, MODE_MANUAL, START + 2 * SECONDS_PER_DAY);
+
+    let totals = stats(&mut cluster);
+    assert_eq!((totals.deposits, totals.volume, totals.active), (2, 4 * SOL, 2));
+    let first = totals.days[day(START) as usize % STATS_DAYS];
+    let second = totals.days[day(START + SECONDS_PER_DAY) as usize % STATS_DAYS];
+    assert_eq!((first.day, first.deposits, first.volume), (day(START), 1, SOL));
+    assert_eq!((second.day, second.deposits, second.volume), (day(START) + 1, 1, 3 * SOL));
+
+    //A bucket is reset when the ring comes back round to it
+    cluster.set_unix_timestamp(START + STATS_DAYS as i64 * SECONDS_PER_DAY);
+    deposit(&mut cluster, SOL, MODE_MANUAL, START + 100 * SECONDS_PER_DAY);
+    let wrapped = stats(&mut cluster).days[day(START) as usize % STATS_DAYS];
+    assert_eq!((wrapped.day, wrapped.deposits), (day(START) + STATS_DAYS as u32, 1));
+}
+
+#[test]
+fn keeper_fees_are_counted_for_the_manager_share() {
+    let Some(mut cluster) = galaxy() else { return };
+    let address = deposit(&mut cluster, SOL, MODE_DELAY, START + 10_000);
+    let keeper = Pubkey::new_unique();
+    cluster.airdrop(&keeper, SOL);
+    cluster.set_payer(keeper);
+
+    let config = CrankConfig { keeper: true, ..CrankConfig::default() };
+    let mut crank = Crank::new(cluster, StdRng::seed_from_u64(4), config);
+    crank.cluster_mut().set_unix_timestamp(START + 200);
+    crank.tick().unwrap().into_iter().for_each(|(_, result)| { result.unwrap(); });
+    assert!(crank.cluster().account::<Deposit>(&address).unwrap().unwrap().hops > 2);
+
+    let fee = 1_000_000; //Default hop planet fee
+    let stats = stats(crank.cluster_mut());
+    assert_eq!(stats.planet_hops, 1);
+    assert_eq!(stats.fees, fee - get_keeper_reward(fee));
+}
+
+#[test]
+fn deposits_on_different_planets_share_no_write_locks() {
+    let [first, second] = [(Pubkey::new_unique(), PLANETS[0]), (Pubkey::new_unique(), PLANETS[1])]
+        .map(|(creator, planet)| instructions::create_deposit(&creator, planet, &Pubkey::new_unique(), SOL, MODE_MANUAL, 0, START));
+    let shared: Vec<Pubkey> = first.accounts.iter()
+        .filter(|meta| meta.is_writable && second.accounts.iter().any(|other| other.is_writable && other.pubkey == meta.pubkey))
+        .map(|meta| meta.pubkey)
+        .collect();
+    assert!(shared.is_empty(), "shared write locks: {shared:?}");
+}
+
+#[test]
+fn deleted_planets_keep_their_counts() {
+    let Some(mut cluster) = galaxy() else { return };
+    deposit(&mut cluster, SOL, MODE_MANUAL, START + 1_000);
+    let id = 9;
+    cluster.send(&[instructions::create_planet(&MANAGER_PUBKEY, id, "short lived", &[])]).unwrap();
+    let creator = Pubkey::new_unique();
+    cluster.airdrop(&creator, 2 * SOL);
+    let address = pda::deposit(&creator);
+    let destination = Pubkey::new_unique();
+    cluster.send_as(&creator, &[instructions::create_deposit(&creator, id, &destination, SOL, MODE_MANUAL, 0, START)]).unwrap();
+    cluster.send(&[instructions::withdraw(&MANAGER_PUBKEY, &address, id, &destination)]).unwrap();
+    cluster.send(&[instructions::delete_planet(&MANAGER_PUBKEY, id, &[])]).unwrap();
+    assert!(cluster.data_len(&pda::planet_stats(id)).is_none());
+
+    let totals = stats(&mut cluster);
+    assert_eq!((totals.deposits, totals.volume, totals.active, totals.withdrawals), (2, 2 * SOL, 1, 1));
+}
@@ -0,0 +1,259 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token_2022::spl_token_2022;
+use oridion::accounts_comet::Deposit;
+use oridion::accounts_stats::PlanetStats;
+use oridion::accounts_token::MintConfig;
+use oridion::shared::select_next_planet;
+use oridion::variables::MANAGER_PUBKEY;
+use oridion_client::crank::{star_ids, HOP_DESTINATION_PROGRAM, MODE_DELAY};
+use oridion_client::svm::{MintExtension, SvmCluster};
+use oridion_client::instructions::{MintFees, UniverseFees};
+use oridion_client::{instructions, pda, ClientError, Cluster, Crank, CrankConfig};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use common::{universe, PLANETS, START};
+
+const FEE_BPS: u16 = 100; //1% transfer fee
+const MIN_AMOUNT: u64 = 1_000;
+const MAX_AMOUNT: u64 = 100_000_000;
+
+/// Three planets serving an allowed Token-2022 mint with `extensions`.
+fn galaxy(mint: &Pubkey, extensions: &[MintExtension], fees: MintFees) -> Option<SvmCluster> {
+    let mut cluster = universe()?;
+    cluster.create_token_2022_mint(mint, 6, extensions);
+    cluster.send(&[instructions::create_mint_config(&MANAGER_PUBKEY, mint, MIN_AMOUNT, MAX_AMOUNT, fees)]).unwrap();
+    for id in PLANETS {
+        cluster.send(&[instructions::create_planet(&MANAGER_PUBKEY, id, &format!("planet {id}"), &[*mint])]).unwrap();
+        let ix = instructions::create_planet_token_account(&MANAGER_PUBKEY, id, mint, &spl_token_2022::ID);
+        cluster.send(&[ix]).unwrap();
+    }
+    Some(cluster)
+}
+
+fn fee_mint() -> [MintExtension; 1] {
+    [MintExtension::TransferFee { basis_points: FEE_BPS, maximum_fee: u64::MAX }]
+}
+
+fn fee(amount: u64) -> u64 {
+    (amount * FEE_BPS as u64).div_ceil(10_000)
+}
+
+fn try_token_deposit(cluster: &mut SvmCluster, planet: u16, mint: &Pubkey, amount: u64) -> Result<(Pubkey, Pubkey), ClientError> {
+    let creator = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    cluster.airdrop(&creator, 1_000_000_000);
+    cluster.mint_to(&creator, mint, amount);
+    let ix = instructions::create_token_deposit(&creator, planet, mint, &spl_token_2022::ID, &destination, amount, MODE_DELAY, 0, START + 1_000);
+    cluster.send_as(&creator, &[ix])?;
+    Ok((pda::deposit(&creator), destination))
+}
+
+fn token_deposit(cluster: &mut SvmCluster, mint: &Pubkey, amount: u64) -> (Pubkey, Pubkey) {
+    try_token_deposit(cluster, PLANETS[0], mint, amount).unwrap()
+}
+
+#[test]
+fn transfer_fee_deposit_tracks_what_landed_at_every_hop() {
+    let mint = Pubkey::new_unique();
+    let Some(mut cluster) = galaxy(&mint, &fee_mint(), MintFees::default()) else { return };
+    let amount = 10_000_000;
+    let (address, destination) = token_deposit(&mut cluster, &mint, amount);
+
+    let deposit: Deposit = cluster.account(&address).unwrap().unwrap();
+    assert_eq!(deposit.lamports, amount - fee(amount));
+    assert_eq!(cluster.token_balance(&pda::planet(PLANETS[0]), &mint), deposit.lamports);
+
+    let config = CrankConfig { salt: b"test salt".to_vec(), ..CrankConfig::default() };
+    let mut crank = Crank::new(cluster, StdRng::seed_from_u64(5), config);
+    let mut now = START;
+    let mut last = amount - fee(amount);
+    loop {
+        now += 200;
+        assert!(now < START + 5_000, "deposit was never withdrawn");
+        crank.cluster_mut().set_unix_timestamp(now);
+        crank.tick().unwrap().into_iter().for_each(|(_, result)| { result.unwrap(); });
+        let Some(deposit) = crank.cluster().account::<Deposit>(&address).unwrap() else { break };
+        //Every hop loses its transfer fee and the planet holds exactly the deposit
+        assert!(deposit.lamports <= last);
+        assert_eq!(crank.cluster().token_balance(&pda::planet(deposit.location), &mint), deposit.lamports);
+        last = deposit.lamports;
+    }
+    assert_eq!(crank.cluster().token_balance(&destination, &mint), last - fee(last));
+}
+
+#[test]
+fn star_token_accounts_close_after_fees_are_harvested() {
+    let mint = Pubkey::new_unique();
+    let Some(mut cluster) = galaxy(&mint, &fee_mint(), MintFees::default()) else { return };
+    let (address, _) = token_deposit(&mut cluster, &mint, 10_000_000);
+
+    let stars = star_ids(b"salt", &address, 2, 3);
+    let stars = [stars[0].as_str(), stars[1].as_str(), stars[2].as_str()];
+    let program = spl_token_2022::ID;
+    let start = instructions::token_star_hop_three_start(&MANAGER_PUBKEY, &address, &mint, &program, PLANETS[0], stars);
+    cluster.send(&[start]).unwrap();
+    let held: Deposit = cluster.account(&address).unwrap().unwrap();
+    assert!(cluster.withheld_fees(&pda::star(stars[0]), &mint) > 0);
+
+    let end = instructions::token_star_hop_three_end(&MANAGER_PUBKEY, &address, &mint, &program, PLANETS[1], stars);
+    cluster.send(&[end]).unwrap();
+    let deposit: Deposit = cluster.account(&address).unwrap().unwrap();
+    assert!(deposit.lamports < held.lamports);
+    assert_eq!(cluster.token_balance(&pda::planet(PLANETS[1]), &mint), deposit.lamports);
+    for star in stars {
+        assert_eq!(cluster.balance(&pda::token_account(&pda::star(star), &mint, &program)), 0);
+    }
+}
+
+#[test]
+fn mints_with_unsupported_extensions_are_rejected() {
+    let Some(mut cluster) = universe() else { return };
+    cluster.send(&[instructions::create_planet(&MANAGER_PUBKEY, PLANETS[0], "planet 1", &[])]).unwrap();
+    let unsupported = [
+        MintExtension::PermanentDelegate(Pubkey::new_unique()),
+        MintExtension::NonTransferable,
+        MintExtension::MintCloseAuthority(Pubkey::new_unique()),
+        MintExtension::DefaultAccountState,
+    ];
+    for extension in unsupported {
+        let mint = Pubkey::new_unique();
+        cluster.create_token_2022_mint(&mint, 6, &[extension]);
+        let ix = instructions::create_mint_config(&MANAGER_PUBKEY, &mint, MIN_AMOUNT, MAX_AMOUNT, MintFees::default());
+        assert!(cluster.send(&[ix]).is_err());
+
+        //Associated token accounts can be created by anyone, the deposit still checks the mint
+        cluster.mint_to(&pda::planet(PLANETS[0]), &mint, 0);
+        assert!(try_token_deposit(&mut cluster, PLANETS[0], &mint, 1_000_000).is_err());
+    }
+}
+
+#[test]
+fn mints_with_a_freeze_authority_are_rejected() {
+    let Some(mut cluster) = universe() else { return };
+    let (classic, token_2022) = (Pubkey::new_unique(), Pubkey::new_unique());
+    cluster.create_mint(&classic, 6);
+    cluster.create_token_2022_mint(&token_2022, 6, &[]);
+    for mint in [classic, token_2022] {
+        cluster.set_freeze_authority(&mint, &Pubkey::new_unique());
+        let ix = instructions::create_mint_config(&MANAGER_PUBKEY, &mint, MIN_AMOUNT, MAX_AMOUNT, MintFees::default());
+        assert!(cluster.send(&[ix]).is_err());
+    }
+}
+
+#[test]
+fn deposits_must_fit_the_mint_limits() {
+    let mint = Pubkey::new_unique();
+    let Some(mut cluster) = galaxy(&mint, &[], MintFees::default()) else { return };
+    assert!(try_token_deposit(&mut cluster, PLANETS[0], &mint, MIN_AMOUNT - 1).is_err());
+    assert!(try_token_deposit(&mut cluster, PLANETS[0], &mint, MAX_AMOUNT + 1).is_err());
+    try_token_deposit(&mut cluster, PLANETS[0], &mint, MAX_AMOUNT).unwrap();
+}
+
+#[test]
+fn planets_only_take_deposits_of_mints_they_serve() {
+    let mint = Pubkey::new_unique();
+    let Some(mut cluster) = galaxy(&mint, &[], MintFees::default()) else { return };
+
+    //Mints without a config cannot be attached
+    let unlisted = Pubkey::new_unique();
+    cluster.create_token_2022_mint(&unlisted, 6, &[]);
+    assert!(cluster.send(&[instructions::create_planet(&MANAGER_PUBKEY, 4, "mars", &[unlisted])]).is_err());
+
+    cluster.send(&[instructions::create_planet(&MANAGER_PUBKEY, 5, "pluto", &[])]).unwrap();
+    let ix = instructions::create_planet_token_account(&MANAGER_PUBKEY, 5, &mint, &spl_token_2022::ID);
+    assert!(cluster.send(&[ix]).is_err());
+    cluster.mint_to(&pda::planet(5), &mint, 0);
+    assert!(try_token_deposit(&mut cluster, 5, &mint, MIN_AMOUNT).is_err());
+
+    //Nor can deposits hop onto it
+    let (address, _) = token_deposit(&mut cluster, &mint, MIN_AMOUNT);
+    assert!(cluster.send(&[instructions::token_planet_hop(&MANAGER_PUBKEY, &address, &mint, &spl_token_2022::ID, PLANETS[0], 5)]).is_err());
+}
+
+#[test]
+fn mint_configs_of_served_mints_cannot_be_deleted() {
+    let mint = Pubkey::new_unique();
+    let Some(mut cluster) = galaxy(&mint, &[], MintFees::default()) else { return };
+    cluster.send(&[instructions::create_planet(&MANAGER_PUBKEY, 4, "mars", &[mint])]).unwrap();
+    assert!(cluster.send(&[instructions::delete_mint_config(&MANAGER_PUBKEY, &mint)]).is_err());
+
+    //Planets count out of the mint as they are deleted
+    for id in PLANETS.into_iter().chain([4]) {
+        assert!(cluster.send(&[instructions::delete_planet(&MANAGER_PUBKEY, id, &[])]).is_err());
+        cluster.send(&[instructions::delete_planet(&MANAGER_PUBKEY, id, &[mint])]).unwrap();
+    }
+    cluster.send(&[instructions::delete_mint_config(&MANAGER_PUBKEY, &mint)]).unwrap();
+    assert!(try_token_deposit(&mut cluster, PLANETS[0], &mint, MIN_AMOUNT).is_err());
+}
+
+#[test]
+fn mint_hop_and_withdraw_fees_go_to_the_manager() {
+    let mint = Pubkey::new_unique();
+    let (hop_fee, withdraw_fee) = (1_000, 2_000);
+    let fees = MintFees { hpfe: Some(hop_fee), wfe: Some(withdraw_fee) };
+    let Some(mut cluster) = galaxy(&mint, &[], fees) else { return };
+    let amount = 1_000_000;
+    let (address, destination) = token_deposit(&mut cluster, &mint, amount);
+    let program = spl_token_2022::ID;
+
+    cluster.send(&[instructions::token_planet_hop(&MANAGER_PUBKEY, &address, &mint, &program, PLANETS[0], PLANETS[1])]).unwrap();
+    let deposit: Deposit = cluster.account(&address).unwrap().unwrap();
+    assert_eq!(deposit.lamports, amount - hop_fee);
+    assert_eq!(deposit.fees, hop_fee);
+    assert_eq!(cluster.token_balance(&pda::planet(PLANETS[1]), &mint), deposit.lamports);
+    assert_eq!(cluster.token_balance(&MANAGER_PUBKEY, &mint), hop_fee);
+
+    cluster.set_unix_timestamp(START + 1_000);
+    cluster.send(&[instructions::token_withdraw(&MANAGER_PUBKEY, &address, &mint, &program, PLANETS[1], &destination)]).unwrap();
+    assert_eq!(cluster.token_balance(&destination, &mint), amount - hop_fee - withdraw_fee);
+    assert_eq!(cluster.token_balance(&MANAGER_PUBKEY, &mint), hop_fee + withdraw_fee);
+}
+
+#[test]
+fn token_deposits_charge_no_comet_fee_like_sol_deposits() {
+    let mint = Pubkey::new_unique();
+    let Some(mut cluster) = galaxy(&mint, &[], MintFees::default()) else { return };
+    let fees = UniverseFees { cfe: 5_000_000, ..UniverseFees::default() };
+    cluster.send(&[instructions::update_fee(&MANAGER_PUBKEY, fees)]).unwrap();
+    let manager_before = cluster.balance(&MANAGER_PUBKEY);
+    token_deposit(&mut cluster, &mint, MIN_AMOUNT);
+    assert_eq!(cluster.balance(&MANAGER_PUBKEY), manager_before);
+
+    //Counted in the planet stats like a SOL deposit, without a lamport volume
+    let stats: PlanetStats = cluster.account(&pda::planet_stats(PLANETS[0])).unwrap().unwrap();
+    assert_eq!((stats.deposits, stats.volume, stats.fees), (1, 0, 0));
+}
+
+#[test]
+fn token_hops_land_on_the_program_selected_planet_serving_the_mint() {
+    let mint = Pubkey::new_unique();
+    let Some(mut cluster) = galaxy(&mint, &[], MintFees::default()) else { return };
+    //A planet outside the mint config is never selected
+    cluster.send(&[instructions::create_planet(&MANAGER_PUBKEY, 9, "planet 9", &[])]).unwrap();
+    let config: MintConfig = cluster.account(&pda::mint_config(&mint)).unwrap().unwrap();
+    assert_eq!(config.p, PLANETS.to_vec());
+
+    let (address, _) = token_deposit(&mut cluster, &mint, 1_000_000);
+    let program = spl_token_2022::ID;
+    let stars = star_ids(b"salt", &address, 2, 2);
+    let stars = [stars[0].as_str(), stars[1].as_str()];
+    cluster.send(&[instructions::token_star_hop_two_start(&MANAGER_PUBKEY, &address, &mint, &program, PLANETS[0], stars)]).unwrap();
+    cluster.send(&[instructions::update_hop_destination_mode(&MANAGER_PUBKEY, HOP_DESTINATION_PROGRAM)]).unwrap();
+
+    let deposit: Deposit = cluster.account(&address).unwrap().unwrap();
+    let selected = select_next_planet(&config.p, &address, &deposit).unwrap();
+    let other = PLANETS.into_iter().find(|id| *id != selected && *id != deposit.location).unwrap();
+    assert!(cluster.send(&[instructions::token_star_hop_two_end(&MANAGER_PUBKEY, &address, &mint, &program, other, stars)]).is_err());
+    cluster.send(&[instructions::token_star_hop_two_end(&MANAGER_PUBKEY, &address, &mint, &program, selected, stars)]).unwrap();
+
+    let deposit: Deposit = cluster.account(&address).unwrap().unwrap();
+    assert_eq!(deposit.location, selected);
+    let selected = select_next_planet(&config.p, &address, &deposit).unwrap();
+    let other = PLANETS.into_iter().find(|id| *id != selected && *id != deposit.location).unwrap();
+    assert!(cluster.send(&[instructions::token_planet_hop(&MANAGER_PUBKEY, &address, &mint, &program, deposit.location, other)]).is_err());
+    cluster.send(&[instructions::token_planet_hop(&MANAGER_PUBKEY, &address, &mint, &program, deposit.location, selected)]).unwrap();
+    let deposit: Deposit = cluster.account(&address).unwrap().unwrap();
+    assert_eq!(deposit.location, selected);
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use oridion::accounts_comet::Deposit;
+use oridion::variables::{MANAGER_PUBKEY, MAX_TRANCHES, MIN_TRANCHE_SPACING};
+use oridion_client::crank::{Action, MODE_DELAY, MODE_MANUAL};
+use oridion_client::svm::SvmCluster;
+use oridion_client::{instructions, pda, Cluster, Crank, CrankConfig};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use common::{galaxy, PLANETS, SOL, START};
+
+/// Deposit withdrawn in `tranches` tranches 60..=120 seconds apart.
+fn scheduled_deposit(cluster: &mut SvmCluster, lamports: u64, mode: u8, tranches: u8) -> (Pubkey, Pubkey, Pubkey) {
+    let creator = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    cluster.airdrop(&creator, lamports + SOL);
+    let create = instructions::create_deposit(&creator, PLANETS[0], &destination, lamports, mode, 0, START + 1_000);
+    let schedule = instructions::set_withdraw_schedule(&creator, None, tranches, 60, 120);
+    cluster.send_as(&creator, &[create, schedule]).unwrap();
+    (pda::deposit(&creator), destination, creator)
+}
+
+fn withdraw(cluster: &mut SvmCluster, address: &Pubkey) -> Result<(), oridion_client::ClientError> {
+    let deposit: Deposit = cluster.account(address).unwrap().unwrap();
+    let ix = instructions::withdraw(&MANAGER_PUBKEY, address, deposit.location, &deposit.destination);
+    cluster.send(&[ix]).map(|_| ())
+}
+
+#[test]
+fn withdraw_releases_one_tranche_per_call_and_closes_on_the_last() {
+    let Some(mut cluster) = galaxy() else { return };
+    let lamports = 3 * SOL;
+    let (address, destination, _) = scheduled_deposit(&mut cluster, lamports, MODE_MANUAL, 3);
+
+    let mut now = START;
+    for left in (1..=3u8).rev() {
+        let before: Deposit = cluster.account(&address).unwrap().unwrap();
+        assert_eq!(before.tranches, left);
+        withdraw(&mut cluster, &address).unwrap();
+        let paid = cluster.balance(&destination);
+        let Some(after) = cluster.account::<Deposit>(&address).unwrap() else {
+            assert_eq!(left, 1, "closed before the last tranche");
+            break;
+        };
+
+        //Deposit stays open with what is left, the next tranche waits for its spacing
+        assert_eq!(after.lamports + paid, lamports);
+        assert!(after.lamports > 0 && after.lamports < before.lamports);
+        assert!(after.next_process_at >= now + 60 && after.next_process_at <= now + 120);
+        assert!(withdraw(&mut cluster, &address).is_err());
+        now = after.next_process_at;
+        cluster.set_unix_timestamp(now);
+    }
+    assert!(cluster.account::<Deposit>(&address).unwrap().is_none());
+    assert_eq!(cluster.balance(&destination), lamports);
+}
+
+#[test]
+fn crank_withdraws_every_tranche() {
+    let Some(mut cluster) = galaxy() else { return };
+    let lamports = 2 * SOL;
+    let (address, destination, _) = scheduled_deposit(&mut cluster, lamports, MODE_DELAY, 4);
+    let config = CrankConfig { salt: b"test salt".to_vec(), ..CrankConfig::default() };
+    let mut crank = Crank::new(cluster, StdRng::seed_from_u64(11), config);
+
+    let mut now = START;
+    let mut withdrawals = 0;
+    while crank.cluster().account::<Deposit>(&address).unwrap().is_some() {
+        now += 30;
+        assert!(now < START + 5_000, "deposit was never fully withdrawn");
+        crank.cluster_mut().set_unix_timestamp(now);
+        for (_, result) in crank.tick().unwrap() {
+            if result.unwrap() == Action::Withdraw {
+                withdrawals += 1;
+            }
+        }
+    }
+    assert_eq!(withdrawals, 4);
+    assert_eq!(crank.cluster().balance(&destination), lamports);
+}
+
+#[test]
+fn invalid_schedules_are_rejected() {
+    let Some(mut cluster) = galaxy() else { return };
+    let (address, _, creator) = scheduled_deposit(&mut cluster, SOL, MODE_MANUAL, 1);
+
+    let invalid = [
+        (0, 60, 120), //No tranches
+        (MAX_TRANCHES + 1, 60, 120), //Too many tranches
+        (2, 120, 60), //Spacing range upside down
+        (2, MIN_TRANCHE_SPACING - 1, 120), //Too close together
+    ];
+    for (tranches, spacing_min, spacing_max) in invalid {
+        let ix = instructions::set_withdraw_schedule(&creator, None, tranches, spacing_min, spacing_max);
+        assert!(cluster.send_as(&creator, &[ix]).is_err());
+    }
+
+    //Only before the first hop
+    let hop = instructions::planet_hop(&MANAGER_PUBKEY, &address, PLANETS[0], PLANETS[1]);
+    cluster.send(&[hop]).unwrap();
+    let ix = instructions::set_withdraw_schedule(&creator, None, 2, 60, 120);
+    assert!(cluster.send_as(&creator, &[ix]).is_err());
+}
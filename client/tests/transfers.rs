@@ -0,0 +1,41 @@
+use anchor_lang::prelude::{AccountInfo, Pubkey};
+use oridion::shared::{get_total_lamports, transfer_lamports};
+
+/// Runs `test` with program owned accounts holding `balances`.
+fn with_accounts(balances: &[u64], test: impl FnOnce(&[AccountInfo])) {
+    let keys: Vec<Pubkey> = balances.iter().map(|_| Pubkey::new_unique()).collect();
+    let mut lamports: Vec<u64> = balances.to_vec();
+    let mut data: Vec<Vec<u8>> = balances.iter().map(|_| Vec::new()).collect();
+    let accounts: Vec<AccountInfo> = keys.iter()
+        .zip(lamports.iter_mut())
+        .zip(data.iter_mut())
+        .map(|((key, lamports), data)| AccountInfo::new(key, false, true, lamports, data, &oridion::ID, false, 0))
+        .collect();
+    test(&accounts);
+}
+
+#[test]
+fn transfers_move_lamports_and_keep_the_total() {
+    with_accounts(&[100, 0, 0], |accounts| {
+        let [planet, star, manager] = accounts else { unreachable!() };
+        transfer_lamports(&[(planet, star, 60), (planet, manager, 30), (star, manager, 10)]).unwrap();
+        assert_eq!((planet.lamports(), star.lamports(), manager.lamports()), (10, 50, 40));
+        assert_eq!(get_total_lamports(&[(planet, star, 0), (star, manager, 0)]), 100);
+    });
+}
+
+#[test]
+fn accounts_are_counted_once() {
+    with_accounts(&[100, 5], |accounts| {
+        let [planet, manager] = accounts else { unreachable!() };
+        assert_eq!(get_total_lamports(&[(planet, manager, 1), (planet, manager, 2), (manager, planet, 3)]), 105);
+    });
+}
+
+#[test]
+fn transfer_fails_when_an_account_runs_dry() {
+    with_accounts(&[10, 0], |accounts| {
+        let [planet, star] = accounts else { unreachable!() };
+        assert!(transfer_lamports(&[(planet, star, 11)]).is_err());
+    });
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use oridion::accounts_universe::{PlanetIndex, Universe};
+use oridion::variables::{MANAGER_PUBKEY, MAX_PLANETS};
+use oridion_client::crank::{MODE_DELAY, MODE_MANUAL};
+use oridion_client::svm::SvmCluster;
+use oridion_client::{instructions, pda, Cluster};
+use common::{galaxy, PLANETS, SOL, START};
+
+/// Delayed one SOL deposit on the first planet.
+fn deposit(cluster: &mut SvmCluster) {
+    deposit_on(cluster, PLANETS[0]);
+}
+
+/// Delayed one SOL deposit on planet `id`.
+fn deposit_on(cluster: &mut SvmCluster, id: u16) {
+    let creator = Pubkey::new_unique();
+    cluster.airdrop(&creator, 2 * SOL);
+    let ix = instructions::create_deposit(&creator, id, &Pubkey::new_unique(), SOL, MODE_DELAY, 0, START + 1_000);
+    cluster.send_as(&creator, &[ix]).unwrap();
+}
+
+#[test]
+fn universe_header_is_fixed_size() {
+    assert_eq!(Universe::LEN, 8 + 8 + 32 + 7 * 8 + 8 * 8 + 32);
+}
+
+#[test]
+fn deposits_do_not_write_the_universe() {
+    let creator = Pubkey::new_unique();
+    let deposits = [
+        instructions::create_deposit(&creator, PLANETS[0], &Pubkey::new_unique(), SOL, MODE_DELAY, 0, START),
+        instructions::create_note_deposit(&creator, PLANETS[0], SOL, MODE_DELAY, 0, START, [1; 32]),
+        instructions::create_bucket_deposit(&creator, PLANETS[0], &Pubkey::new_unique(), 0, SOL, MODE_DELAY, 0, START),
+    ];
+    for ix in deposits {
+        assert!(!ix.accounts.iter().any(|meta| meta.pubkey == pda::universe() && meta.is_writable));
+    }
+}
+
+#[test]
+fn deposit_lands_straight_on_the_planet() {
+    let Some(mut cluster) = galaxy() else { return };
+    let universe_before = cluster.balance(&pda::universe());
+    let planet_before = cluster.balance(&pda::planet(PLANETS[0]));
+    deposit(&mut cluster);
+    assert_eq!(cluster.balance(&pda::universe()), universe_before);
+    assert_eq!(cluster.balance(&pda::planet(PLANETS[0])), planet_before + SOL);
+}
+
+#[test]
+fn planet_index_holds_up_to_max_planets() {
+    let Some(mut cluster) = galaxy() else { return };
+    //Planet ids are below MAX_PLANETS, a full index uses them all
+    assert!(cluster.send(&[instructions::create_planet(&MANAGER_PUBKEY, MAX_PLANETS as u16, "too high", &[])]).is_err());
+    for id in (0..MAX_PLANETS as u16).filter(|id| !PLANETS.contains(id)) {
+        cluster.send(&[instructions::create_planet(&MANAGER_PUBKEY, id, &format!("planet {id}"), &[])]).unwrap();
+    }
+    let index: PlanetIndex = cluster.account(&pda::planet_index()).unwrap().unwrap();
+    assert_eq!(index.p.len(), MAX_PLANETS);
+}
+
+#[test]
+fn only_the_planet_holding_a_deposit_pays_it_out() {
+    let Some(mut cluster) = galaxy() else { return };
+    let creator = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    cluster.airdrop(&creator, 2 * SOL);
+    let ix = instructions::create_deposit(&creator, PLANETS[0], &destination, SOL, MODE_MANUAL, 0, START);
+    cluster.send_as(&creator, &[ix]).unwrap();
+    let address = pda::deposit(&creator);
+    //Funds of another deposit sit on the second planet
+    deposit_on(&mut cluster, PLANETS[1]);
+
+    let balances: Vec<u64> = PLANETS.iter().map(|id| cluster.balance(&pda::planet(*id))).collect();
+    let elsewhere = [
+        instructions::planet_hop(&MANAGER_PUBKEY, &address, PLANETS[1], PLANETS[2]),
+        instructions::star_hop_two_start(&MANAGER_PUBKEY, &address, PLANETS[1], ["a", "b"]),
+        instructions::star_hop_three_start(&MANAGER_PUBKEY, &address, PLANETS[1], ["a", "b", "c"]),
+        instructions::withdraw(&MANAGER_PUBKEY, &address, PLANETS[1], &destination),
+    ];
+    for ix in elsewhere {
+        assert!(cluster.send(&[ix]).is_err());
+    }
+    assert_eq!(PLANETS.iter().map(|id| cluster.balance(&pda::planet(*id))).collect::<Vec<u64>>(), balances);
+
+    cluster.send(&[instructions::withdraw(&MANAGER_PUBKEY, &address, PLANETS[0], &destination)]).unwrap();
+    assert_eq!(cluster.balance(&destination), SOL);
+}
@@ -21,55 +21,293 @@ pub struct CreateDeposit<'info> {
     #[account(mut)]
     pub creator: Signer<'info>,
     #[account(mut)]
-    pub universe: Account<'info,Universe>,
-    #[account(mut)]
     pub planet: Account<'info,Planet>,
     pub destination: SystemAccount<'info>,
     pub system_program: Program<'info,System>,
     pub rent: Sysvar<'info, Rent>,
+    #[account(mut, seeds = [PLANET_STATS_SEED, planet.id.to_le_bytes().as_ref()], bump = stats.bump)]
+    pub stats: Account<'info, PlanetStats>,
+}
+
+//Fixed denomination deposit. The nonce lets one creator open several in one transaction.
+#[derive(Accounts)]
+#[instruction(nonce: u8)]
+pub struct CreateBucketDeposit<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = DISCRIMINATOR_LENGTH + Deposit::INIT_SPACE,
+        seeds = [
+            DEPOSIT_PDA_SEED_PRE,
+            creator.key().as_ref(),
+            &[nonce],
+            DEPOSIT_PDA_SEED_POST
+        ],
+        bump
+    )]
+    pub deposit: Account<'info,Deposit>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    #[account(seeds = [UNIVERSE_PDA_SEED], bump = universe.load()?.bp)]
+    pub universe: AccountLoader<'info,Universe>,
+    #[account(mut)]
+    pub planet: Account<'info,Planet>,
+    pub destination: SystemAccount<'info>,
+    pub system_program: Program<'info,System>,
+    #[account(mut, seeds = [PLANET_STATS_SEED, planet.id.to_le_bytes().as_ref()], bump = stats.bump)]
+    pub stats: Account<'info, PlanetStats>,
 }
 
+//Deposit storing hash(destination + salt), or hash(secret + destination + relay fee) for notes, instead of the destination.
+#[derive(Accounts)]
+pub struct CreateCommittedDeposit<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = DISCRIMINATOR_LENGTH + Deposit::INIT_SPACE,
+        seeds = [
+            DEPOSIT_PDA_SEED_PRE,
+            creator.key().as_ref(),
+            DEPOSIT_PDA_SEED_POST
+        ],
+        bump
+    )]
+    pub deposit: Account<'info,Deposit>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    #[account(mut)]
+    pub planet: Account<'info,Planet>,
+    pub system_program: Program<'info,System>,
+    #[account(mut, seeds = [PLANET_STATS_SEED, planet.id.to_le_bytes().as_ref()], bump = stats.bump)]
+    pub stats: Account<'info, PlanetStats>,
+}
 
 #[account]
 #[derive(InitSpace)]
 pub struct Deposit {
+    pub version: u8, //Account layout version
     pub mode: u8, //1 Delay, 2 Instant, 3 Manual
     pub next_process_at: i64, //Next process timestamp
     pub next_process: u8, // (0 'hop'  1 'withdraw')
-    pub last_process: u8, //('0 - deposit','1 - hop')
-    #[max_len(10)]
-    pub location: String, // Current planet location
+    pub last_process: u8, //('0 - deposit','1 - hop', '2 - tranche withdrawn')
+    pub location: u16, // Current planet id
     pub delay: u32, // Set delay in seconds
     pub hops: u16, //Amount of hops
     pub withdraw_at: i64, //Set withdraw timestamp
     pub created_at: i64, //Deposit started
     pub last_process_at: i64, //Last updated timestamp
-    pub lamports: u64, //Lamports deposited
-    pub destination: Pubkey //Destination wallet address
+    pub lamports: u64, //Lamports deposited (token amount for token deposits, net of transfer fees)
+    pub destination: Pubkey, //Destination wallet address
+    pub mint: Pubkey, //Token mint (NATIVE_SOL for lamport deposits)
+    pub sm: u8, //Split mode (0 single destination, 1 basis points, 2 random with minimums)
+    #[max_len(MAX_DESTINATIONS)]
+    pub splits: Vec<DestinationSplit>, //Split destinations, the first one is also `destination`
+    pub tranches: u8, //Withdrawal tranches left (0 or 1 withdraws everything at once)
+    pub spacing_min: u32, //Minimum seconds between tranches
+    pub spacing_max: u32, //Maximum seconds between tranches
+    pub dm: u8, //Destination mode (0 plaintext, 1 committed, 2 note)
+    pub commitment: [u8; 32], //hash(destination + salt) for committed deposits, hash(secret + destination + relay fee) for notes
+    pub route: RoutePolicy, //Route the creator asked for, all zero for none
+    pub star_hops: u8, //Hops made through stars
+    pub fees: u64, //Hop fees paid out of the deposit
+    pub visited: u64, //Bitmap of the planet ids landed on since the route policy was set
+    pub recent: [u16; MAX_RECENT_PLANETS], //Planets left most recently, newest first
+    pub rn: u8, //Entries in recent, at most the recent window of the last landing
+    pub stars: u8, //Stars holding the deposit between a star hop start and end, 0 once landed
+}
+
+//Zero means no limit (or no minimum) for every field.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, InitSpace)]
+pub struct RoutePolicy {
+    pub min_hops: u16, //Hops before the deposit can be withdrawn
+    pub max_hops: u16, //Hops after which it can only be withdrawn
+    pub min_stars: u8, //Star hops (two or three stars) before it can be withdrawn
+    pub max_fees: u64, //Hop fees it may pay, in lamports or the mint's units. Withdraw fees are left out, so it can always be withdrawn
+    pub avoid_revisit: bool, //Never land on a planet it has been on
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, InitSpace)]
+pub struct DestinationSplit {
+    pub destination: Pubkey, //Destination wallet address
+    pub bps: u16, //Share in basis points (split mode 1)
+    pub min: u64, //Minimum lamports (split mode 2)
+}
+
+//Creator spreads the withdrawal over several destinations before the first hop.
+//The nonce is only passed for bucket deposits.
+#[derive(Accounts)]
+pub struct SetDepositSplits<'info> {
+    #[account(
+        mut,
+        constraint = deposit.mint == NATIVE_SOL @ OridionError::DepositMintMismatch,
+        constraint = deposit.dm == 0 @ OridionError::DestinationCommitted,
+        constraint = deposit.last_process == 0 @ OridionError::DepositAlreadyStarted
+    )]
+    pub deposit: Account<'info, Deposit>,
+    pub creator: Signer<'info>
+}
+
+//Creator staggers the withdrawal over several tranches before the first hop.
+#[derive(Accounts)]
+pub struct SetWithdrawSchedule<'info> {
+    #[account(
+        mut,
+        constraint = deposit.mint == NATIVE_SOL @ OridionError::DepositMintMismatch,
+        constraint = deposit.last_process == 0 @ OridionError::DepositAlreadyStarted
+    )]
+    pub deposit: Account<'info, Deposit>,
+    pub creator: Signer<'info>
+}
+
+//Creator sets the route policy before the first hop.
+#[derive(Accounts)]
+pub struct SetRoutePolicy<'info> {
+    #[account(mut, constraint = deposit.last_process == 0 @ OridionError::DepositAlreadyStarted)]
+    pub deposit: Account<'info, Deposit>,
+    #[account(seeds = [PLANET_INDEX_SEED], bump = planet_index.bump)]
+    pub planet_index: Account<'info,PlanetIndex>,
+    pub creator: Signer<'info>
 }
 
 #[derive(Accounts)]
 pub struct PlanetHop<'info> {
-    #[account(mut)]
+    #[account(mut, constraint = deposit.mint == NATIVE_SOL @ OridionError::DepositMintMismatch)]
     pub deposit: Account<'info, Deposit>,
     #[account(mut)]
     pub to_planet: Account<'info,Planet>,
-    #[account(mut)]
+    #[account(mut, constraint = from_planet.id == deposit.location @ OridionError::HopErrorWrongPlanet)]
     pub from_planet: Account<'info,Planet>,
     #[account(mut, address = MANAGER_PUBKEY)]
-    pub manager: Signer<'info>
+    pub manager: Signer<'info>,
+    #[account(seeds = [UNIVERSE_PDA_SEED], bump = universe.load()?.bp)]
+    pub universe: AccountLoader<'info,Universe>,
+    #[account(seeds = [PLANET_INDEX_SEED], bump = planet_index.bump)]
+    pub planet_index: Account<'info,PlanetIndex>,
+    #[account(mut, seeds = [PLANET_STATS_SEED, to_planet.id.to_le_bytes().as_ref()], bump = stats.bump)]
+    pub stats: Account<'info, PlanetStats>,
 }
 
+//Planet hops for many deposits. Entries are passed as writable remaining accounts,
+//BATCH_HOP_ACCOUNTS each: deposit, from planet, to planet, to planet stats.
+#[derive(Accounts)]
+pub struct BatchHop<'info> {
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+    #[account(seeds = [UNIVERSE_PDA_SEED], bump = universe.load()?.bp)]
+    pub universe: AccountLoader<'info,Universe>,
+    #[account(seeds = [PLANET_INDEX_SEED], bump = planet_index.bump)]
+    pub planet_index: Account<'info,PlanetIndex>,
+}
+
+//Withdraws the next tranche. Closed by the program after the last one.
 #[derive(Accounts)]
 pub struct WithdrawAccounts<'info> {
-    #[account(mut, close = manager)]
+    #[account(
+        mut,
+        constraint = deposit.mint == NATIVE_SOL @ OridionError::DepositMintMismatch,
+        constraint = deposit.dm == 0 @ OridionError::DestinationCommitted
+    )]
+    pub deposit: Account<'info, Deposit>,
+    #[account(mut, constraint = from_planet.id == deposit.location @ OridionError::HopErrorWrongPlanet)]
+    pub from_planet: Account<'info,Planet>,
+    #[account(mut, address = deposit.destination)]
+    pub destination: SystemAccount<'info>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+    #[account(seeds = [PLANET_INDEX_SEED], bump = planet_index.bump)]
+    pub planet_index: Account<'info,PlanetIndex>,
+    #[account(mut, seeds = [PLANET_STATS_SEED, from_planet.id.to_le_bytes().as_ref()], bump = stats.bump)]
+    pub stats: Account<'info, PlanetStats>,
+}
+
+//Withdraw of a committed deposit. The destination is checked against the commitment
+//by the program; the authority is the manager or the deposit creator.
+#[derive(Accounts)]
+pub struct WithdrawCommitted<'info> {
+    #[account(
+        mut,
+        constraint = deposit.mint == NATIVE_SOL @ OridionError::DepositMintMismatch,
+        constraint = deposit.dm == 1 @ OridionError::CommitmentMismatch
+    )]
     pub deposit: Account<'info, Deposit>,
+    #[account(mut, constraint = from_planet.id == deposit.location @ OridionError::HopErrorWrongPlanet)]
+    pub from_planet: Account<'info,Planet>,
     #[account(mut)]
+    pub destination: SystemAccount<'info>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: SystemAccount<'info>,
+    pub authority: Signer<'info>,
+    #[account(seeds = [PLANET_INDEX_SEED], bump = planet_index.bump)]
+    pub planet_index: Account<'info,PlanetIndex>,
+    #[account(mut, seeds = [PLANET_STATS_SEED, from_planet.id.to_le_bytes().as_ref()], bump = stats.bump)]
+    pub stats: Account<'info, PlanetStats>,
+}
+
+//Withdraw of a note deposit by whoever holds the secret, to the destination the note commits to.
+#[derive(Accounts)]
+pub struct WithdrawNote<'info> {
+    #[account(
+        mut,
+        constraint = deposit.mint == NATIVE_SOL @ OridionError::DepositMintMismatch,
+        constraint = deposit.dm == 2 @ OridionError::NoteMismatch
+    )]
+    pub deposit: Account<'info, Deposit>,
+    #[account(mut, constraint = from_planet.id == deposit.location @ OridionError::HopErrorWrongPlanet)]
+    pub from_planet: Account<'info,Planet>,
+    #[account(mut)]
+    pub destination: SystemAccount<'info>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: SystemAccount<'info>,
+    #[account(seeds = [PLANET_INDEX_SEED], bump = planet_index.bump)]
+    pub planet_index: Account<'info,PlanetIndex>,
+    #[account(mut, seeds = [PLANET_STATS_SEED, from_planet.id.to_le_bytes().as_ref()], bump = stats.bump)]
+    pub stats: Account<'info, PlanetStats>,
+}
+
+//Permissionless hop. Destination is selected by the program.
+#[derive(Accounts)]
+pub struct KeeperHop<'info> {
+    #[account(mut, constraint = deposit.mint == NATIVE_SOL @ OridionError::DepositMintMismatch)]
+    pub deposit: Account<'info, Deposit>,
+    #[account(mut)]
+    pub to_planet: Account<'info,Planet>,
+    #[account(mut, constraint = from_planet.id == deposit.location @ OridionError::HopErrorWrongPlanet)]
+    pub from_planet: Account<'info,Planet>,
+    #[account(seeds = [UNIVERSE_PDA_SEED], bump = universe.load()?.bp)]
+    pub universe: AccountLoader<'info,Universe>,
+    #[account(seeds = [PLANET_INDEX_SEED], bump = planet_index.bump)]
+    pub planet_index: Account<'info,PlanetIndex>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: SystemAccount<'info>,
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    #[account(mut, seeds = [PLANET_STATS_SEED, to_planet.id.to_le_bytes().as_ref()], bump = stats.bump)]
+    pub stats: Account<'info, PlanetStats>,
+}
+
+//Permissionless withdraw to the deposit destination. Closed by the program after the last tranche.
+#[derive(Accounts)]
+pub struct KeeperWithdraw<'info> {
+    #[account(
+        mut,
+        constraint = deposit.mint == NATIVE_SOL @ OridionError::DepositMintMismatch,
+        constraint = deposit.dm == 0 @ OridionError::DestinationCommitted
+    )]
+    pub deposit: Account<'info, Deposit>,
+    #[account(mut, constraint = from_planet.id == deposit.location @ OridionError::HopErrorWrongPlanet)]
     pub from_planet: Account<'info,Planet>,
     #[account(mut, address = deposit.destination)]
     pub destination: SystemAccount<'info>,
+    #[account(seeds = [UNIVERSE_PDA_SEED], bump = universe.load()?.bp)]
+    pub universe: AccountLoader<'info,Universe>,
     #[account(mut, address = MANAGER_PUBKEY)]
-    pub manager: Signer<'info>
+    pub manager: SystemAccount<'info>,
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    #[account(seeds = [PLANET_INDEX_SEED], bump = planet_index.bump)]
+    pub planet_index: Account<'info,PlanetIndex>,
+    #[account(mut, seeds = [PLANET_STATS_SEED, from_planet.id.to_le_bytes().as_ref()], bump = stats.bump)]
+    pub stats: Account<'info, PlanetStats>,
 }
 
 
@@ -77,9 +315,9 @@ pub struct WithdrawAccounts<'info> {
 #[derive(Accounts)]
 #[instruction(star_one_id: String, star_two_id: String)]
 pub struct StarHopTwoStart<'info> {
-    #[account(mut)]
+    #[account(mut, constraint = deposit.mint == NATIVE_SOL @ OridionError::DepositMintMismatch)]
     pub deposit: Account<'info, Deposit>,
-    #[account(mut)]
+    #[account(mut, constraint = from_planet.id == deposit.location @ OridionError::HopErrorWrongPlanet)]
     pub from_planet: Account<'info,Planet>,
     #[account(init, payer = manager, space = DISCRIMINATOR_LENGTH + Star::INIT_SPACE,
         seeds = [
@@ -108,7 +346,7 @@ pub struct StarHopTwoStart<'info> {
 //Return from stars to destination planet
 #[derive(Accounts)]
 pub struct StarHopTwoEnd<'info> {
-    #[account(mut)]
+    #[account(mut, constraint = deposit.mint == NATIVE_SOL @ OridionError::DepositMintMismatch)]
     pub deposit: Account<'info, Deposit>,
     #[account(mut)]
     pub to_planet: Account<'info,Planet>,
@@ -117,16 +355,22 @@ pub struct StarHopTwoEnd<'info> {
     #[account(mut, close = manager, has_one = manager, constraint = manager.key == &star_two.manager)]
     pub star_two: Account<'info, Star>,
     #[account(mut, address = MANAGER_PUBKEY)]
-    pub manager: Signer<'info>
+    pub manager: Signer<'info>,
+    #[account(seeds = [UNIVERSE_PDA_SEED], bump = universe.load()?.bp)]
+    pub universe: AccountLoader<'info,Universe>,
+    #[account(seeds = [PLANET_INDEX_SEED], bump = planet_index.bump)]
+    pub planet_index: Account<'info,PlanetIndex>,
+    #[account(mut, seeds = [PLANET_STATS_SEED, to_planet.id.to_le_bytes().as_ref()], bump = stats.bump)]
+    pub stats: Account<'info, PlanetStats>,
 }
 
 
 #[derive(Accounts)]
 #[instruction(star_one_id: String, star_two_id: String, star_three_id: String )]
 pub struct StarHopThreeStart<'info> {
-    #[account(mut)]
+    #[account(mut, constraint = deposit.mint == NATIVE_SOL @ OridionError::DepositMintMismatch)]
     pub deposit: Account<'info, Deposit>,
-    #[account(mut)]
+    #[account(mut, constraint = from_planet.id == deposit.location @ OridionError::HopErrorWrongPlanet)]
     pub from_planet: Account<'info,Planet>,
     #[account(init, payer = manager, space = DISCRIMINATOR_LENGTH + Star::INIT_SPACE,
         seeds = [
@@ -165,7 +409,7 @@ pub struct StarHopThreeStart<'info> {
 
 #[derive(Accounts)]
 pub struct StarHopThreeEnd<'info> {
-    #[account(mut)]
+    #[account(mut, constraint = deposit.mint == NATIVE_SOL @ OridionError::DepositMintMismatch)]
     pub deposit: Account<'info, Deposit>,
     #[account(mut)]
     pub to_planet: Account<'info,Planet>,
@@ -176,12 +420,19 @@ pub struct StarHopThreeEnd<'info> {
     #[account(mut, close = manager, has_one = manager, constraint = manager.key == &star_three.manager)]
     pub star_three: Account<'info, Star>,
     #[account(mut, address = MANAGER_PUBKEY)]
-    pub manager: Signer<'info>
+    pub manager: Signer<'info>,
+    #[account(seeds = [UNIVERSE_PDA_SEED], bump = universe.load()?.bp)]
+    pub universe: AccountLoader<'info,Universe>,
+    #[account(seeds = [PLANET_INDEX_SEED], bump = planet_index.bump)]
+    pub planet_index: Account<'info,PlanetIndex>,
+    #[account(mut, seeds = [PLANET_STATS_SEED, to_planet.id.to_le_bytes().as_ref()], bump = stats.bump)]
+    pub stats: Account<'info, PlanetStats>,
 }
 
 #[account]
 #[derive(InitSpace)]
 pub struct Star {
+    pub version: u8, //Account layout version
     pub amount: u64,
     pub manager: Pubkey
 }
\ No newline at end of file
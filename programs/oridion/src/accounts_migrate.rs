@@ -0,0 +1,254 @@
+use anchor_lang::prelude::*;
+use super::*;
+
+///------------------------------------------------------------//
+/// MIGRATIONS
+/// v3.0.0 accounts have no version byte (version 0). Migrations
+/// parse the old layout by hand, realloc the account (rent paid
+/// by the manager) and rewrite it at ACCOUNT_VERSION.
+/// Planets were keyed by name, so they move to their id PDA.
+/// Stars of star hops started by v3.0.0 are migrated in place
+/// so the current program can end the hop.
+///------------------------------------------------------------//
+#[derive(Accounts)]
+pub struct MigrateUniverse<'info> {
+    /// CHECK: Old layout, checked and parsed by the migration
+    #[account(mut, seeds = [UNIVERSE_PDA_SEED], bump)]
+    pub universe: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = manager,
+        space = PlanetIndex::LEN,
+        seeds = [PLANET_INDEX_SEED],
+        bump
+    )]
+    pub planet_index: Account<'info, PlanetIndex>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u16)]
+pub struct MigratePlanet<'info> {
+    /// CHECK: Old layout, checked and parsed by the migration
+    #[account(mut)]
+    pub legacy_planet: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = manager,
+        space = Planet::LEN,
+        seeds = [
+            PLANET_PDA_SEED_PRE,
+            id.to_le_bytes().as_ref(),
+            PLANET_PDA_SEED_POST
+        ],
+        bump
+    )]
+    pub planet: Account<'info, Planet>,
+    #[account(
+        init,
+        payer = manager,
+        space = DISCRIMINATOR_LENGTH + PlanetStats::INIT_SPACE,
+        seeds = [PLANET_STATS_SEED, id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub planet_stats: Account<'info, PlanetStats>,
+    #[account(mut, seeds = [PLANET_INDEX_SEED], bump = planet_index.bump)]
+    pub planet_index: Account<'info, PlanetIndex>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateDeposit<'info> {
+    /// CHECK: Old layout, checked and parsed by the migration
+    #[account(mut)]
+    pub deposit: UncheckedAccount<'info>,
+    //Migrated planet the deposit sits on
+    pub planet: Account<'info, Planet>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateStar<'info> {
+    /// CHECK: Old layout, checked and parsed by the migration
+    #[account(mut)]
+    pub star: UncheckedAccount<'info>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+//v3.0.0 Universe
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct UniverseV3 {
+    pub pda: Pubkey,
+    pub p: Vec<String>,
+    pub st: i64,
+    pub up: i64,
+    pub bp: u8,
+    pub cfe: u64,
+    pub hpfe: u64,
+    pub hsfe2: u64,
+    pub hsfe3: u64,
+    pub wfe: u64
+}
+
+impl UniverseV3 {
+    pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + PUBLIC_KEY_LENGTH //Universe PDA
+        + PLANETS_VEC_LENGTH // Planets vector
+        + TIMESTAMP_LENGTH // Universe started
+        + TIMESTAMP_LENGTH // Last star generated
+        + U8_LENGTH //Bump
+        + LAMPORT_LENGTH * 5; // Fees
+
+    /// Config header only. Planets join the planet index as they are
+    /// migrated. No denomination buckets until set.
+    pub fn migrate(self) -> Universe {
+        Universe {
+            version: ACCOUNT_VERSION,
+            bp: self.bp,
+            bkl: 0,
+            rw: 0,
+            hdm: 0,
+            padding: [0; 3],
+            pda: self.pda,
+            st: self.st,
+            up: self.up,
+            cfe: self.cfe,
+            hpfe: self.hpfe,
+            hsfe2: self.hsfe2,
+            hsfe3: self.hsfe3,
+            wfe: self.wfe,
+            bk: [0; MAX_BUCKETS],
+            lut: Pubkey::default(),
+        }
+    }
+}
+
+//v3.0.0 Planet
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct PlanetV3 {
+    pub name: String,
+    pub pda: Pubkey,
+    pub created: i64,
+    pub bump: u8,
+    pub visits: u64
+}
+
+impl PlanetV3 {
+    pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + STRING_LENGTH_PREFIX + PLANET_NAME //Planet ID / Name
+        + PUBLIC_KEY_LENGTH //PDA
+        + TIMESTAMP_LENGTH // Created
+        + U8_LENGTH //Bump
+        + U64_LENGTH; //Visitors
+
+    /// Planet at its id PDA, keeping the name for display.
+    /// SOL only until mints are served.
+    pub fn migrate(self, id: u16, pda: Pubkey, bump: u8) -> Planet {
+        Planet {
+            version: ACCOUNT_VERSION,
+            id,
+            name: self.name,
+            pda,
+            created: self.created,
+            bump,
+            visits: self.visits,
+            mints: Vec::new(),
+        }
+    }
+}
+
+//v3.0.0 Deposit
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct DepositV3 {
+    pub mode: u8,
+    pub next_process_at: i64,
+    pub next_process: u8,
+    pub last_process: u8,
+    pub location: String,
+    pub delay: u32,
+    pub hops: u16,
+    pub withdraw_at: i64,
+    pub created_at: i64,
+    pub last_process_at: i64,
+    pub lamports: u64,
+    pub destination: Pubkey
+}
+
+impl DepositV3 {
+    pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + U8_LENGTH //Mode
+        + TIMESTAMP_LENGTH //Next process at
+        + U8_LENGTH * 2 //Next and last process
+        + STRING_LENGTH_PREFIX + MAX_PLANET_TITLE_LENGTH //Location
+        + 4 //Delay
+        + 2 //Hops
+        + TIMESTAMP_LENGTH * 3 //Withdraw, created and last process at
+        + LAMPORT_LENGTH //Lamports
+        + PUBLIC_KEY_LENGTH; //Destination
+
+    /// SOL deposit to a single plaintext destination, withdrawn at once.
+    /// `location` is the id of the migrated planet it sits on, `stars` the
+    /// number of stars holding it when caught mid star hop (0 once landed).
+    pub fn migrate(self, location: u16, stars: u8) -> Deposit {
+        Deposit {
+            version: ACCOUNT_VERSION,
+            mode: self.mode,
+            next_process_at: self.next_process_at,
+            next_process: self.next_process,
+            last_process: self.last_process,
+            location,
+            delay: self.delay,
+            hops: self.hops,
+            withdraw_at: self.withdraw_at,
+            created_at: self.created_at,
+            last_process_at: self.last_process_at,
+            lamports: self.lamports,
+            destination: self.destination,
+            mint: NATIVE_SOL,
+            sm: 0,
+            splits: Vec::new(),
+            tranches: 0,
+            spacing_min: 0,
+            spacing_max: 0,
+            dm: 0,
+            commitment: [0; 32],
+            route: RoutePolicy::default(),
+            star_hops: 0,
+            fees: 0,
+            visited: get_planet_bit(location),
+            recent: [0; MAX_RECENT_PLANETS],
+            rn: 0,
+            stars,
+        }
+    }
+}
+
+//v3.0.0 Star
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct StarV3 {
+    pub amount: u64,
+    pub manager: Pubkey
+}
+
+impl StarV3 {
+    pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + LAMPORT_LENGTH //Amount
+        + PUBLIC_KEY_LENGTH; //Manager
+
+    /// Star of a SOL star hop started by v3.0.0, ended by the current program.
+    pub fn migrate(self) -> Star {
+        Star {
+            version: ACCOUNT_VERSION,
+            amount: self.amount,
+            manager: self.manager,
+        }
+    }
+}
@@ -6,7 +6,7 @@ use super::*;
 /// PLANETS PDA
 ///-------------------------------------------------------------//
 #[derive(Accounts)]
-#[instruction(name: String)]
+#[instruction(id: u16)]
 pub struct CreatePlanet<'info> {
     #[account(
         init,
@@ -14,14 +14,22 @@ pub struct CreatePlanet<'info> {
         space = Planet::LEN,
         seeds = [
             PLANET_PDA_SEED_PRE,
-            name.as_ref(),
+            id.to_le_bytes().as_ref(),
             PLANET_PDA_SEED_POST
         ],
         bump
     )]
     pub planet: Account<'info, Planet>,
-    #[account(mut)]
-    pub universe: Account<'info, Universe>,
+    #[account(
+        init,
+        payer = creator,
+        space = DISCRIMINATOR_LENGTH + PlanetStats::INIT_SPACE,
+        seeds = [PLANET_STATS_SEED, id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub planet_stats: Account<'info, PlanetStats>,
+    #[account(mut, seeds = [PLANET_INDEX_SEED], bump = planet_index.bump)]
+    pub planet_index: Account<'info, PlanetIndex>,
     #[account(mut, address = MANAGER_PUBKEY)]
     pub creator: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -31,27 +39,42 @@ pub struct CreatePlanet<'info> {
 
 #[derive(Accounts)]
 pub struct DeletePlanet<'info> {
-    #[account(mut, close = creator, constraint = &planet.to_account_info().lamports() <= &1593840)]
+    #[account(mut, close = creator)]
     pub planet: Account<'info, Planet>,
-    #[account(mut)]
-    pub universe: Account<'info, Universe>,
+    //Folded into the galaxy stats before it closes
+    #[account(mut, close = creator, seeds = [PLANET_STATS_SEED, planet.id.to_le_bytes().as_ref()], bump = planet_stats.bump)]
+    pub planet_stats: Box<Account<'info, PlanetStats>>,
+    #[account(mut, seeds = [GALAXY_STATS_SEED], bump = stats.bump)]
+    pub stats: Box<Account<'info, GalaxyStats>>,
+    #[account(mut, seeds = [PLANET_INDEX_SEED], bump = planet_index.bump)]
+    pub planet_index: Account<'info, PlanetIndex>,
+    //The recent window must still leave a planet to hop to
+    #[account(seeds = [UNIVERSE_PDA_SEED], bump = universe.load()?.bp)]
+    pub universe: AccountLoader<'info, Universe>,
     #[account(mut, address = MANAGER_PUBKEY)]
     pub creator: Signer<'info>,
+    //MintConfigs of the planet's mints follow as writable remaining accounts, in order
 }
 
 #[account]
 pub struct Planet {
-    pub name: String, //Name/ID of planet
+    pub version: u8, //Account layout version
+    pub id: u16, //ID of planet, used in seeds and deposit locations
+    pub name: String, //Display name
     pub pda: Pubkey, //PDA
     pub created: i64, //Planet started
     pub bump: u8, // Bump
     pub visits: u64, //Visitors 
+    pub mints: Vec<Pubkey>, //Token mints served (each needs a MintConfig)
 }
 impl Planet {
-    const LEN: usize = DISCRIMINATOR_LENGTH
-        + STRING_LENGTH_PREFIX + PLANET_NAME //Planet ID / Name
+    pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + U8_LENGTH //Version
+        + U16_LENGTH //Planet ID
+        + STRING_LENGTH_PREFIX + PLANET_NAME //Display name
         + PUBLIC_KEY_LENGTH //PDA
         + TIMESTAMP_LENGTH // Created
         + U8_LENGTH //Bump
-        + U64_LENGTH; //Visitors
+        + U64_LENGTH //Visitors
+        + PLANET_MINTS_VEC_LENGTH; //Mints
 }
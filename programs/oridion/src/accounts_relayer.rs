@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+use super::*;
+
+///------------------------------------------------------------//
+/// RELAYER REGISTRY PDA
+/// Relayers the manager allows to submit withdrawals of
+/// committed and note deposits for a capped fee.
+///------------------------------------------------------------//
+#[derive(Accounts)]
+pub struct CreateRelayerRegistry<'info> {
+    #[account(
+        init,
+        payer = manager,
+        space = DISCRIMINATOR_LENGTH + RelayerRegistry::INIT_SPACE,
+        seeds = [RELAYER_REGISTRY_SEED],
+        bump
+    )]
+    pub registry: Account<'info, RelayerRegistry>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRelayerRegistry<'info> {
+    #[account(mut, seeds = [RELAYER_REGISTRY_SEED], bump = registry.bump)]
+    pub registry: Account<'info, RelayerRegistry>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RelayerRegistry {
+    #[max_len(MAX_RELAYERS)]
+    pub relayers: Vec<Pubkey>, //Allowed relayers
+    pub max_fee: u64, //Maximum relayer fee per withdrawal in lamports
+    pub bump: u8, //Bump
+}
+
+//Relayer submits the withdraw of a committed (salt) or note (secret) deposit
+//and is paid its fee out of the withdrawn amount.
+#[derive(Accounts)]
+pub struct RelayWithdraw<'info> {
+    #[account(
+        mut,
+        constraint = deposit.mint == NATIVE_SOL @ OridionError::DepositMintMismatch,
+        constraint = deposit.dm != 0 @ OridionError::CommitmentMismatch
+    )]
+    pub deposit: Account<'info, Deposit>,
+    #[account(mut, constraint = from_planet.id == deposit.location @ OridionError::HopErrorWrongPlanet)]
+    pub from_planet: Account<'info,Planet>,
+    #[account(mut)]
+    pub destination: SystemAccount<'info>,
+    #[account(
+        seeds = [RELAYER_REGISTRY_SEED],
+        bump = registry.bump,
+        constraint = registry.relayers.contains(relayer.key) @ OridionError::RelayerNotRegistered
+    )]
+    pub registry: Account<'info, RelayerRegistry>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: SystemAccount<'info>,
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+    #[account(seeds = [PLANET_INDEX_SEED], bump = planet_index.bump)]
+    pub planet_index: Account<'info,PlanetIndex>,
+    #[account(mut, seeds = [PLANET_STATS_SEED, from_planet.id.to_le_bytes().as_ref()], bump = stats.bump)]
+    pub stats: Account<'info, PlanetStats>,
+}
@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+use super::*;
+
+///------------------------------------------------------------//
+/// GALAXY STATS PDA
+/// Universe-wide totals plus the last STATS_DAYS days, bucketed
+/// by Clock day, so dashboards can read them without an indexer.
+/// Hot paths write the PlanetStats shard of the planet they touch;
+/// aggregate_stats folds the shards in here.
+///------------------------------------------------------------//
+#[derive(Accounts)]
+pub struct CreateGalaxyStats<'info> {
+    #[account(
+        init,
+        payer = manager,
+        space = DISCRIMINATOR_LENGTH + GalaxyStats::INIT_SPACE,
+        seeds = [GALAXY_STATS_SEED],
+        bump
+    )]
+    pub stats: Account<'info, GalaxyStats>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct GalaxyStats {
+    pub deposits: u64, //Deposits created
+    pub volume: u64, //Lamports deposited (SOL deposits only)
+    pub active: u64, //Deposits not yet fully withdrawn, as of the last aggregation
+    pub closed: u64, //Deposits fully withdrawn
+    pub planet_hops: u64, //Planet to planet hops
+    pub star_hops_two: u64, //Hops through two stars
+    pub star_hops_three: u64, //Hops through three stars
+    pub withdrawals: u64, //Withdrawals, every tranche counts
+    pub fees: u64, //Fees collected by the manager in lamports (token fees are in mint units and not summed)
+    pub days: [DayStats; STATS_DAYS], //Ring of day buckets, indexed by day % STATS_DAYS
+    pub bump: u8, //Bump
+}
+
+///------------------------------------------------------------//
+/// PLANET STATS PDA
+/// Counters of one planet since the last aggregation. Created and
+/// closed with the planet, so deposits on different planets never
+/// share a write lock.
+///------------------------------------------------------------//
+#[derive(Accounts)]
+pub struct AggregateStats<'info> {
+    #[account(mut, seeds = [GALAXY_STATS_SEED], bump = stats.bump)]
+    pub stats: Account<'info, GalaxyStats>,
+    //PlanetStats to fold in are passed as writable remaining accounts
+}
+
+#[account]
+#[derive(InitSpace, Default)]
+pub struct PlanetStats {
+    pub id: u16, //Planet id
+    pub deposits: u64,
+    pub volume: u64,
+    pub closed: u64, //Deposits fully withdrawn
+    pub planet_hops: u64,
+    pub star_hops_two: u64,
+    pub star_hops_three: u64,
+    pub withdrawals: u64,
+    pub fees: u64,
+    pub days: [DayStats; STATS_DAYS], //Same ring as GalaxyStats
+    pub bump: u8, //Bump
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, InitSpace)]
+pub struct DayStats {
+    pub day: u32, //Days since the unix epoch
+    pub deposits: u64,
+    pub volume: u64,
+    pub planet_hops: u64,
+    pub star_hops_two: u64,
+    pub star_hops_three: u64,
+    pub withdrawals: u64,
+    pub fees: u64,
+}
@@ -0,0 +1,358 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use super::*;
+
+///------------------------------------------------------------//
+/// MINT CONFIG PDA
+/// One per allowed mint. Planets only serve mints with a config.
+///------------------------------------------------------------//
+#[derive(Accounts)]
+pub struct CreateMintConfig<'info> {
+    #[account(
+        init,
+        payer = manager,
+        space = DISCRIMINATOR_LENGTH + MintConfig::INIT_SPACE,
+        seeds = [
+            MINT_CONFIG_SEED_PRE,
+            mint.key().as_ref(),
+            MINT_CONFIG_SEED_POST
+        ],
+        bump
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMintConfig<'info> {
+    #[account(mut)]
+    pub mint_config: Account<'info, MintConfig>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+}
+
+//Planets still serving the mint must be deleted first.
+#[derive(Accounts)]
+pub struct DeleteMintConfig<'info> {
+    #[account(mut, close = manager, constraint = mint_config.p.is_empty() @ OridionError::MintConfigInUse)]
+    pub mint_config: Account<'info, MintConfig>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MintConfig {
+    pub mint: Pubkey, //Allowed mint
+    pub min: u64, //Minimum deposit amount
+    pub max: u64, //Maximum deposit amount
+    pub hpfe: Option<u64>, //Hop planet fee in mint units, taken out of the deposit
+    pub wfe: Option<u64>, //Withdraw fee in mint units, taken out of the deposit
+    #[max_len(MAX_PLANETS)]
+    pub p: Vec<u16>, //Planets serving the mint, in planet index order
+    pub bump: u8, //Bump
+}
+
+///------------------------------------------------------------//
+/// TOKEN DEPOSITS
+/// Planets and stars hold tokens in their associated token
+/// accounts. Deposit.mint records the deposited token. Both the
+/// SPL Token and Token-2022 programs are accepted.
+///------------------------------------------------------------//
+#[derive(Accounts)]
+pub struct CreatePlanetTokenAccount<'info> {
+    #[account(constraint = planet.mints.contains(&mint.key()) @ OridionError::PlanetMintNotServed)]
+    pub planet: Account<'info,Planet>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(init, payer = manager, associated_token::mint = mint, associated_token::authority = planet, associated_token::token_program = token_program)]
+    pub planet_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateTokenDeposit<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = DISCRIMINATOR_LENGTH + Deposit::INIT_SPACE,
+        seeds = [
+            DEPOSIT_PDA_SEED_PRE,
+            creator.key().as_ref(),
+            DEPOSIT_PDA_SEED_POST
+        ],
+        bump
+    )]
+    pub deposit: Account<'info,Deposit>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    #[account(mut, token::mint = mint, token::authority = creator, token::token_program = token_program)]
+    pub creator_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, constraint = planet.mints.contains(&mint.key()) @ OridionError::PlanetMintNotServed)]
+    pub planet: Account<'info,Planet>,
+    #[account(mut, associated_token::mint = mint, associated_token::authority = planet, associated_token::token_program = token_program)]
+    pub planet_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(seeds = [MINT_CONFIG_SEED_PRE, mint.key().as_ref(), MINT_CONFIG_SEED_POST], bump = mint_config.bump)]
+    pub mint_config: Account<'info, MintConfig>,
+    pub destination: SystemAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info,System>,
+    #[account(mut, seeds = [PLANET_STATS_SEED, planet.id.to_le_bytes().as_ref()], bump = stats.bump)]
+    pub stats: Account<'info, PlanetStats>,
+}
+
+//The hop fee goes to the manager's token account.
+#[derive(Accounts)]
+pub struct TokenPlanetHop<'info> {
+    #[account(mut, constraint = deposit.mint == mint.key() @ OridionError::DepositMintMismatch)]
+    pub deposit: Account<'info, Deposit>,
+    #[account(mut, constraint = to_planet.mints.contains(&mint.key()) @ OridionError::PlanetMintNotServed)]
+    pub to_planet: Account<'info,Planet>,
+    #[account(mut, associated_token::mint = mint, associated_token::authority = to_planet, associated_token::token_program = token_program)]
+    pub to_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, constraint = from_planet.id == deposit.location @ OridionError::HopErrorWrongPlanet)]
+    pub from_planet: Account<'info,Planet>,
+    #[account(mut, associated_token::mint = mint, associated_token::authority = from_planet, associated_token::token_program = token_program)]
+    pub from_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(seeds = [MINT_CONFIG_SEED_PRE, mint.key().as_ref(), MINT_CONFIG_SEED_POST], bump = mint_config.bump)]
+    pub mint_config: Box<Account<'info, MintConfig>>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+    #[account(init_if_needed, payer = manager, associated_token::mint = mint, associated_token::authority = manager, associated_token::token_program = token_program)]
+    pub manager_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    #[account(seeds = [UNIVERSE_PDA_SEED], bump = universe.load()?.bp)]
+    pub universe: AccountLoader<'info, Universe>,
+    #[account(mut, seeds = [PLANET_STATS_SEED, to_planet.id.to_le_bytes().as_ref()], bump = stats.bump)]
+    pub stats: Box<Account<'info, PlanetStats>>,
+}
+
+//The withdraw fee goes to the manager's token account.
+#[derive(Accounts)]
+pub struct TokenWithdraw<'info> {
+    #[account(mut, close = manager, constraint = deposit.mint == mint.key() @ OridionError::DepositMintMismatch)]
+    pub deposit: Account<'info, Deposit>,
+    #[account(mut, constraint = from_planet.id == deposit.location @ OridionError::HopErrorWrongPlanet)]
+    pub from_planet: Account<'info,Planet>,
+    #[account(mut, associated_token::mint = mint, associated_token::authority = from_planet, associated_token::token_program = token_program)]
+    pub from_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = deposit.destination)]
+    pub destination: SystemAccount<'info>,
+    #[account(init_if_needed, payer = manager, associated_token::mint = mint, associated_token::authority = destination, associated_token::token_program = token_program)]
+    pub destination_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(seeds = [MINT_CONFIG_SEED_PRE, mint.key().as_ref(), MINT_CONFIG_SEED_POST], bump = mint_config.bump)]
+    pub mint_config: Box<Account<'info, MintConfig>>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+    #[account(init_if_needed, payer = manager, associated_token::mint = mint, associated_token::authority = manager, associated_token::token_program = token_program)]
+    pub manager_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    #[account(mut, seeds = [PLANET_STATS_SEED, from_planet.id.to_le_bytes().as_ref()], bump = stats.bump)]
+    pub stats: Box<Account<'info, PlanetStats>>,
+}
+
+
+//Token star hop from Planet to Split stars
+#[derive(Accounts)]
+#[instruction(star_one_id: String, star_two_id: String)]
+pub struct TokenStarHopTwoStart<'info> {
+    #[account(mut, constraint = deposit.mint == mint.key() @ OridionError::DepositMintMismatch)]
+    pub deposit: Account<'info, Deposit>,
+    #[account(mut, constraint = from_planet.id == deposit.location @ OridionError::HopErrorWrongPlanet)]
+    pub from_planet: Account<'info,Planet>,
+    #[account(mut, associated_token::mint = mint, associated_token::authority = from_planet, associated_token::token_program = token_program)]
+    pub from_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(init, payer = manager, space = DISCRIMINATOR_LENGTH + Star::INIT_SPACE,
+        seeds = [
+            STAR_SEED_PRE,
+            star_one_id.as_ref(),
+            STAR_SEED_POST
+        ],
+        bump
+    )]
+    pub star_one: Account<'info, Star>,
+    #[account(init, payer = manager, associated_token::mint = mint, associated_token::authority = star_one, associated_token::token_program = token_program)]
+    pub star_one_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(init, payer = manager, space = DISCRIMINATOR_LENGTH + Star::INIT_SPACE,
+        seeds = [
+            STAR_SEED_PRE,
+            star_two_id.as_ref(),
+            STAR_SEED_POST
+        ],
+        bump
+    )]
+    pub star_two: Account<'info, Star>,
+    #[account(init, payer = manager, associated_token::mint = mint, associated_token::authority = star_two, associated_token::token_program = token_program)]
+    pub star_two_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+//Return from token stars to destination planet. Star ids are needed to sign for the stars.
+#[derive(Accounts)]
+#[instruction(star_one_id: String, star_two_id: String)]
+pub struct TokenStarHopTwoEnd<'info> {
+    #[account(mut, constraint = deposit.mint == mint.key() @ OridionError::DepositMintMismatch)]
+    pub deposit: Account<'info, Deposit>,
+    #[account(mut, constraint = to_planet.mints.contains(&mint.key()) @ OridionError::PlanetMintNotServed)]
+    pub to_planet: Account<'info,Planet>,
+    #[account(mut, associated_token::mint = mint, associated_token::authority = to_planet, associated_token::token_program = token_program)]
+    pub to_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, close = manager, has_one = manager,
+        seeds = [
+            STAR_SEED_PRE,
+            star_one_id.as_ref(),
+            STAR_SEED_POST
+        ],
+        bump
+    )]
+    pub star_one: Account<'info, Star>,
+    #[account(mut, associated_token::mint = mint, associated_token::authority = star_one, associated_token::token_program = token_program)]
+    pub star_one_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, close = manager, has_one = manager,
+        seeds = [
+            STAR_SEED_PRE,
+            star_two_id.as_ref(),
+            STAR_SEED_POST
+        ],
+        bump
+    )]
+    pub star_two: Account<'info, Star>,
+    #[account(mut, associated_token::mint = mint, associated_token::authority = star_two, associated_token::token_program = token_program)]
+    pub star_two_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)] //Withheld transfer fees are harvested into the mint
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(seeds = [MINT_CONFIG_SEED_PRE, mint.key().as_ref(), MINT_CONFIG_SEED_POST], bump = mint_config.bump)]
+    pub mint_config: Box<Account<'info, MintConfig>>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    #[account(seeds = [UNIVERSE_PDA_SEED], bump = universe.load()?.bp)]
+    pub universe: AccountLoader<'info, Universe>,
+    #[account(mut, seeds = [PLANET_STATS_SEED, to_planet.id.to_le_bytes().as_ref()], bump = stats.bump)]
+    pub stats: Account<'info, PlanetStats>,
+}
+
+
+#[derive(Accounts)]
+#[instruction(star_one_id: String, star_two_id: String, star_three_id: String)]
+pub struct TokenStarHopThreeStart<'info> {
+    #[account(mut, constraint = deposit.mint == mint.key() @ OridionError::DepositMintMismatch)]
+    pub deposit: Account<'info, Deposit>,
+    #[account(mut, constraint = from_planet.id == deposit.location @ OridionError::HopErrorWrongPlanet)]
+    pub from_planet: Account<'info,Planet>,
+    #[account(mut, associated_token::mint = mint, associated_token::authority = from_planet, associated_token::token_program = token_program)]
+    pub from_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(init, payer = manager, space = DISCRIMINATOR_LENGTH + Star::INIT_SPACE,
+        seeds = [
+            STAR_SEED_PRE,
+            star_one_id.as_ref(),
+            STAR_SEED_POST
+        ],
+        bump
+    )]
+    pub star_one: Account<'info, Star>,
+    #[account(init, payer = manager, associated_token::mint = mint, associated_token::authority = star_one, associated_token::token_program = token_program)]
+    pub star_one_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(init, payer = manager, space = DISCRIMINATOR_LENGTH + Star::INIT_SPACE,
+        seeds = [
+            STAR_SEED_PRE,
+            star_two_id.as_ref(),
+            STAR_SEED_POST
+        ],
+        bump
+    )]
+    pub star_two: Account<'info, Star>,
+    #[account(init, payer = manager, associated_token::mint = mint, associated_token::authority = star_two, associated_token::token_program = token_program)]
+    pub star_two_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(init, payer = manager, space = DISCRIMINATOR_LENGTH + Star::INIT_SPACE,
+        seeds = [
+            STAR_SEED_PRE,
+            star_three_id.as_ref(),
+            STAR_SEED_POST
+        ],
+        bump
+    )]
+    pub star_three: Account<'info, Star>,
+    #[account(init, payer = manager, associated_token::mint = mint, associated_token::authority = star_three, associated_token::token_program = token_program)]
+    pub star_three_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(star_one_id: String, star_two_id: String, star_three_id: String)]
+pub struct TokenStarHopThreeEnd<'info> {
+    #[account(mut, constraint = deposit.mint == mint.key() @ OridionError::DepositMintMismatch)]
+    pub deposit: Account<'info, Deposit>,
+    #[account(mut, constraint = to_planet.mints.contains(&mint.key()) @ OridionError::PlanetMintNotServed)]
+    pub to_planet: Account<'info,Planet>,
+    #[account(mut, associated_token::mint = mint, associated_token::authority = to_planet, associated_token::token_program = token_program)]
+    pub to_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, close = manager, has_one = manager,
+        seeds = [
+            STAR_SEED_PRE,
+            star_one_id.as_ref(),
+            STAR_SEED_POST
+        ],
+        bump
+    )]
+    pub star_one: Account<'info, Star>,
+    #[account(mut, associated_token::mint = mint, associated_token::authority = star_one, associated_token::token_program = token_program)]
+    pub star_one_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, close = manager, has_one = manager,
+        seeds = [
+            STAR_SEED_PRE,
+            star_two_id.as_ref(),
+            STAR_SEED_POST
+        ],
+        bump
+    )]
+    pub star_two: Account<'info, Star>,
+    #[account(mut, associated_token::mint = mint, associated_token::authority = star_two, associated_token::token_program = token_program)]
+    pub star_two_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, close = manager, has_one = manager,
+        seeds = [
+            STAR_SEED_PRE,
+            star_three_id.as_ref(),
+            STAR_SEED_POST
+        ],
+        bump
+    )]
+    pub star_three: Account<'info, Star>,
+    #[account(mut, associated_token::mint = mint, associated_token::authority = star_three, associated_token::token_program = token_program)]
+    pub star_three_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)] //Withheld transfer fees are harvested into the mint
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(seeds = [MINT_CONFIG_SEED_PRE, mint.key().as_ref(), MINT_CONFIG_SEED_POST], bump = mint_config.bump)]
+    pub mint_config: Box<Account<'info, MintConfig>>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub manager: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    #[account(seeds = [UNIVERSE_PDA_SEED], bump = universe.load()?.bp)]
+    pub universe: AccountLoader<'info, Universe>,
+    #[account(mut, seeds = [PLANET_STATS_SEED, to_planet.id.to_le_bytes().as_ref()], bump = stats.bump)]
+    pub stats: Account<'info, PlanetStats>,
+}
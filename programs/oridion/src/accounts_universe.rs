@@ -13,7 +13,15 @@ pub struct BigBang<'info> {
         seeds = [UNIVERSE_PDA_SEED],
         bump
     )]
-    pub universe: Account<'info, Universe>,
+    pub universe: AccountLoader<'info, Universe>,
+    #[account(
+        init,
+        payer = creator,
+        space = PlanetIndex::LEN,
+        seeds = [PLANET_INDEX_SEED],
+        bump
+    )]
+    pub planet_index: Account<'info, PlanetIndex>,
     #[account(mut, address = MANAGER_PUBKEY)]
     pub creator: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -23,35 +31,87 @@ pub struct BigBang<'info> {
 #[derive(Accounts)]
 pub struct UpdateUniverseFee<'info> {
     #[account(mut)]
-    pub universe: Account<'info, Universe>,
+    pub universe: AccountLoader<'info, Universe>,
     #[account(mut, address = MANAGER_PUBKEY)]
     pub creator: Signer<'info>
 }
 
-#[account]
+#[derive(Accounts)]
+pub struct UpdateUniverseBuckets<'info> {
+    #[account(mut)]
+    pub universe: AccountLoader<'info, Universe>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub creator: Signer<'info>
+}
+
+#[derive(Accounts)]
+pub struct UpdateUniverseRecentWindow<'info> {
+    #[account(mut)]
+    pub universe: AccountLoader<'info, Universe>,
+    #[account(seeds = [PLANET_INDEX_SEED], bump = planet_index.bump)]
+    pub planet_index: Account<'info, PlanetIndex>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub creator: Signer<'info>
+}
+
+#[derive(Accounts)]
+pub struct UpdateUniverseHopDestinationMode<'info> {
+    #[account(mut)]
+    pub universe: AccountLoader<'info, Universe>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub creator: Signer<'info>
+}
+
+#[derive(Accounts)]
+pub struct UpdateUniverseLookupTable<'info> {
+    #[account(mut)]
+    pub universe: AccountLoader<'info, Universe>,
+    /// CHECK: Only its owner is checked, the table's contents are up to the clients
+    #[account(owner = solana_program::address_lookup_table::program::ID @ OridionError::LookupTableInvalid)]
+    pub lookup_table: UncheckedAccount<'info>,
+    #[account(mut, address = MANAGER_PUBKEY)]
+    pub creator: Signer<'info>
+}
+
+//Fixed size config header, read without deserializing.
+//Planet ids live in the PlanetIndex.
+#[account(zero_copy)]
 pub struct Universe {
+    pub version: u8, //Account layout version
+    pub bp: u8, // Bump
+    pub bkl: u8, // Denomination buckets in use
+    pub rw: u8, // Recent window: hops may not land on the last rw planets a deposit left (0 off)
+    pub hdm: u8, // Hop destination mode (0 manager selected, 1 program selected)
+    pub padding: [u8; 3],
     pub pda: Pubkey, //PDA
-    pub p: Vec<String>, //Planets
     pub st: i64, //Universe started
     pub up: i64, //Last updated (used for comet random id)
-    pub bp: u8, // Bump
     pub cfe: u64, // Comet Fee in lamports
     pub hpfe: u64, // Hop planet Fee in lamports
     pub hsfe2: u64, // Hop star Fee in lamports
     pub hsfe3: u64, // Hop star 3 Fee in lamports
-    pub wfe: u64 // Withdraw Fee in lamports
+    pub wfe: u64, // Withdraw Fee in lamports
+    pub bk: [u64; MAX_BUCKETS], // Denomination buckets in lamports (first bkl)
+    pub lut: Pubkey, // Address lookup table clients build v0 transactions with (default until set)
 }
 
 impl Universe {
-    const LEN: usize = DISCRIMINATOR_LENGTH
-        + PUBLIC_KEY_LENGTH //Universe PDA
-        + PLANETS_VEC_LENGTH // Planets vector (planets count can be counted here!)
-        + TIMESTAMP_LENGTH // Universe started
-        + TIMESTAMP_LENGTH // Last star generated
-        + U8_LENGTH //Bump
-        + LAMPORT_LENGTH // Comet fee
-        + LAMPORT_LENGTH // Hop planet fee
-        + LAMPORT_LENGTH // Hop star2 fee
-        + LAMPORT_LENGTH // Hop star3 fee
-        + LAMPORT_LENGTH; // Withdraw fee
-}
\ No newline at end of file
+    pub const LEN: usize = DISCRIMINATOR_LENGTH + std::mem::size_of::<Universe>();
+
+    pub fn buckets(&self) -> &[u64] {
+        &self.bk[..self.bkl as usize]
+    }
+}
+
+//Planet ids, only loaded by instructions that pick or manage planets.
+#[account]
+pub struct PlanetIndex {
+    pub p: Vec<u16>, //Planet ids
+    pub bump: u8, // Bump
+}
+
+impl PlanetIndex {
+    pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + PLANET_INDEX_VEC_LENGTH // Planets vector
+        + U8_LENGTH; //Bump
+}
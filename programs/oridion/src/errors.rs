@@ -14,4 +14,100 @@ pub enum OridionError {
     PlanetNotEnoughFundsError,
     #[msg("Star split calculations do not add up!")]
     StarHopCalculationError,
+    #[msg("Deposit is not due for processing")]
+    DepositNotDue,
+    #[msg("Planet does not match deposit location")]
+    HopErrorWrongPlanet,
+    #[msg("Destination planet does not match program selection")]
+    HopErrorWrongDestination,
+    #[msg("Deposit does not cover the fee")]
+    DepositTooSmallForFee,
+    #[msg("Deposit mint does not match")]
+    DepositMintMismatch,
+    #[msg("Mint extension is not supported")]
+    MintExtensionNotSupported,
+    #[msg("Transfer fee could not be calculated")]
+    TransferFeeError,
+    #[msg("Planet does not serve this mint")]
+    PlanetMintNotServed,
+    #[msg("Planet serves too many mints")]
+    PlanetTooManyMints,
+    #[msg("Mint config missing for planet mint")]
+    MintConfigMissing,
+    #[msg("Mint config minimum is above maximum")]
+    MintConfigInvalid,
+    #[msg("Deposit amount is outside the mint limits")]
+    DepositAmountOutOfRange,
+    #[msg("Buckets must be unique, non-zero and at most MAX_BUCKETS")]
+    BucketsInvalid,
+    #[msg("Deposit amount does not match a bucket")]
+    DepositNotABucket,
+    #[msg("Destination splits are invalid")]
+    SplitsInvalid,
+    #[msg("Split destination accounts do not match the deposit")]
+    SplitDestinationMismatch,
+    #[msg("Deposit has already started hopping")]
+    DepositAlreadyStarted,
+    #[msg("Signer did not create this deposit")]
+    DepositCreatorMismatch,
+    #[msg("Withdrawal schedule is invalid")]
+    ScheduleInvalid,
+    #[msg("Next tranche is not due yet")]
+    TrancheNotDue,
+    #[msg("Deposit destination is committed and must be revealed")]
+    DestinationCommitted,
+    #[msg("Destination and salt do not match the commitment")]
+    CommitmentMismatch,
+    #[msg("Secret does not match the deposit note")]
+    NoteMismatch,
+    #[msg("Relayer is not registered")]
+    RelayerNotRegistered,
+    #[msg("Relayer is already registered")]
+    RelayerAlreadyRegistered,
+    #[msg("Relayer registry is full")]
+    RelayerRegistryFull,
+    #[msg("Relayer fee is above the maximum")]
+    RelayerFeeTooHigh,
+    #[msg("Account is not a migratable program account")]
+    MigrationInvalidAccount,
+    #[msg("Account is already at the current version")]
+    AccountAlreadyMigrated,
+    #[msg("Planet index is full")]
+    PlanetIndexFull,
+    #[msg("Lamports were created or destroyed by a transfer")]
+    LamportsNotConserved,
+    #[msg("Account is not an address lookup table")]
+    LookupTableInvalid,
+    #[msg("Route policy is invalid")]
+    RoutePolicyInvalid,
+    #[msg("Deposit has not made the hops its route policy requires")]
+    RouteIncomplete,
+    #[msg("Deposit has made the most hops its route policy allows")]
+    RouteMaxHopsReached,
+    #[msg("Hop fee is over the deposit's fee budget")]
+    RouteFeeBudgetExceeded,
+    #[msg("Route policy does not allow landing on a planet twice")]
+    RoutePlanetRevisited,
+    #[msg("Recent window is larger than MAX_RECENT_PLANETS or leaves no planet to hop to")]
+    RecentWindowInvalid,
+    #[msg("Deposit left this planet within the universe's recent window")]
+    HopErrorRecentPlanet,
+    #[msg("Hop destination mode must be 0 (manager selected) or 1 (program selected)")]
+    HopDestinationModeInvalid,
+    #[msg("Batch hop entries are (deposit, from planet, to planet, to planet stats), all writable")]
+    BatchHopInvalid,
+    #[msg("Deposit is in stars between a star hop start and end")]
+    DepositInStarHop,
+    #[msg("Deposit has no star hop through this many stars to end")]
+    StarHopNotStarted,
+    #[msg("Token hops are manager selected and fail while the program selects hop destinations")]
+    TokenHopDestinationModeInvalid, //No longer raised, kept so later error codes do not move
+    #[msg("Migrated deposits are held by 0, 2 or 3 stars")]
+    MigrationStarsInvalid,
+    #[msg("Mints with a freeze authority are not supported")]
+    MintFreezeAuthorityNotSupported,
+    #[msg("Mint config is still used by planets")]
+    MintConfigInUse,
+    #[msg("Planet ids must be below MAX_PLANETS")]
+    PlanetIdInvalid,
 }
\ No newline at end of file
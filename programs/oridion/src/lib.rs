@@ -1,9 +1,13 @@
-mod variables;
-mod accounts_universe;
-mod accounts_comet;
-mod accounts_planet;
-mod shared;
-mod errors;
+pub mod variables;
+pub mod accounts_universe;
+pub mod accounts_comet;
+pub mod accounts_planet;
+pub mod accounts_token;
+pub mod accounts_relayer;
+pub mod accounts_stats;
+pub mod accounts_migrate;
+pub mod shared;
+pub mod errors;
 
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::system_instruction;
@@ -14,6 +18,11 @@ use shared::*;
 use accounts_universe::*;
 use accounts_comet::*;
 use accounts_planet::*;
+use accounts_token::*;
+use accounts_relayer::*;
+use accounts_stats::*;
+use accounts_migrate::*;
+use anchor_spl::token_interface;
 use errors::*;
 
 declare_id!("33J2bC6ZYvg8Y77MWULZEWpWRkxCoM2aziZ5f67dwBXi");
@@ -29,7 +38,6 @@ security_txt! {
 
 #[program]
 pub mod oridion {
-    use solana_program::instruction::Instruction;
     use super::*;
 
     ///-------------------------------------------------------------------///
@@ -37,9 +45,9 @@ pub mod oridion {
     ///-------------------------------------------------------------------///
     pub fn bang(ctx: Context<BigBang>) -> Result<()> {
         let clock: Clock = Clock::get().unwrap();
-        let universe: &mut Account<Universe> = &mut ctx.accounts.universe;
+        let universe = &mut ctx.accounts.universe.load_init()?;
+        universe.version = ACCOUNT_VERSION;
         universe.bp = ctx.bumps.universe; // store bump seed in `Counter` account
-        universe.p = Vec::<String>::new();
         universe.st = clock.unix_timestamp;
         universe.up = clock.unix_timestamp; //must set this here as well for random comet id
         universe.cfe = 0; //Comet fee Lamports (0) - Starts at 0 cents (when solana is $100 per 1)
@@ -47,8 +55,16 @@ pub mod oridion {
         universe.hsfe2 = 2000000; //Hop star lamports (2000000)
         universe.hsfe3 = 3000000; //Hop star lamports (3000000)
         universe.wfe = 0; //Withdraw Lamports (0) - no withdraw fee - Starts at .0 cents (when solana is $100 per 1)
-        let(pda, _bump_seed) = Pubkey::find_program_address(&[UNIVERSE_PDA_SEED], &ctx.program_id);
+        universe.bkl = 0; //No denomination buckets until set
+        universe.rw = 0; //Hops may land anywhere until a recent window is set
+        universe.hdm = 0; //Manager selects hop destinations
+        let(pda, _bump_seed) = Pubkey::find_program_address(&[UNIVERSE_PDA_SEED], ctx.program_id);
         universe.pda = pda;
+
+        //Planets are kept in their own index
+        let planet_index: &mut Account<PlanetIndex> = &mut ctx.accounts.planet_index;
+        planet_index.p = Vec::<u16>::new();
+        planet_index.bump = ctx.bumps.planet_index;
         Ok(())
     }
 
@@ -57,7 +73,7 @@ pub mod oridion {
     ///-------------------------------------------------------------------///
     pub fn update_fee(ctx: Context<UpdateUniverseFee>, comet_fee: u32, hop_planet_fee: u32, hop_star_fee2: u32, hop_star_fee3: u32, withdraw_fee: u32) -> Result<()> {
         let clock: Clock = Clock::get().unwrap();
-        let universe: &mut Account<Universe> = &mut ctx.accounts.universe;
+        let universe = &mut ctx.accounts.universe.load_mut()?;
         universe.up = clock.unix_timestamp; //must set this here as well for random comet id
         universe.cfe = comet_fee as u64; //Lamports
         universe.hpfe = hop_planet_fee as u64; //Lamports
@@ -67,112 +83,332 @@ pub mod oridion {
         Ok(())
     }
 
+    /// -------------------------------------------------------------------///
+    /// UPDATE DENOMINATION BUCKETS
+    /// Empty list turns bucket deposits off.
+    ///-------------------------------------------------------------------///
+    pub fn update_buckets(ctx: Context<UpdateUniverseBuckets>, buckets: Vec<u64>) -> Result<()> {
+        require!(buckets.len() <= MAX_BUCKETS && !buckets.contains(&0), OridionError::BucketsInvalid);
+        let mut sorted: Vec<u64> = buckets.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        require!(sorted.len() == buckets.len(), OridionError::BucketsInvalid);
+
+        let clock: Clock = Clock::get().unwrap();
+        let universe = &mut ctx.accounts.universe.load_mut()?;
+        universe.up = clock.unix_timestamp;
+        universe.bk = [0; MAX_BUCKETS];
+        universe.bk[..sorted.len()].copy_from_slice(&sorted);
+        universe.bkl = sorted.len() as u8;
+        Ok(())
+    }
+
+    /// -------------------------------------------------------------------///
+    /// UPDATE RECENT WINDOW
+    /// Hops and star hop ends may not land on any of the last `window`
+    /// planets a deposit left. 0 turns the check off. The window must
+    /// leave a deposit at least one planet besides its own to hop to,
+    /// delete_planet checks it again.
+    ///-------------------------------------------------------------------///
+    pub fn update_recent_window(ctx: Context<UpdateUniverseRecentWindow>, window: u8) -> Result<()> {
+        check_recent_window(window, ctx.accounts.planet_index.p.len())?;
+        let universe = &mut ctx.accounts.universe.load_mut()?;
+        universe.rw = window;
+        Ok(())
+    }
+
+    /// -------------------------------------------------------------------///
+    /// UPDATE HOP DESTINATION MODE
+    /// 0 the manager picks where planet hops and star hops land, 1 the
+    /// program selects the planet from the deposit and the planet index.
+    ///-------------------------------------------------------------------///
+    pub fn update_hop_destination_mode(ctx: Context<UpdateUniverseHopDestinationMode>, mode: u8) -> Result<()> {
+        require!(mode <= 1, OridionError::HopDestinationModeInvalid);
+        let universe = &mut ctx.accounts.universe.load_mut()?;
+        universe.hdm = mode;
+        Ok(())
+    }
+
+    /// -------------------------------------------------------------------///
+    /// UPDATE LOOKUP TABLE
+    /// Canonical address lookup table, so every client builds its v0
+    /// transactions against the same one.
+    ///-------------------------------------------------------------------///
+    pub fn update_lookup_table(ctx: Context<UpdateUniverseLookupTable>) -> Result<()> {
+        let universe = &mut ctx.accounts.universe.load_mut()?;
+        universe.lut = ctx.accounts.lookup_table.key();
+        Ok(())
+    }
+
+
+    ///-------------------------------------------------------------------///
+    /// GALAXY STATS
+    /// Created once by the manager. Deposit, hop and withdraw instructions
+    /// update the PlanetStats of their planet instead, anyone can fold
+    /// those in with aggregate_stats.
+    ///-------------------------------------------------------------------///
+    pub fn create_galaxy_stats(ctx: Context<CreateGalaxyStats>) -> Result<()> {
+        ctx.accounts.stats.bump = ctx.bumps.stats;
+        Ok(())
+    }
+
+    pub fn aggregate_stats<'info>(ctx: Context<'_, '_, 'info, 'info, AggregateStats<'info>>) -> Result<()> {
+        let galaxy: &mut Account<GalaxyStats> = &mut ctx.accounts.stats;
+        for account in ctx.remaining_accounts {
+            let mut planet: Account<PlanetStats> = Account::try_from(account)?;
+            aggregate_planet_stats(galaxy, &mut planet);
+            planet.exit(ctx.program_id)?;
+        }
+        Ok(())
+    }
+
+
+    ///-------------------------------------------------------------------///
+    /// MIGRATIONS
+    /// Rewrite v3.0.0 accounts at ACCOUNT_VERSION. Fields added since get
+    /// their defaults: no buckets, no mints, a single SOL destination.
+    /// Planets move to their id PDA and join the planet index.
+    ///-------------------------------------------------------------------///
+    pub fn migrate_universe(ctx: Context<MigrateUniverse>) -> Result<()> {
+        let account: &AccountInfo = ctx.accounts.universe.as_ref();
+        let old: UniverseV3 = read_legacy_account::<Universe, UniverseV3>(account, UniverseV3::LEN, ctx.program_id)?;
+        write_migrated_zero_copy(account, ctx.accounts.manager.as_ref(), ctx.accounts.system_program.as_ref(), &old.migrate())?;
+
+        let planet_index: &mut Account<PlanetIndex> = &mut ctx.accounts.planet_index;
+        planet_index.p = Vec::<u16>::new();
+        planet_index.bump = ctx.bumps.planet_index;
+        Ok(())
+    }
+
+    pub fn migrate_planet(ctx: Context<MigratePlanet>, id: u16) -> Result<()> {
+        let legacy: AccountInfo = ctx.accounts.legacy_planet.to_account_info();
+        let old: PlanetV3 = read_legacy_account::<Planet, PlanetV3>(&legacy, PlanetV3::LEN, ctx.program_id)?;
+        require!(old.pda == legacy.key(), OridionError::MigrationInvalidAccount);
+
+        let planet_index: &mut Account<PlanetIndex> = &mut ctx.accounts.planet_index;
+        require!(planet_index.p.len() < MAX_PLANETS, OridionError::PlanetIndexFull);
+        require!((id as usize) < MAX_PLANETS, OridionError::PlanetIdInvalid);
+        planet_index.p.push(id);
+
+        let planet_stats: &mut Account<PlanetStats> = &mut ctx.accounts.planet_stats;
+        planet_stats.id = id;
+        planet_stats.bump = ctx.bumps.planet_stats;
+
+        let planet: &mut Account<Planet> = &mut ctx.accounts.planet;
+        let planet_key: Pubkey = planet.key();
+        planet.set_inner(old.migrate(id, planet_key, ctx.bumps.planet));
+
+        // TRANSACTION - Deposits move to the new planet, the old rent goes back to manager
+        close_legacy_planet(&legacy, planet.as_ref(), ctx.accounts.manager.as_ref())
+    }
+
+    /// `stars` is 2 or 3 for a deposit whose v3.0.0 star hop has started but not ended, 0 otherwise.
+    pub fn migrate_deposit(ctx: Context<MigrateDeposit>, stars: u8) -> Result<()> {
+        require!(stars == 0 || stars == 2 || stars == 3, OridionError::MigrationStarsInvalid);
+        let account: AccountInfo = ctx.accounts.deposit.to_account_info();
+        let old: DepositV3 = read_legacy_account::<Deposit, DepositV3>(&account, DepositV3::LEN, ctx.program_id)?;
+        let planet: &Account<Planet> = &ctx.accounts.planet;
+        require!(planet.name == old.location, OridionError::HopErrorWrongPlanet);
+        write_migrated_account(&account, ctx.accounts.manager.as_ref(), ctx.accounts.system_program.as_ref(), &old.migrate(planet.id, stars), DISCRIMINATOR_LENGTH + Deposit::INIT_SPACE)
+    }
+
+    pub fn migrate_star(ctx: Context<MigrateStar>) -> Result<()> {
+        let account: AccountInfo = ctx.accounts.star.to_account_info();
+        let old: StarV3 = read_legacy_account::<Star, StarV3>(&account, StarV3::LEN, ctx.program_id)?;
+        write_migrated_account(&account, ctx.accounts.manager.as_ref(), ctx.accounts.system_program.as_ref(), &old.migrate(), DISCRIMINATOR_LENGTH + Star::INIT_SPACE)
+    }
+
 
     ///-------------------------------------------------------------------///
     /// CREATE PLANET
+    /// Token mints served by the planet are passed with their MintConfig
+    /// accounts as writable remaining accounts, in the same order.
     ///-------------------------------------------------------------------///
-    pub fn create_planet(ctx: Context<CreatePlanet>, name: String) -> Result<()> {
+    pub fn create_planet<'info>(ctx: Context<'_, '_, 'info, 'info, CreatePlanet<'info>>, id: u16, name: String, mints: Vec<Pubkey>) -> Result<()> {
         let clock: Clock = Clock::get().unwrap();
         let planet: &mut Account<Planet> = &mut ctx.accounts.planet;
-        let universe: &mut Account<Universe> = &mut ctx.accounts.universe;
+        let planet_index: &mut Account<PlanetIndex> = &mut ctx.accounts.planet_index;
 
         //Planet name too long
         require!(name.len() <= MAX_PLANET_TITLE_LENGTH, OridionError::PlanetNameTooLong);
+        require!(planet_index.p.len() < MAX_PLANETS, OridionError::PlanetIndexFull);
+        require!((id as usize) < MAX_PLANETS, OridionError::PlanetIdInvalid);
+
+        //Every mint must be on the allowlist
+        require!(mints.len() <= MAX_PLANET_MINTS, OridionError::PlanetTooManyMints);
+        update_mint_planets(id, &mints, ctx.remaining_accounts, true, ctx.program_id)?;
 
-        planet.name = name.clone();
+        planet.version = ACCOUNT_VERSION;
+        planet.id = id;
+        planet.name = name;
         planet.created = clock.unix_timestamp;
         planet.bump = ctx.bumps.planet;
-        planet.pda = get_planet_program_address(&name,&ctx.program_id);
+        planet.pda = get_planet_program_address(id, ctx.program_id);
         planet.visits = 0;
-        universe.p.push(name.clone());
+        planet.mints = mints;
+        planet_index.p.push(id);
+
+        let planet_stats: &mut Account<PlanetStats> = &mut ctx.accounts.planet_stats;
+        planet_stats.id = id;
+        planet_stats.bump = ctx.bumps.planet_stats;
         Ok(())
     }
 
     ///-------------------------------------------------------------------///
     /// DELETE PLANET
+    /// Fails if the planets left are too few for the recent window.
+    /// The MintConfigs of the planet's mints are passed as in create_planet.
     ///-------------------------------------------------------------------///
-    pub fn delete_planet(ctx: Context<DeletePlanet>) -> Result<()> {
+    pub fn delete_planet<'info>(ctx: Context<'_, '_, 'info, 'info, DeletePlanet<'info>>) -> Result<()> {
         let planet_lamports = ctx.accounts.planet.get_lamports();
 
-        //ERROR! PLANET HAS SOL! (1593840 == empty planet) 1593840
-        require!(planet_lamports <= 1593840, OridionError::PlanetDeleteHasFundsError);
+        //ERROR! PLANET HAS SOL! (rent exempt minimum == empty planet)
+        let empty_lamports = Rent::get()?.minimum_balance(ctx.accounts.planet.to_account_info().data_len());
+        require!(planet_lamports <= empty_lamports, OridionError::PlanetDeleteHasFundsError);
 
-        //Remove planet from universe list
-        let universe: &mut Account<Universe> = &mut ctx.accounts.universe;
+        //Remove planet from the planet index
+        let planet_index: &mut Account<PlanetIndex> = &mut ctx.accounts.planet_index;
         let planet: &mut Account<Planet> = &mut ctx.accounts.planet;
-        universe.p.retain(|x| x != &planet.name);
+        planet_index.p.retain(|x| *x != planet.id);
+        update_mint_planets(planet.id, &planet.mints, ctx.remaining_accounts, false, ctx.program_id)?;
+        let window: u8 = ctx.accounts.universe.load()?.rw;
+        check_recent_window(window, planet_index.p.len())?;
+
+        //Keep the planet's counters before its stats close
+        aggregate_planet_stats(&mut ctx.accounts.stats, &mut ctx.accounts.planet_stats);
         //msg!("== PLANET {} DELETED ==", planet.name.to_string());
         Ok(())
     }
 
     ///-------------------------------------------------------------------///
     /// CREATE DEPOSIT
-    /// Creates user's deposit and handles transfer from galaxy to planet. 
+    /// Creates user's deposit and transfers it straight to the planet.
     /// - Signed by user
     /// - Deposit (In lamports)
     /// - Occurs before creating deposit account.
     ///-------------------------------------------------------------------///
     pub fn create_deposit(ctx: Context<CreateDeposit>,deposit_lamports: u64, mode: u8, delay: u32, withdraw_at: i64 ) -> Result<()> {
 
-        let clock: Clock = Clock::get().unwrap();
-        let now = clock.unix_timestamp;
-        //msg!("Now Timestamp: {}", now.to_string());
+        // CREATE DEPOSIT ACCOUNT
+        let accounts: &mut CreateDeposit = ctx.accounts;
+        create_sol_deposit(&mut accounts.deposit, &mut accounts.planet, &mut accounts.stats, &accounts.creator, &accounts.system_program, deposit_lamports, mode, delay, withdraw_at)?;
+        accounts.deposit.destination = accounts.destination.key(); //Destination must be set.
+        Ok(())
+    }
+
+    ///-------------------------------------------------------------------///
+    /// CREATE COMMITTED DEPOSIT
+    /// Same as create_deposit, but the destination is never passed or stored.
+    /// The deposit keeps hash(destination + salt), revealed at withdraw_committed.
+    ///-------------------------------------------------------------------///
+    pub fn create_committed_deposit(ctx: Context<CreateCommittedDeposit>, deposit_lamports: u64, mode: u8, delay: u32, withdraw_at: i64, commitment: [u8; 32]) -> Result<()> {
+
+        // CREATE DEPOSIT ACCOUNT - No destination is stored, only its commitment
+        let accounts: &mut CreateCommittedDeposit = ctx.accounts;
+        create_sol_deposit(&mut accounts.deposit, &mut accounts.planet, &mut accounts.stats, &accounts.creator, &accounts.system_program, deposit_lamports, mode, delay, withdraw_at)?;
+        let deposit_account: &mut Account<Deposit> = &mut accounts.deposit;
+        deposit_account.dm = 1;
+        deposit_account.commitment = commitment;
+        Ok(())
+    }
+
+    ///-------------------------------------------------------------------///
+    /// CREATE NOTE DEPOSIT
+    /// Same as create_deposit, but the deposit only stores hash(secret + destination + relay fee).
+    /// Whoever presents the secret withdraws to that destination at withdraw_note.
+    ///-------------------------------------------------------------------///
+    pub fn create_note_deposit(ctx: Context<CreateCommittedDeposit>, deposit_lamports: u64, mode: u8, delay: u32, withdraw_at: i64, note: [u8; 32]) -> Result<()> {
+
+        // CREATE DEPOSIT ACCOUNT - No destination, only the hash of the secret
+        let accounts: &mut CreateCommittedDeposit = ctx.accounts;
+        create_sol_deposit(&mut accounts.deposit, &mut accounts.planet, &mut accounts.stats, &accounts.creator, &accounts.system_program, deposit_lamports, mode, delay, withdraw_at)?;
+        let deposit_account: &mut Account<Deposit> = &mut accounts.deposit;
+        deposit_account.dm = 2;
+        deposit_account.commitment = note;
+        Ok(())
+    }
+
+    ///-------------------------------------------------------------------///
+    /// CREATE BUCKET DEPOSIT
+    /// Same as create_deposit, but the amount must match one of the
+    /// universe buckets so withdrawals cannot be linked by value.
+    /// Larger amounts are split into several bucket deposits (nonces).
+    ///-------------------------------------------------------------------///
+    pub fn create_bucket_deposit(ctx: Context<CreateBucketDeposit>, _nonce: u8, deposit_lamports: u64, mode: u8, delay: u32, withdraw_at: i64) -> Result<()> {
+
+        // VALIDATION - Amount must be a bucket
+        require!(ctx.accounts.universe.load()?.buckets().contains(&deposit_lamports), OridionError::DepositNotABucket);
 
         // CREATE DEPOSIT ACCOUNT
-        let deposit_account: &mut Account<Deposit> = &mut ctx.accounts.deposit;
-        let destination: &AccountInfo = ctx.accounts.destination.as_ref();
-
-        deposit_account.created_at = now;
-        deposit_account.last_process_at = now;
-        deposit_account.hops = 2;
-        deposit_account.lamports = deposit_lamports;
-        deposit_account.mode = mode;
-        deposit_account.last_process = 0; //0 = deposit
-        deposit_account.delay = delay;
-        deposit_account.withdraw_at = withdraw_at;
-        deposit_account.destination = *destination.key; //Destination must be set.
-
-        //Depending on the withdrawal timestamp, set the next process and hop process timestamp
-        if (now + 180) > withdraw_at {
-            //Withdraw is the next action.
-            deposit_account.next_process = 1; //1 = withdraw
-            deposit_account.next_process_at = withdraw_at;
-        } else {
-            //Set the next hop processing timestamp
-            deposit_account.next_process = 0; //0 = hop
-            deposit_account.next_process_at = now + 180;
-        }
+        let accounts: &mut CreateBucketDeposit = ctx.accounts;
+        create_sol_deposit(&mut accounts.deposit, &mut accounts.planet, &mut accounts.stats, &accounts.creator, &accounts.system_program, deposit_lamports, mode, delay, withdraw_at)?;
+        accounts.deposit.destination = accounts.destination.key();
+        Ok(())
+    }
 
 
-        // INCREMENT PLANET VISIT
-        let planet: &mut Account<Planet> = &mut ctx.accounts.planet;
-        planet.visits += 1;
+    ///-------------------------------------------------------------------///
+    /// SET DEPOSIT SPLITS
+    /// Spreads the withdrawal over up to MAX_DESTINATIONS wallets, by basis
+    /// points (mode 1) or random shares above per-destination minimums (mode 2).
+    /// Only the creator, before the first hop. The first split destination
+    /// becomes the deposit destination.
+    ///-------------------------------------------------------------------///
+    pub fn set_deposit_splits(ctx: Context<SetDepositSplits>, nonce: Option<u8>, mode: u8, splits: Vec<DestinationSplit>) -> Result<()> {
+        check_deposit_creator(&ctx.accounts.deposit.key(), ctx.accounts.creator.key, nonce, ctx.program_id)?;
+
+        let deposit: &mut Account<Deposit> = &mut ctx.accounts.deposit;
+        validate_splits(mode, &splits, deposit.lamports)?;
+        if let Some(first) = splits.first() {
+            deposit.destination = first.destination;
+        }
+        deposit.sm = mode;
+        deposit.splits = splits;
+        Ok(())
+    }
 
 
-        //Set planet name to deposit
-        deposit_account.location = planet.name.clone();
+    ///-------------------------------------------------------------------///
+    /// SET WITHDRAW SCHEDULE
+    /// Staggers the withdrawal over up to MAX_TRANCHES tranches of random
+    /// size, spaced a random spacing_min..=spacing_max seconds apart.
+    /// Only the creator, before the first hop.
+    ///-------------------------------------------------------------------///
+    pub fn set_withdraw_schedule(ctx: Context<SetWithdrawSchedule>, nonce: Option<u8>, tranches: u8, spacing_min: u32, spacing_max: u32) -> Result<()> {
+        check_deposit_creator(&ctx.accounts.deposit.key(), ctx.accounts.creator.key, nonce, ctx.program_id)?;
+        validate_schedule(tranches, spacing_min, spacing_max)?;
 
+        let deposit: &mut Account<Deposit> = &mut ctx.accounts.deposit;
+        deposit.tranches = tranches;
+        deposit.spacing_min = spacing_min;
+        deposit.spacing_max = spacing_max;
+        Ok(())
+    }
 
-        // TRANSACTION SETUP
-        let creator_account: &Signer = &ctx.accounts.creator;
-        let universe_account: &Account<Universe> = &ctx.accounts.universe;
 
-        // DEPOSIT TRANSFER
-        let transfer_instruction: Instruction = system_instruction::transfer(creator_account.key, universe_account.to_account_info().key, deposit_lamports);
-        solana_program::program::invoke_signed(
-            &transfer_instruction,
-            &[
-                creator_account.to_account_info(),
-                universe_account.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-            &[],
-        )?;
+    ///-------------------------------------------------------------------///
+    /// SET ROUTE POLICY
+    /// Minimum and maximum hops, minimum star hops, a hop fee budget and
+    /// no revisits. Hops outside the policy fail and withdrawals wait for
+    /// its minimums, or for the deposit to run out of unvisited planets.
+    /// Only the creator, before the first hop. Only SOL deposits avoid revisits.
+    ///-------------------------------------------------------------------///
+    pub fn set_route_policy(ctx: Context<SetRoutePolicy>, nonce: Option<u8>, policy: RoutePolicy) -> Result<()> {
+        check_deposit_creator(&ctx.accounts.deposit.key(), ctx.accounts.creator.key, nonce, ctx.program_id)?;
+        let planets: &[u16] = &ctx.accounts.planet_index.p;
+        validate_route_policy(&policy, planets.len())?;
 
-        // TRANSACTION - From galaxy to planet
-        //msg!("Hopping from galaxy to {}", planet_account.name);
-        ctx.accounts.planet.add_lamports(deposit_lamports)?;
-        ctx.accounts.universe.sub_lamports(deposit_lamports)?;
+        //The planet index does not know which planets serve a mint
+        let deposit: &mut Account<Deposit> = &mut ctx.accounts.deposit;
+        require!(!policy.avoid_revisit || deposit.mint == NATIVE_SOL, OridionError::RoutePolicyInvalid);
+        deposit.route = policy;
+        deposit.visited = 0;
+        let location: u16 = deposit.location;
+        push_visited_planet(deposit, location);
+        //A delay deposit due to withdraw keeps hopping until the minimums are met
+        if !is_route_complete(deposit, planets) {
+            deposit.next_process = 0;
+        }
         Ok(())
     }
 
@@ -182,53 +418,242 @@ pub mod oridion {
     /// -------------------------------------------------------------------///
     pub fn planet_hop(ctx: Context<PlanetHop>) -> Result<()>{
 
+        let (window, hdm): (u8, u8) = {
+            let universe = ctx.accounts.universe.load()?;
+            (universe.rw, universe.hdm)
+        };
+        let deposit_key: Pubkey = ctx.accounts.deposit.key();
+
         //Deposit account for updating location
         let deposit: &mut Account<Deposit> = &mut ctx.accounts.deposit;
         let from: &mut Account<Planet> = &mut ctx.accounts.from_planet;
         let to: &mut Account<Planet> = &mut ctx.accounts.to_planet;
 
         // IMPORTANT VALIDATION: TO AND FROM CANNOT BE THE SAME
-        require!(from.name != to.name, OridionError::HopErrorToAndFromAreSame);
+        require!(from.id != to.id, OridionError::HopErrorToAndFromAreSame);
+
+        // IMPORTANT VALIDATION: IN PROGRAM SELECTED MODE THE MANAGER CANNOT CHOOSE THE DESTINATION
+        check_hop_destination(hdm, &ctx.accounts.planet_index.p, &deposit_key, deposit, to.id)?;
 
         // Update deposit with new data
-        deposit.location = to.name.clone();
-        hop_deposit(deposit);
+        land_deposit(deposit, to.id, 0, 0, window, &ctx.accounts.planet_index.p)?;
 
         //Increment visits
         to.visits += 1;
 
         // TRANSACTION: Move funds from planet to planet
-        ctx.accounts.to_planet.add_lamports(deposit.lamports)?;
-        ctx.accounts.from_planet.sub_lamports(deposit.lamports)?;
+        transfer_lamports(&[(ctx.accounts.from_planet.as_ref(), ctx.accounts.to_planet.as_ref(), deposit.lamports)])?;
+
+        record_hop(&mut ctx.accounts.stats, 0, 0)
+    }
 
+    ///-------------------------------------------------------------------///
+    /// BATCH HOP
+    /// Planet hops for many deposits in one transaction. Every entry is
+    /// checked on its own as in planet_hop, and any bad entry fails the batch.
+    ///-------------------------------------------------------------------///
+    pub fn batch_hop<'info>(ctx: Context<'_, '_, 'info, 'info, BatchHop<'info>>) -> Result<()> {
+        let (window, hdm): (u8, u8) = {
+            let universe = ctx.accounts.universe.load()?;
+            (universe.rw, universe.hdm)
+        };
+        let entries: &'info [AccountInfo<'info>] = ctx.remaining_accounts;
+        require!(!entries.is_empty(), OridionError::BatchHopInvalid);
+        //An incomplete last entry is a short chunk, rejected by batch_hop_entry
+        for entry in entries.chunks(BATCH_HOP_ACCOUNTS) {
+            batch_hop_entry(entry, window, hdm, &ctx.accounts.planet_index.p, ctx.program_id)?;
+        }
         Ok(())
     }
 
     /// WITHDRAW COMET FUNDS TO FINAL DESTINATION.
-    pub fn withdraw(ctx: Context<WithdrawAccounts>) -> Result<()> {
+    /// Scheduled deposits release one tranche per call and close after the last.
+    pub fn withdraw<'info>(ctx: Context<'_, '_, 'info, 'info, WithdrawAccounts<'info>>) -> Result<()> {
+        let accounts: &mut WithdrawAccounts = ctx.accounts;
+        let closed: bool = withdraw_tranche(&mut accounts.deposit, &mut accounts.from_planet, accounts.destination.as_ref(), ctx.remaining_accounts, accounts.manager.as_ref(), None, &accounts.planet_index.p)?;
+        record_withdrawal(&mut accounts.stats, closed, 0)
+    }
+
+    ///-------------------------------------------------------------------///
+    /// WITHDRAW COMMITTED
+    /// Withdraws a deposit that only stores hash(destination + salt).
+    /// The manager or the creator reveals the destination and salt; the
+    /// creator only once the deposit is due. Bucket deposits pass their nonce.
+    ///-------------------------------------------------------------------///
+    pub fn withdraw_committed(ctx: Context<WithdrawCommitted>, nonce: Option<u8>, salt: [u8; 32]) -> Result<()> {
+        let clock: Clock = Clock::get().unwrap();
+        let deposit: &Account<Deposit> = &ctx.accounts.deposit;
+
+        // IMPORTANT VALIDATION: REVEALED DESTINATION MUST MATCH THE COMMITMENT
+        let commitment: [u8; 32] = get_destination_commitment(ctx.accounts.destination.key, &salt);
+        require!(commitment == deposit.commitment, OridionError::CommitmentMismatch);
+
+        if ctx.accounts.authority.key() != MANAGER_PUBKEY {
+            check_deposit_creator(&deposit.key(), ctx.accounts.authority.key, nonce, ctx.program_id)?;
+            require!(clock.unix_timestamp >= deposit.withdraw_at && clock.unix_timestamp >= deposit.next_process_at, OridionError::DepositNotDue);
+        }
+
+        let accounts: &mut WithdrawCommitted = ctx.accounts;
+        let closed: bool = withdraw_tranche(&mut accounts.deposit, &mut accounts.from_planet, accounts.destination.as_ref(), &[], accounts.manager.as_ref(), None, &accounts.planet_index.p)?;
+        record_withdrawal(&mut accounts.stats, closed, 0)
+    }
+
+
+    ///-------------------------------------------------------------------///
+    /// WITHDRAW NOTE
+    /// Withdraws a note deposit to the destination its note commits to. The
+    /// secret, destination and relay fee are checked against the stored hash;
+    /// anyone holding the secret can withdraw once due. No relayer is paid.
+    ///-------------------------------------------------------------------///
+    pub fn withdraw_note(ctx: Context<WithdrawNote>, secret: [u8; 32], relay_fee: u64) -> Result<()> {
+        let clock: Clock = Clock::get().unwrap();
+        let deposit: &Account<Deposit> = &ctx.accounts.deposit;
+
+        // IMPORTANT VALIDATION: SECRET MUST BE THE NOTE PREIMAGE
+        require!(get_note_hash(&secret, ctx.accounts.destination.key, relay_fee) == deposit.commitment, OridionError::NoteMismatch);
+        require!(clock.unix_timestamp >= deposit.withdraw_at && clock.unix_timestamp >= deposit.next_process_at, OridionError::DepositNotDue);
+
+        let accounts: &mut WithdrawNote = ctx.accounts;
+        let closed: bool = withdraw_tranche(&mut accounts.deposit, &mut accounts.from_planet, accounts.destination.as_ref(), &[], accounts.manager.as_ref(), None, &accounts.planet_index.p)?;
+        record_withdrawal(&mut accounts.stats, closed, 0)
+    }
+
 
-        //Get deposit account to determine how much to withdraw
+    ///-------------------------------------------------------------------///
+    /// KEEPER SECTION
+    /// Permissionless hop and withdraw for delay deposits once due.
+    /// The keeper is paid KEEPER_REWARD_BPS of the fee, the rest goes to manager.
+    ///-------------------------------------------------------------------///
+    pub fn keeper_hop(ctx: Context<KeeperHop>) -> Result<()> {
+        let clock: Clock = Clock::get().unwrap();
+        let deposit_key: Pubkey = ctx.accounts.deposit.key();
         let deposit: &mut Account<Deposit> = &mut ctx.accounts.deposit;
-        let withdraw_lamports = deposit.lamports.clone();
+        let to: &mut Account<Planet> = &mut ctx.accounts.to_planet;
+        let universe = ctx.accounts.universe.load()?;
 
-        let from: &mut Account<Planet> = &mut ctx.accounts.from_planet;
+        // Only delay deposits waiting on a hop, landed on a planet
+        require!(deposit.mode == 1 && deposit.next_process == 0, OridionError::DepositNotDue);
+        check_deposit_landed(deposit)?;
+        require!(clock.unix_timestamp >= deposit.next_process_at, OridionError::DepositNotDue);
 
-        //VALIDATION 
-        let current_from_lamports_balance: u64 = from.get_lamports();
-        require!(current_from_lamports_balance > withdraw_lamports, OridionError::PlanetNotEnoughFundsError);
+        // IMPORTANT VALIDATION: KEEPER CANNOT CHOOSE THE DESTINATION
+        let selected = select_next_planet(&ctx.accounts.planet_index.p, &deposit_key, deposit);
+        require!(selected == Some(to.id), OridionError::HopErrorWrongDestination);
 
-        // TRANSACTION - Transfer to destination
-        ctx.accounts.destination.add_lamports(withdraw_lamports)?;
-        ctx.accounts.from_planet.sub_lamports(withdraw_lamports)?;
+        let fee: u64 = universe.hpfe;
+        require!(deposit.lamports > fee, OridionError::DepositTooSmallForFee);
+        let reward: u64 = get_keeper_reward(fee);
+        deposit.lamports -= fee;
 
-        //Remove remaining lamports from deposit
-        let deposit_remaining_lamports = ctx.accounts.deposit.get_lamports();
-        ctx.accounts.deposit.sub_lamports(deposit_remaining_lamports)?;
-        ctx.accounts.manager.add_lamports(deposit_remaining_lamports)?;
+        // Update deposit with new data
+        land_deposit(deposit, to.id, 0, fee, universe.rw, &ctx.accounts.planet_index.p)?;
 
+        //Increment visits
+        to.visits += 1;
+
+        // TRANSACTION: Move funds from planet to planet, fee to keeper and manager
+        let from: &AccountInfo = ctx.accounts.from_planet.as_ref();
+        transfer_lamports(&[
+            (from, ctx.accounts.to_planet.as_ref(), deposit.lamports),
+            (from, ctx.accounts.keeper.as_ref(), reward),
+            (from, ctx.accounts.manager.as_ref(), fee - reward),
+        ])?;
+        record_hop(&mut ctx.accounts.stats, 0, fee - reward)
+    }
+
+    pub fn keeper_withdraw<'info>(ctx: Context<'_, '_, 'info, 'info, KeeperWithdraw<'info>>) -> Result<()> {
+        let clock: Clock = Clock::get().unwrap();
+        let deposit_key: Pubkey = ctx.accounts.deposit.key();
+        let deposit: &mut Account<Deposit> = &mut ctx.accounts.deposit;
+
+        require!(deposit.mode == 1, OridionError::DepositNotDue);
+        check_deposit_landed(deposit)?;
+        require!(clock.unix_timestamp >= deposit.withdraw_at && clock.unix_timestamp >= deposit.next_process_at, OridionError::DepositNotDue);
+        require!(is_route_complete(deposit, &ctx.accounts.planet_index.p), OridionError::RouteIncomplete);
+
+        //The fee is charged on every tranche
+        let tranche_lamports: u64 = get_tranche_amount(&deposit_key, deposit)?;
+        let fee: u64 = ctx.accounts.universe.load()?.wfe;
+        require!(tranche_lamports > fee, OridionError::DepositTooSmallForFee);
+        let reward: u64 = get_keeper_reward(fee);
+        let withdraw_lamports: u64 = tranche_lamports - fee;
+
+        //VALIDATION
+        let current_from_lamports_balance: u64 = ctx.accounts.from_planet.get_lamports();
+        require!(current_from_lamports_balance > tranche_lamports, OridionError::PlanetNotEnoughFundsError);
+
+        // TRANSACTION - Transfer to destinations, fee to keeper and manager
+        let fees = [(ctx.accounts.keeper.as_ref(), reward), (ctx.accounts.manager.as_ref(), fee - reward)];
+        pay_destinations(&deposit_key, deposit, ctx.accounts.from_planet.as_ref(), ctx.accounts.destination.as_ref(), ctx.remaining_accounts, withdraw_lamports, &fees)?;
+
+        //Keep the deposit open until the last tranche
+        let closed: bool = tranche_lamports == ctx.accounts.deposit.lamports;
+        if closed {
+            ctx.accounts.deposit.close(ctx.accounts.manager.to_account_info())?;
+        } else {
+            schedule_next_tranche(&deposit_key, &mut ctx.accounts.deposit, tranche_lamports)?;
+        }
+        record_withdrawal(&mut ctx.accounts.stats, closed, fee - reward)
+    }
+
+
+    ///-------------------------------------------------------------------///
+    /// RELAYER SECTION
+    /// Registered relayers submit withdrawals of committed and note deposits
+    /// so destination wallets need no SOL. The relayer fee (capped by the
+    /// registry) comes out of the withdrawn amount.
+    ///-------------------------------------------------------------------///
+    pub fn create_relayer_registry(ctx: Context<CreateRelayerRegistry>, max_fee: u64) -> Result<()> {
+        let registry: &mut Account<RelayerRegistry> = &mut ctx.accounts.registry;
+        registry.relayers = Vec::new();
+        registry.max_fee = max_fee;
+        registry.bump = ctx.bumps.registry;
         Ok(())
     }
 
+    pub fn update_relayer_fee(ctx: Context<UpdateRelayerRegistry>, max_fee: u64) -> Result<()> {
+        ctx.accounts.registry.max_fee = max_fee;
+        Ok(())
+    }
+
+    pub fn add_relayer(ctx: Context<UpdateRelayerRegistry>, relayer: Pubkey) -> Result<()> {
+        let registry: &mut Account<RelayerRegistry> = &mut ctx.accounts.registry;
+        require!(!registry.relayers.contains(&relayer), OridionError::RelayerAlreadyRegistered);
+        require!(registry.relayers.len() < MAX_RELAYERS, OridionError::RelayerRegistryFull);
+        registry.relayers.push(relayer);
+        Ok(())
+    }
+
+    pub fn remove_relayer(ctx: Context<UpdateRelayerRegistry>, relayer: Pubkey) -> Result<()> {
+        let registry: &mut Account<RelayerRegistry> = &mut ctx.accounts.registry;
+        require!(registry.relayers.contains(&relayer), OridionError::RelayerNotRegistered);
+        registry.relayers.retain(|x| x != &relayer);
+        Ok(())
+    }
+
+    /// Withdraws a due committed deposit (`secret` is the salt) or note deposit
+    /// (`secret` is the preimage) through a registered relayer. A note fixes the
+    /// relayer fee, a committed deposit leaves it to the relayer within the cap.
+    pub fn relay_withdraw(ctx: Context<RelayWithdraw>, secret: [u8; 32], fee: u64) -> Result<()> {
+        let clock: Clock = Clock::get().unwrap();
+        let deposit: &Account<Deposit> = &ctx.accounts.deposit;
+        require!(fee <= ctx.accounts.registry.max_fee, OridionError::RelayerFeeTooHigh);
+
+        // IMPORTANT VALIDATION: SECRET MUST OPEN THE COMMITMENT OR NOTE
+        if deposit.dm == 1 {
+            let commitment: [u8; 32] = get_destination_commitment(ctx.accounts.destination.key, &secret);
+            require!(commitment == deposit.commitment, OridionError::CommitmentMismatch);
+        } else {
+            require!(get_note_hash(&secret, ctx.accounts.destination.key, fee) == deposit.commitment, OridionError::NoteMismatch);
+        }
+        require!(clock.unix_timestamp >= deposit.withdraw_at && clock.unix_timestamp >= deposit.next_process_at, OridionError::DepositNotDue);
+
+        let accounts: &mut RelayWithdraw = ctx.accounts;
+        let relayer: &AccountInfo = accounts.relayer.as_ref();
+        let closed: bool = withdraw_tranche(&mut accounts.deposit, &mut accounts.from_planet, accounts.destination.as_ref(), &[], accounts.manager.as_ref(), Some((relayer, fee)), &accounts.planet_index.p)?;
+        record_withdrawal(&mut accounts.stats, closed, 0)
+    }
+
 
     ///-------------------------------------------------------------------///
     /// STAR HOP SECTION
@@ -243,27 +668,25 @@ pub mod oridion {
 
         // IMPORTANT VALIDATION: STAR ONE AND TWO CANNOT BE THE SAME
         require!(star_one != star_two, OridionError::HopErrorStarsMustBeUnique);
+        start_star_hop(deposit, 2)?;
 
         //msg!("Validation successful");
-        let percent: f32 = get_random_percent();
-        let star_one_amount: u64 = ((percent / 100f32) * deposit.lamports as f32) as u64;
-        let star_two_amount: u64 = deposit.lamports - star_one_amount;
-        //msg!("Hopping to star 1: {}", star_one_amount.to_string());
-        //msg!("Hopping to Star 2: {}", star_two_amount.to_string());
-
-        //Make sure the addition of split amounts are equal to deposit
-        require!(star_one_amount + star_two_amount == deposit.lamports, OridionError::StarHopCalculationError);
+        let (star_one_amount, star_two_amount) = get_two_star_split(deposit.lamports)?;
 
         //Set amounts to accounts
-        star1.amount = star_one_amount.clone();
-        star2.amount = star_two_amount.clone();
+        star1.amount = star_one_amount;
+        star2.amount = star_two_amount;
         star1.manager = *manager.key;
+        star1.version = ACCOUNT_VERSION;
         star2.manager = *manager.key;
+        star2.version = ACCOUNT_VERSION;
 
         // TRANSACTION
-        ctx.accounts.star_one.add_lamports(star_one_amount)?;
-        ctx.accounts.star_two.add_lamports(star_two_amount)?;
-        ctx.accounts.from_planet.sub_lamports(deposit.lamports)?;
+        let from: &AccountInfo = ctx.accounts.from_planet.as_ref();
+        transfer_lamports(&[
+            (from, ctx.accounts.star_one.as_ref(), star_one_amount),
+            (from, ctx.accounts.star_two.as_ref(), star_two_amount),
+        ])?;
 
         Ok(())
     }
@@ -274,19 +697,26 @@ pub mod oridion {
     ///-------------------------------------------------------------------///
     pub fn star_hop_two_end(ctx: Context<StarHopTwoEnd>) -> Result<()>{
 
+        let (window, hdm): (u8, u8) = {
+            let universe = ctx.accounts.universe.load()?;
+            (universe.rw, universe.hdm)
+        };
+        let deposit_key: Pubkey = ctx.accounts.deposit.key();
         let deposit: &mut Account<Deposit> = &mut ctx.accounts.deposit;
 
         let to: &mut Account<Planet> = &mut ctx.accounts.to_planet;
         let star1: &mut Account<Star> = &mut ctx.accounts.star_one;
         let star2: &mut Account<Star> = &mut ctx.accounts.star_two;
-        let star_one_amount: u64 = star1.amount.clone();
-        let star_two_amount: u64 = star2.amount.clone();
+        let star_one_amount: u64 = star1.amount;
+        let star_two_amount: u64 = star2.amount;
 
         require!(star_one_amount + star_two_amount == deposit.lamports, OridionError::StarHopCalculationError);
 
+        // IMPORTANT VALIDATION: IN PROGRAM SELECTED MODE THE MANAGER CANNOT CHOOSE THE DESTINATION
+        check_hop_destination(hdm, &ctx.accounts.planet_index.p, &deposit_key, deposit, to.id)?;
+
         // Update deposit with new data
-        deposit.location = to.name.clone();
-        hop_deposit(deposit);
+        land_deposit(deposit, to.id, 2, 0, window, &ctx.accounts.planet_index.p)?;
 
          //Clear our star amount
         star1.amount = 0;
@@ -296,24 +726,18 @@ pub mod oridion {
         to.visits += 1;
         
         // TRANSACTIONS
-        // Transaction from stars one and two to destination planet       
-        let total_lamports: u64 = star_one_amount + star_two_amount;
-        ctx.accounts.star_one.sub_lamports(star_one_amount)?;
-        ctx.accounts.star_two.sub_lamports(star_two_amount)?;
-        ctx.accounts.to_planet.add_lamports(total_lamports)?;
-        //msg!("Hop from two stars to {} complete", to_planet_name);     
-        //msg!("Hop from two stars to planet complete");     
-
-        //Transfer out remaining lamports
-        let star_one_remaining_lamports = ctx.accounts.star_one.get_lamports();
-        let star_two_remaining_lamports = ctx.accounts.star_two.get_lamports();
-        ctx.accounts.manager.add_lamports(star_one_remaining_lamports)?;
-
-        ctx.accounts.star_one.sub_lamports(star_one_remaining_lamports)?;
-        ctx.accounts.star_two.sub_lamports(star_two_remaining_lamports)?;
-        ctx.accounts.manager.add_lamports(star_two_remaining_lamports)?;
-
-        Ok(())
+        // Transaction from stars one and two to destination planet,
+        // remaining lamports (rent) to the manager
+        let (star_one, star_two): (&AccountInfo, &AccountInfo) = (ctx.accounts.star_one.as_ref(), ctx.accounts.star_two.as_ref());
+        let (to, manager): (&AccountInfo, &AccountInfo) = (ctx.accounts.to_planet.as_ref(), ctx.accounts.manager.as_ref());
+        transfer_lamports(&[
+            (star_one, to, star_one_amount),
+            (star_two, to, star_two_amount),
+            (star_one, manager, star_one.lamports() - star_one_amount),
+            (star_two, manager, star_two.lamports() - star_two_amount),
+        ])?;
+
+        record_hop(&mut ctx.accounts.stats, 2, 0)
     }
 
 
@@ -335,47 +759,27 @@ pub mod oridion {
 
         // IMPORTANT VALIDATION: STAR ONE AND TWO CANNOT BE THE SAME
         require!(star_one != star_two && star_two != star_three && star_one != star_three, OridionError::HopErrorStarsMustBeUnique);
+        start_star_hop(deposit, 3)?;
 
-        let first_split_percent: f32 = get_random_percent();
-        let second_split_percent: f32 = 100f32 - first_split_percent;
-        //msg!("Split: {}% / {}%", first_split_percent.to_string(), second_split_percent.to_string());
-        
-        //Determine side amounts here
-        let side_one_amount: u64 = ((first_split_percent / 100f32) * deposit.lamports as f32) as u64;
-        let side_two_amount: u64 = deposit.lamports - side_one_amount;
-
-        let (star_one_amount, star_two_amount, star_three_amount) = if side_one_amount > side_two_amount {
-            //Side one is larger so we split side one.
-            //Side two is set as star 2
-            //We further split side one to create star one and star three
-            let one: u64 = ((second_split_percent / 100f32) * side_one_amount as f32) as u64;
-            let three: u64 = side_one_amount - one;
-            //msg!("Third split on first side");
-            (one,side_two_amount,three)
-        } else {
-            //Side two is larger so we split side two.
-            //Side one is set as star one
-            //We further split side two to create side two and side three
-            let two: u64 = ((second_split_percent / 100f32) * side_two_amount as f32) as u64;
-            let three: u64 = side_two_amount - two;
-            //msg!("Third split on second side");
-            (side_one_amount,two,three)
-        };
-
-        require!(star_one_amount + star_two_amount + star_three_amount == deposit.lamports, OridionError::StarHopCalculationError);
+        let (star_one_amount, star_two_amount, star_three_amount) = get_three_star_split(deposit.lamports)?;
 
         star1.amount = star_one_amount;
         star2.amount = star_two_amount;
         star3.amount = star_three_amount;
         star1.manager = *manager.key;
+        star1.version = ACCOUNT_VERSION;
         star2.manager = *manager.key;
+        star2.version = ACCOUNT_VERSION;
         star3.manager = *manager.key;
-
-        // Transfer from planet to star one and two
-        ctx.accounts.star_one.add_lamports(star_one_amount)?;
-        ctx.accounts.star_two.add_lamports(star_two_amount)?;
-        ctx.accounts.star_three.add_lamports(star_three_amount)?;
-        ctx.accounts.from_planet.sub_lamports(deposit.lamports)?;
+        star3.version = ACCOUNT_VERSION;
+
+        // Transfer from planet to stars one, two and three
+        let from: &AccountInfo = ctx.accounts.from_planet.as_ref();
+        transfer_lamports(&[
+            (from, ctx.accounts.star_one.as_ref(), star_one_amount),
+            (from, ctx.accounts.star_two.as_ref(), star_two_amount),
+            (from, ctx.accounts.star_three.as_ref(), star_three_amount),
+        ])?;
         Ok(())
     }
 
@@ -383,6 +787,11 @@ pub mod oridion {
     pub fn star_hop_three_end(ctx: Context<StarHopThreeEnd>) -> Result<()>{
 
         // Deposit account
+        let (window, hdm): (u8, u8) = {
+            let universe = ctx.accounts.universe.load()?;
+            (universe.rw, universe.hdm)
+        };
+        let deposit_key: Pubkey = ctx.accounts.deposit.key();
         let deposit: &mut Account<Deposit> = &mut ctx.accounts.deposit;
 
         let to: &mut Account<Planet> = &mut ctx.accounts.to_planet;
@@ -390,15 +799,17 @@ pub mod oridion {
         let star1: &mut Account<Star> = &mut ctx.accounts.star_one;
         let star2: &mut Account<Star> = &mut ctx.accounts.star_two;
         let star3: &mut Account<Star> = &mut ctx.accounts.star_three;
-        let star_one_amount: u64 = star1.amount.clone();
-        let star_two_amount: u64 = star2.amount.clone();
-        let star_three_amount: u64 = star3.amount.clone();
+        let star_one_amount: u64 = star1.amount;
+        let star_two_amount: u64 = star2.amount;
+        let star_three_amount: u64 = star3.amount;
 
         require!(star_one_amount + star_two_amount + star_three_amount == deposit.lamports, OridionError::StarHopCalculationError);
 
+        // IMPORTANT VALIDATION: IN PROGRAM SELECTED MODE THE MANAGER CANNOT CHOOSE THE DESTINATION
+        check_hop_destination(hdm, &ctx.accounts.planet_index.p, &deposit_key, deposit, to.id)?;
+
         // Update deposit location
-        deposit.location = to.name.clone();
-        hop_deposit(deposit);
+        land_deposit(deposit, to.id, 3, 0, window, &ctx.accounts.planet_index.p)?;
 
          //Clear our star amount
         star1.amount = 0;
@@ -412,26 +823,322 @@ pub mod oridion {
         //msg!("Star 2: {}", star_two_amount.to_string());
         //msg!("Star 3: {}", star_three_amount.to_string());
 
-        // Transactions
-        ctx.accounts.star_one.sub_lamports(star_one_amount)?;
-        ctx.accounts.star_two.sub_lamports(star_two_amount)?;
-        ctx.accounts.star_three.sub_lamports(star_three_amount)?;
-        ctx.accounts.to_planet.add_lamports(deposit.lamports)?;
-        //msg!("Hop to {} complete", to_planet_name);
-
-        //Transfer out remaining lamports
-        let star_one_remaining_lamports = ctx.accounts.star_one.get_lamports();
-        let star_two_remaining_lamports = ctx.accounts.star_two.get_lamports();
-        let star_three_remaining_lamports = ctx.accounts.star_three.get_lamports();
-        ctx.accounts.manager.add_lamports(star_one_remaining_lamports)?;
-        ctx.accounts.star_one.sub_lamports(star_one_remaining_lamports)?;
-        
-        ctx.accounts.manager.add_lamports(star_two_remaining_lamports)?;
-        ctx.accounts.star_two.sub_lamports(star_two_remaining_lamports)?;
+        // Transactions: stars to the destination planet, remaining lamports (rent) to the manager
+        let (star_one, star_two, star_three): (&AccountInfo, &AccountInfo, &AccountInfo) =
+            (ctx.accounts.star_one.as_ref(), ctx.accounts.star_two.as_ref(), ctx.accounts.star_three.as_ref());
+        let (to, manager): (&AccountInfo, &AccountInfo) = (ctx.accounts.to_planet.as_ref(), ctx.accounts.manager.as_ref());
+        transfer_lamports(&[
+            (star_one, to, star_one_amount),
+            (star_two, to, star_two_amount),
+            (star_three, to, star_three_amount),
+            (star_one, manager, star_one.lamports() - star_one_amount),
+            (star_two, manager, star_two.lamports() - star_two_amount),
+            (star_three, manager, star_three.lamports() - star_three_amount),
+        ])?;
+
+        record_hop(&mut ctx.accounts.stats, 3, 0)
+    }
 
-        ctx.accounts.manager.add_lamports(star_three_remaining_lamports)?;
-        ctx.accounts.star_three.sub_lamports(star_three_remaining_lamports)?;
 
+    ///-------------------------------------------------------------------///
+    /// TOKEN SECTION
+    /// Same flow as the lamport instructions for SPL token deposits.
+    /// Planets and stars sign token transfers with their PDA seeds.
+    /// Token-2022 transfer fees are withheld at every hop, so Deposit.lamports
+    /// and Star.amount always hold what actually landed.
+    ///-------------------------------------------------------------------///
+    pub fn create_mint_config(ctx: Context<CreateMintConfig>, min: u64, max: u64, hpfe: Option<u64>, wfe: Option<u64>) -> Result<()> {
+        check_mint_extensions(&ctx.accounts.mint.to_account_info())?;
+        require!(min <= max, OridionError::MintConfigInvalid);
+
+        let config: &mut Account<MintConfig> = &mut ctx.accounts.mint_config;
+        config.mint = ctx.accounts.mint.key();
+        config.min = min;
+        config.max = max;
+        config.hpfe = hpfe;
+        config.wfe = wfe;
+        config.p = Vec::new();
+        config.bump = ctx.bumps.mint_config;
+        Ok(())
+    }
+
+    pub fn update_mint_config(ctx: Context<UpdateMintConfig>, min: u64, max: u64, hpfe: Option<u64>, wfe: Option<u64>) -> Result<()> {
+        require!(min <= max, OridionError::MintConfigInvalid);
+
+        let config: &mut Account<MintConfig> = &mut ctx.accounts.mint_config;
+        config.min = min;
+        config.max = max;
+        config.hpfe = hpfe;
+        config.wfe = wfe;
+        Ok(())
+    }
+
+    /// Removes the mint from the allowlist once no planet serves it.
+    pub fn delete_mint_config(_ctx: Context<DeleteMintConfig>) -> Result<()> {
         Ok(())
     }
+
+    pub fn create_planet_token_account(ctx: Context<CreatePlanetTokenAccount>) -> Result<()> {
+        check_mint_extensions(&ctx.accounts.mint.to_account_info())
+    }
+
+    pub fn create_token_deposit(ctx: Context<CreateTokenDeposit>, amount: u64, mode: u8, delay: u32, withdraw_at: i64) -> Result<()> {
+
+        // VALIDATION - Mint limits, extensions and transfer fee
+        let config: &Account<MintConfig> = &ctx.accounts.mint_config;
+        require!(amount >= config.min && amount <= config.max, OridionError::DepositAmountOutOfRange);
+        let mint: AccountInfo = ctx.accounts.mint.to_account_info();
+        check_mint_extensions(&mint)?;
+        let fee: u64 = get_transfer_fee(&mint, amount)?;
+        require!(amount > fee, OridionError::DepositTooSmallForFee);
+
+        // CREATE DEPOSIT ACCOUNT - Deposit holds what lands on the planet
+        let deposit_account: &mut Account<Deposit> = &mut ctx.accounts.deposit;
+        init_deposit(deposit_account, amount - fee, mode, delay, withdraw_at);
+        deposit_account.destination = ctx.accounts.destination.key(); //Destination must be set.
+        deposit_account.mint = ctx.accounts.mint.key();
+
+        // INCREMENT PLANET VISIT
+        let planet: &mut Account<Planet> = &mut ctx.accounts.planet;
+        planet.visits += 1;
+        deposit_account.location = planet.id;
+
+        record_deposit(&mut ctx.accounts.stats, 0, 0)?;
+
+        // DEPOSIT TRANSFER - From creator straight to the planet token account
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::TransferChecked {
+                    from: ctx.accounts.creator_token_account.to_account_info(),
+                    mint,
+                    to: ctx.accounts.planet_token_account.to_account_info(),
+                    authority: ctx.accounts.creator.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )
+    }
+
+    pub fn token_planet_hop(ctx: Context<TokenPlanetHop>) -> Result<()> {
+        let (window, hdm): (u8, u8) = {
+            let universe = ctx.accounts.universe.load()?;
+            (universe.rw, universe.hdm)
+        };
+        let deposit_key: Pubkey = ctx.accounts.deposit.key();
+        let deposit: &mut Account<Deposit> = &mut ctx.accounts.deposit;
+        let from: &Account<Planet> = &ctx.accounts.from_planet;
+        let to: &mut Account<Planet> = &mut ctx.accounts.to_planet;
+
+        // IMPORTANT VALIDATION: TO AND FROM CANNOT BE THE SAME
+        require!(from.id != to.id, OridionError::HopErrorToAndFromAreSame);
+
+        // IMPORTANT VALIDATION: IN PROGRAM SELECTED MODE THE MANAGER CANNOT CHOOSE THE DESTINATION
+        check_hop_destination(hdm, &ctx.accounts.mint_config.p, &deposit_key, deposit, to.id)?;
+
+        // HOP FEE - In mint units, out of the deposit
+        let fee: u64 = ctx.accounts.mint_config.hpfe.unwrap_or(0);
+        require!(deposit.lamports > fee, OridionError::DepositTooSmallForFee);
+        deposit.lamports -= fee;
+
+        // Update deposit with new data
+        land_deposit(deposit, to.id, 0, fee, window, &ctx.accounts.mint_config.p)?;
+
+        //Increment visits
+        to.visits += 1;
+
+        // TRANSACTION: Move tokens from planet to planet, fee to manager
+        let id = from.id.to_le_bytes();
+        let bump = [from.bump];
+        let from_seeds: &[&[u8]] = &[PLANET_PDA_SEED_PRE, &id, PLANET_PDA_SEED_POST, &bump];
+        deposit.lamports = transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.from_token_account,
+            &ctx.accounts.to_token_account,
+            &ctx.accounts.mint,
+            from.to_account_info(),
+            deposit.lamports,
+            from_seeds,
+        )?;
+        if fee > 0 {
+            transfer_tokens(&ctx.accounts.token_program, &ctx.accounts.from_token_account, &ctx.accounts.manager_token_account, &ctx.accounts.mint, from.to_account_info(), fee, from_seeds)?;
+        }
+        record_hop(&mut ctx.accounts.stats, 0, 0)
+    }
+
+    pub fn token_withdraw(ctx: Context<TokenWithdraw>) -> Result<()> {
+        let deposit: &Account<Deposit> = &ctx.accounts.deposit;
+        let from: &Account<Planet> = &ctx.accounts.from_planet;
+
+        //VALIDATION
+        check_deposit_landed(deposit)?;
+        require!(is_route_complete(deposit, &ctx.accounts.mint_config.p), OridionError::RouteIncomplete);
+        require!(ctx.accounts.from_token_account.amount >= deposit.lamports, OridionError::PlanetNotEnoughFundsError);
+
+        // WITHDRAW FEE - In mint units, out of the deposit
+        let fee: u64 = ctx.accounts.mint_config.wfe.unwrap_or(0);
+        require!(deposit.lamports > fee, OridionError::DepositTooSmallForFee);
+
+        // TRANSACTION - Transfer to destination token account, fee to manager
+        let id = from.id.to_le_bytes();
+        let bump = [from.bump];
+        let from_seeds: &[&[u8]] = &[PLANET_PDA_SEED_PRE, &id, PLANET_PDA_SEED_POST, &bump];
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.from_token_account,
+            &ctx.accounts.destination_token_account,
+            &ctx.accounts.mint,
+            from.to_account_info(),
+            deposit.lamports - fee,
+            from_seeds,
+        )?;
+        if fee > 0 {
+            transfer_tokens(&ctx.accounts.token_program, &ctx.accounts.from_token_account, &ctx.accounts.manager_token_account, &ctx.accounts.mint, from.to_account_info(), fee, from_seeds)?;
+        }
+        record_withdrawal(&mut ctx.accounts.stats, true, 0)
+    }
+
+    pub fn token_star_hop_two_start(ctx: Context<TokenStarHopTwoStart>, star_one: String, star_two: String) -> Result<()> {
+
+        // IMPORTANT VALIDATION: STAR ONE AND TWO CANNOT BE THE SAME
+        require!(star_one != star_two, OridionError::HopErrorStarsMustBeUnique);
+        start_star_hop(&mut ctx.accounts.deposit, 2)?;
+
+        let (star_one_amount, star_two_amount) = get_two_star_split(ctx.accounts.deposit.lamports)?;
+        let manager: Pubkey = ctx.accounts.manager.key();
+        ctx.accounts.star_one.manager = manager;
+        ctx.accounts.star_one.version = ACCOUNT_VERSION;
+        ctx.accounts.star_two.manager = manager;
+        ctx.accounts.star_two.version = ACCOUNT_VERSION;
+
+        // TRANSACTION - Stars record what landed after transfer fees
+        let from: &Account<Planet> = &ctx.accounts.from_planet;
+        let id = from.id.to_le_bytes();
+        let bump = [from.bump];
+        let from_seeds: &[&[u8]] = &[PLANET_PDA_SEED_PRE, &id, PLANET_PDA_SEED_POST, &bump];
+        let token_program = &ctx.accounts.token_program;
+        let mint = &ctx.accounts.mint;
+        ctx.accounts.star_one.amount = transfer_tokens(token_program, &ctx.accounts.from_token_account, &ctx.accounts.star_one_token_account, mint, from.to_account_info(), star_one_amount, from_seeds)?;
+        ctx.accounts.star_two.amount = transfer_tokens(token_program, &ctx.accounts.from_token_account, &ctx.accounts.star_two_token_account, mint, from.to_account_info(), star_two_amount, from_seeds)?;
+        ctx.accounts.deposit.lamports = ctx.accounts.star_one.amount + ctx.accounts.star_two.amount;
+        Ok(())
+    }
+
+    pub fn token_star_hop_two_end(ctx: Context<TokenStarHopTwoEnd>, star_one: String, star_two: String) -> Result<()> {
+        let star_one_amount: u64 = ctx.accounts.star_one.amount;
+        let star_two_amount: u64 = ctx.accounts.star_two.amount;
+        let (window, hdm): (u8, u8) = {
+            let universe = ctx.accounts.universe.load()?;
+            (universe.rw, universe.hdm)
+        };
+        let deposit_key: Pubkey = ctx.accounts.deposit.key();
+        let deposit: &mut Account<Deposit> = &mut ctx.accounts.deposit;
+        let to: &mut Account<Planet> = &mut ctx.accounts.to_planet;
+
+        require!(star_one_amount + star_two_amount == deposit.lamports, OridionError::StarHopCalculationError);
+
+        // IMPORTANT VALIDATION: IN PROGRAM SELECTED MODE THE MANAGER CANNOT CHOOSE THE DESTINATION
+        check_hop_destination(hdm, &ctx.accounts.mint_config.p, &deposit_key, deposit, to.id)?;
+
+        // Update deposit with new data
+        land_deposit(deposit, to.id, 2, 0, window, &ctx.accounts.mint_config.p)?;
+
+        //Increment planet visit
+        to.visits += 1;
+
+        // TRANSACTIONS - Stars to destination planet, then close star token accounts
+        let token_program = &ctx.accounts.token_program;
+        let mint = &ctx.accounts.mint;
+        let manager: AccountInfo = ctx.accounts.manager.to_account_info();
+        let star_one_bump = [ctx.bumps.star_one];
+        let star_one_seeds: &[&[u8]] = &[STAR_SEED_PRE, star_one.as_bytes(), STAR_SEED_POST, &star_one_bump];
+        let star_two_bump = [ctx.bumps.star_two];
+        let star_two_seeds: &[&[u8]] = &[STAR_SEED_PRE, star_two.as_bytes(), STAR_SEED_POST, &star_two_bump];
+
+        let star1: AccountInfo = ctx.accounts.star_one.to_account_info();
+        let star2: AccountInfo = ctx.accounts.star_two.to_account_info();
+        let landed_one: u64 = transfer_tokens(token_program, &ctx.accounts.star_one_token_account, &ctx.accounts.to_token_account, mint, star1.clone(), star_one_amount, star_one_seeds)?;
+        let landed_two: u64 = transfer_tokens(token_program, &ctx.accounts.star_two_token_account, &ctx.accounts.to_token_account, mint, star2.clone(), star_two_amount, star_two_seeds)?;
+        deposit.lamports = landed_one + landed_two;
+        close_star_token_account(token_program, &ctx.accounts.star_one_token_account, mint, star1, manager.clone(), star_one_seeds)?;
+        close_star_token_account(token_program, &ctx.accounts.star_two_token_account, mint, star2, manager, star_two_seeds)?;
+        record_hop(&mut ctx.accounts.stats, 2, 0)
+    }
+
+    pub fn token_star_hop_three_start(ctx: Context<TokenStarHopThreeStart>, star_one: String, star_two: String, star_three: String) -> Result<()> {
+
+        // IMPORTANT VALIDATION: STARS MUST BE UNIQUE
+        require!(star_one != star_two && star_two != star_three && star_one != star_three, OridionError::HopErrorStarsMustBeUnique);
+        start_star_hop(&mut ctx.accounts.deposit, 3)?;
+
+        let (star_one_amount, star_two_amount, star_three_amount) = get_three_star_split(ctx.accounts.deposit.lamports)?;
+        let manager: Pubkey = ctx.accounts.manager.key();
+        ctx.accounts.star_one.manager = manager;
+        ctx.accounts.star_one.version = ACCOUNT_VERSION;
+        ctx.accounts.star_two.manager = manager;
+        ctx.accounts.star_two.version = ACCOUNT_VERSION;
+        ctx.accounts.star_three.manager = manager;
+        ctx.accounts.star_three.version = ACCOUNT_VERSION;
+
+        // TRANSACTION - Stars record what landed after transfer fees
+        let from: &Account<Planet> = &ctx.accounts.from_planet;
+        let id = from.id.to_le_bytes();
+        let bump = [from.bump];
+        let from_seeds: &[&[u8]] = &[PLANET_PDA_SEED_PRE, &id, PLANET_PDA_SEED_POST, &bump];
+        let token_program = &ctx.accounts.token_program;
+        let mint = &ctx.accounts.mint;
+        ctx.accounts.star_one.amount = transfer_tokens(token_program, &ctx.accounts.from_token_account, &ctx.accounts.star_one_token_account, mint, from.to_account_info(), star_one_amount, from_seeds)?;
+        ctx.accounts.star_two.amount = transfer_tokens(token_program, &ctx.accounts.from_token_account, &ctx.accounts.star_two_token_account, mint, from.to_account_info(), star_two_amount, from_seeds)?;
+        ctx.accounts.star_three.amount = transfer_tokens(token_program, &ctx.accounts.from_token_account, &ctx.accounts.star_three_token_account, mint, from.to_account_info(), star_three_amount, from_seeds)?;
+        ctx.accounts.deposit.lamports = ctx.accounts.star_one.amount + ctx.accounts.star_two.amount + ctx.accounts.star_three.amount;
+        Ok(())
+    }
+
+    pub fn token_star_hop_three_end(ctx: Context<TokenStarHopThreeEnd>, star_one: String, star_two: String, star_three: String) -> Result<()> {
+        let star_one_amount: u64 = ctx.accounts.star_one.amount;
+        let star_two_amount: u64 = ctx.accounts.star_two.amount;
+        let star_three_amount: u64 = ctx.accounts.star_three.amount;
+        let (window, hdm): (u8, u8) = {
+            let universe = ctx.accounts.universe.load()?;
+            (universe.rw, universe.hdm)
+        };
+        let deposit_key: Pubkey = ctx.accounts.deposit.key();
+        let deposit: &mut Account<Deposit> = &mut ctx.accounts.deposit;
+        let to: &mut Account<Planet> = &mut ctx.accounts.to_planet;
+
+        require!(star_one_amount + star_two_amount + star_three_amount == deposit.lamports, OridionError::StarHopCalculationError);
+
+        // IMPORTANT VALIDATION: IN PROGRAM SELECTED MODE THE MANAGER CANNOT CHOOSE THE DESTINATION
+        check_hop_destination(hdm, &ctx.accounts.mint_config.p, &deposit_key, deposit, to.id)?;
+
+        // Update deposit location
+        land_deposit(deposit, to.id, 3, 0, window, &ctx.accounts.mint_config.p)?;
+
+        //Increment planet visit
+        to.visits += 1;
+
+        // TRANSACTIONS - Stars to destination planet, then close star token accounts
+        let token_program = &ctx.accounts.token_program;
+        let mint = &ctx.accounts.mint;
+        let manager: AccountInfo = ctx.accounts.manager.to_account_info();
+        let star_one_bump = [ctx.bumps.star_one];
+        let star_one_seeds: &[&[u8]] = &[STAR_SEED_PRE, star_one.as_bytes(), STAR_SEED_POST, &star_one_bump];
+        let star_two_bump = [ctx.bumps.star_two];
+        let star_two_seeds: &[&[u8]] = &[STAR_SEED_PRE, star_two.as_bytes(), STAR_SEED_POST, &star_two_bump];
+        let star_three_bump = [ctx.bumps.star_three];
+        let star_three_seeds: &[&[u8]] = &[STAR_SEED_PRE, star_three.as_bytes(), STAR_SEED_POST, &star_three_bump];
+
+        let star1: AccountInfo = ctx.accounts.star_one.to_account_info();
+        let star2: AccountInfo = ctx.accounts.star_two.to_account_info();
+        let star3: AccountInfo = ctx.accounts.star_three.to_account_info();
+        let landed_one: u64 = transfer_tokens(token_program, &ctx.accounts.star_one_token_account, &ctx.accounts.to_token_account, mint, star1.clone(), star_one_amount, star_one_seeds)?;
+        let landed_two: u64 = transfer_tokens(token_program, &ctx.accounts.star_two_token_account, &ctx.accounts.to_token_account, mint, star2.clone(), star_two_amount, star_two_seeds)?;
+        let landed_three: u64 = transfer_tokens(token_program, &ctx.accounts.star_three_token_account, &ctx.accounts.to_token_account, mint, star3.clone(), star_three_amount, star_three_seeds)?;
+        deposit.lamports = landed_one + landed_two + landed_three;
+        close_star_token_account(token_program, &ctx.accounts.star_one_token_account, mint, star1, manager.clone(), star_one_seeds)?;
+        close_star_token_account(token_program, &ctx.accounts.star_two_token_account, mint, star2, manager.clone(), star_two_seeds)?;
+        close_star_token_account(token_program, &ctx.accounts.star_three_token_account, mint, star3, manager, star_three_seeds)?;
+        record_hop(&mut ctx.accounts.stats, 3, 0)
+    }
 }
\ No newline at end of file
@@ -1,6 +1,44 @@
 use super::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::{Discriminator, ZeroCopy};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use anchor_spl::token_2022::spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
 
-pub fn hop_deposit(deposit_account: &mut Account<Deposit>){
+/// Sets up a new deposit's amounts and schedule. Location, destination and mint are set by the caller.
+pub fn init_deposit(deposit_account: &mut Account<Deposit>, amount: u64, mode: u8, delay: u32, withdraw_at: i64){
+    let clock: Clock = Clock::get().unwrap();
+    let now = clock.unix_timestamp;
+    //msg!("Now Timestamp: {}", now.to_string());
+
+    deposit_account.version = ACCOUNT_VERSION;
+    deposit_account.created_at = now;
+    deposit_account.last_process_at = now;
+    deposit_account.hops = 2;
+    deposit_account.lamports = amount;
+    deposit_account.mode = mode;
+    deposit_account.last_process = 0; //0 = deposit
+    deposit_account.delay = delay;
+    deposit_account.withdraw_at = withdraw_at;
+    deposit_account.sm = 0; //Single destination until splits are set
+    deposit_account.tranches = 0; //Single withdrawal until a schedule is set
+    deposit_account.dm = 0; //Plaintext destination unless committed or a note
+
+    //Depending on the withdrawal timestamp, set the next process and hop process timestamp
+    if (now + 180) > withdraw_at {
+        //Withdraw is the next action.
+        deposit_account.next_process = 1; //1 = withdraw
+        deposit_account.next_process_at = withdraw_at;
+    } else {
+        //Set the next hop processing timestamp
+        deposit_account.next_process = 0; //0 = hop
+        deposit_account.next_process_at = now + 180;
+    }
+}
+
+pub fn hop_deposit(deposit_account: &mut Account<Deposit>, planets: &[u16]){
     let clock: Clock = Clock::get().unwrap();
     let now = clock.unix_timestamp;
     let withdraw_time = deposit_account.withdraw_at;
@@ -10,11 +48,13 @@ pub fn hop_deposit(deposit_account: &mut Account<Deposit>){
     deposit_account.last_process_at = now;
 
     //Manual, Instant, Delay
-    //Since we will be able to trigger hop manually through lambda function,
+    //Since we will be able to trigger hop manually through the crank (client/),
     //We update delay type deposits only
     if deposit_account.mode == 1 {
-        //Depending on the withdrawal timestamp, set the next process and hop process timestamp
-        if (now + 180) > withdraw_time {
+        //Depending on the withdrawal timestamp and route policy, set the next process and hop process timestamp
+        let route_done: bool = is_route_complete(deposit_account, planets);
+        let route_over: bool = is_route_full(deposit_account) || is_route_exhausted(deposit_account, planets);
+        if route_over || (route_done && (now + 180) > withdraw_time) {
             //Withdraw is the next action.
             deposit_account.next_process = 1; //1 = withdraw
             deposit_account.next_process_at = withdraw_time;
@@ -26,17 +66,682 @@ pub fn hop_deposit(deposit_account: &mut Account<Deposit>){
     }
 }
 
+/// Moves the deposit onto planet `to` after a hop through `stars` stars (0 for a
+/// planet hop) that cost it `fee`, once the route policy allows it. `window` is
+/// the universe's recent window and `planets` the planet index (the planets
+/// serving the mint for token deposits). A planet hop needs the deposit landed,
+/// a star hop end needs the same number of stars started.
+pub fn land_deposit(deposit_account: &mut Account<Deposit>, to: u16, stars: u8, fee: u64, window: u8, planets: &[u16]) -> Result<()> {
+    if stars == 0 {
+        check_deposit_landed(deposit_account)?;
+    } else {
+        require!(deposit_account.stars == stars, OridionError::StarHopNotStarted);
+    }
+    check_route_hop(deposit_account, to, fee)?;
+    check_recent_planets(deposit_account, to, window)?;
+    let from: u16 = deposit_account.location;
+    push_visited_planet(deposit_account, from);
+    push_visited_planet(deposit_account, to);
+    push_recent_planet(deposit_account, window);
+    deposit_account.location = to;
+    deposit_account.stars = 0;
+    if stars > 0 {
+        deposit_account.star_hops = deposit_account.star_hops.saturating_add(1);
+    }
+    deposit_account.fees += fee;
+    hop_deposit(deposit_account, planets);
+    Ok(())
+}
+
+/// Marks the deposit as held by `stars` stars until its star hop end lands it.
+pub fn start_star_hop(deposit: &mut Deposit, stars: u8) -> Result<()> {
+    check_deposit_landed(deposit)?;
+    require!(!is_route_full(deposit), OridionError::RouteMaxHopsReached);
+    deposit.stars = stars;
+    Ok(())
+}
+
+/// Funds of a deposit mid star hop sit in its stars, not on `location`.
+pub fn check_deposit_landed(deposit: &Deposit) -> Result<()> {
+    require!(deposit.stars == 0, OridionError::DepositInStarHop);
+    Ok(())
+}
+
+/// One batch_hop entry: (deposit, from planet, to planet, to planet stats), checked
+/// like planet_hop. Written back before the next entry loads, so a planet can
+/// appear in several entries and a deposit can hop more than once.
+pub fn batch_hop_entry<'info>(entry: &'info [AccountInfo<'info>], window: u8, hdm: u8, planets: &[u16], program_id: &Pubkey) -> Result<()> {
+    let [deposit_info, from_info, to_info, stats_info] = entry else {
+        return err!(OridionError::BatchHopInvalid);
+    };
+    require!(entry.iter().all(|account| account.is_writable), OridionError::BatchHopInvalid);
+    let mut deposit: Account<Deposit> = Account::try_from(deposit_info)?;
+    let from: Account<Planet> = Account::try_from(from_info)?;
+    let mut to: Account<Planet> = Account::try_from(to_info)?;
+    let mut stats: Account<PlanetStats> = Account::try_from(stats_info)?;
+
+    require!(deposit.mint == NATIVE_SOL, OridionError::DepositMintMismatch);
+    require!(from.id == deposit.location, OridionError::HopErrorWrongPlanet);
+    require!(from.id != to.id, OridionError::HopErrorToAndFromAreSame);
+    require!(stats.id == to.id, OridionError::BatchHopInvalid);
+    check_hop_destination(hdm, planets, deposit_info.key, &deposit, to.id)?;
+
+    land_deposit(&mut deposit, to.id, 0, 0, window, planets)?;
+    to.visits += 1;
+    transfer_lamports(&[(from_info, to_info, deposit.lamports)])?;
+    record_hop(&mut stats, 0, 0)?;
+
+    deposit.exit(program_id)?;
+    to.exit(program_id)?;
+    stats.exit(program_id)
+}
+
+
+/// Reads an account still in its v3.0.0 layout. `T` is the current account type,
+/// whose discriminator the old layout shares.
+pub fn read_legacy_account<T: Discriminator, L: AnchorDeserialize>(account: &AccountInfo, legacy_len: usize, program_id: &Pubkey) -> Result<L> {
+    require!(account.owner == program_id, OridionError::MigrationInvalidAccount);
+    let data = account.try_borrow_data()?;
+    require!(data.len() >= DISCRIMINATOR_LENGTH && data[..DISCRIMINATOR_LENGTH] == T::DISCRIMINATOR, OridionError::MigrationInvalidAccount);
+    require!(data.len() == legacy_len, OridionError::AccountAlreadyMigrated);
+    L::deserialize(&mut &data[DISCRIMINATOR_LENGTH..]).map_err(|_| error!(OridionError::MigrationInvalidAccount))
+}
+
+/// Reallocs an account to `len`, topping up its rent from the manager, and
+/// zero-fills it for the migrated value.
+pub fn resize_migrated_account<'info>(
+    account: &AccountInfo<'info>,
+    manager: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    len: usize,
+) -> Result<()> {
+    let rent: u64 = Rent::get()?.minimum_balance(len);
+    let lamports: u64 = account.lamports();
+    if rent > lamports {
+        transfer_from_signer(manager, account, system_program, rent - lamports)?;
+    }
+    account.realloc(len, false)?;
+    account.try_borrow_mut_data()?.fill(0);
+    Ok(())
+}
+
+/// Resizes an account and rewrites it with the migrated value.
+pub fn write_migrated_account<'info, T: AccountSerialize>(
+    account: &AccountInfo<'info>,
+    manager: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    migrated: &T,
+    len: usize,
+) -> Result<()> {
+    resize_migrated_account(account, manager, system_program, len)?;
+    let mut data = account.try_borrow_mut_data()?;
+    migrated.try_serialize(&mut &mut data[..])
+}
+
+/// Resizes an account and rewrites it with a migrated zero-copy value:
+/// the discriminator followed by the raw struct.
+pub fn write_migrated_zero_copy<'info, T: ZeroCopy>(
+    account: &AccountInfo<'info>,
+    manager: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    migrated: &T,
+) -> Result<()> {
+    resize_migrated_account(account, manager, system_program, DISCRIMINATOR_LENGTH + std::mem::size_of::<T>())?;
+    let mut data = account.try_borrow_mut_data()?;
+    data[..DISCRIMINATOR_LENGTH].copy_from_slice(&T::DISCRIMINATOR);
+    data[DISCRIMINATOR_LENGTH..].copy_from_slice(bytemuck::bytes_of(migrated));
+    Ok(())
+}
+
+/// Moves the funds of a v3.0.0 planet to its migrated account and closes it,
+/// returning its rent to the manager.
+pub fn close_legacy_planet<'info>(legacy: &AccountInfo<'info>, planet: &AccountInfo<'info>, manager: &AccountInfo<'info>) -> Result<()> {
+    let lamports: u64 = legacy.lamports();
+    let rent: u64 = Rent::get()?.minimum_balance(legacy.data_len()).min(lamports);
+    transfer_lamports(&[(legacy, planet, lamports - rent), (legacy, manager, rent)])?;
+    legacy.assign(&System::id());
+    legacy.realloc(0, false)?;
+    Ok(())
+}
+
+/// Today's bucket of a day ring, reset when the ring comes round to a new day.
+pub fn get_day_stats(days: &mut [DayStats; STATS_DAYS]) -> Result<&mut DayStats> {
+    let clock: Clock = Clock::get()?;
+    let day: u32 = (clock.unix_timestamp / SECONDS_PER_DAY) as u32;
+    let bucket: &mut DayStats = &mut days[day as usize % STATS_DAYS];
+    if bucket.day != day {
+        *bucket = DayStats { day, ..DayStats::default() };
+    }
+    Ok(bucket)
+}
+
+/// New deposit of `lamports` and the fee the manager collected for it. Stats amounts are
+/// lamports, so token deposits, hops and withdrawals only count (`lamports` and `fee` 0).
+pub fn record_deposit(stats: &mut PlanetStats, lamports: u64, fee: u64) -> Result<()> {
+    stats.deposits += 1;
+    stats.volume += lamports;
+    stats.fees += fee;
+    let today: &mut DayStats = get_day_stats(&mut stats.days)?;
+    today.deposits += 1;
+    today.volume += lamports;
+    today.fees += fee;
+    Ok(())
+}
+
+/// Hop straight to a planet (`stars` 0) or through two or three stars.
+pub fn record_hop(stats: &mut PlanetStats, stars: u8, fee: u64) -> Result<()> {
+    match stars {
+        2 => stats.star_hops_two += 1,
+        3 => stats.star_hops_three += 1,
+        _ => stats.planet_hops += 1,
+    }
+    stats.fees += fee;
+    let today: &mut DayStats = get_day_stats(&mut stats.days)?;
+    match stars {
+        2 => today.star_hops_two += 1,
+        3 => today.star_hops_three += 1,
+        _ => today.planet_hops += 1,
+    }
+    today.fees += fee;
+    Ok(())
+}
+
+/// Withdrawal or tranche. `closed` when it was the deposit's last.
+pub fn record_withdrawal(stats: &mut PlanetStats, closed: bool, fee: u64) -> Result<()> {
+    stats.withdrawals += 1;
+    if closed {
+        stats.closed += 1;
+    }
+    stats.fees += fee;
+    let today: &mut DayStats = get_day_stats(&mut stats.days)?;
+    today.withdrawals += 1;
+    today.fees += fee;
+    Ok(())
+}
 
+/// Adds a planet's counters to the galaxy totals and clears them.
+/// Day buckets older than the galaxy ring are dropped.
+pub fn aggregate_planet_stats(galaxy: &mut GalaxyStats, planet: &mut PlanetStats) {
+    galaxy.deposits += planet.deposits;
+    galaxy.volume += planet.volume;
+    galaxy.closed += planet.closed;
+    galaxy.active = galaxy.deposits.saturating_sub(galaxy.closed);
+    galaxy.planet_hops += planet.planet_hops;
+    galaxy.star_hops_two += planet.star_hops_two;
+    galaxy.star_hops_three += planet.star_hops_three;
+    galaxy.withdrawals += planet.withdrawals;
+    galaxy.fees += planet.fees;
+    for pending in planet.days.iter().filter(|day| **day != DayStats::default()) {
+        let bucket: &mut DayStats = &mut galaxy.days[pending.day as usize % STATS_DAYS];
+        if bucket.day > pending.day {
+            continue;
+        }
+        if bucket.day < pending.day {
+            *bucket = DayStats { day: pending.day, ..DayStats::default() };
+        }
+        bucket.deposits += pending.deposits;
+        bucket.volume += pending.volume;
+        bucket.planet_hops += pending.planet_hops;
+        bucket.star_hops_two += pending.star_hops_two;
+        bucket.star_hops_three += pending.star_hops_three;
+        bucket.withdrawals += pending.withdrawals;
+        bucket.fees += pending.fees;
+    }
+    *planet = PlanetStats { id: planet.id, bump: planet.bump, ..PlanetStats::default() };
+}
 
-pub fn get_planet_program_address(planet_name: &String, program_id: &Pubkey) -> Pubkey {
+pub fn get_planet_program_address(planet_id: u16, program_id: &Pubkey) -> Pubkey {
     let(pk, _pda_bump) = Pubkey::find_program_address(&[
         PLANET_PDA_SEED_PRE,
-        planet_name.as_ref(),
+        &planet_id.to_le_bytes(),
         PLANET_PDA_SEED_POST
     ], program_id);
     pk
 }
 
+/// Random split of an amount between two stars.
+pub fn get_two_star_split(amount: u64) -> Result<(u64, u64)> {
+    let percent: f32 = get_random_percent();
+    let star_one_amount: u64 = ((percent / 100f32) * amount as f32) as u64;
+    let star_two_amount: u64 = amount - star_one_amount;
+    //msg!("Hopping to star 1: {}", star_one_amount.to_string());
+    //msg!("Hopping to Star 2: {}", star_two_amount.to_string());
+
+    //Make sure the addition of split amounts are equal to deposit
+    require!(star_one_amount + star_two_amount == amount, OridionError::StarHopCalculationError);
+    Ok((star_one_amount, star_two_amount))
+}
+
+/// Random split of an amount between three stars. The larger side of the
+/// first split is split again.
+pub fn get_three_star_split(amount: u64) -> Result<(u64, u64, u64)> {
+    let first_split_percent: f32 = get_random_percent();
+    let second_split_percent: f32 = 100f32 - first_split_percent;
+    //msg!("Split: {}% / {}%", first_split_percent.to_string(), second_split_percent.to_string());
+
+    //Determine side amounts here
+    let side_one_amount: u64 = ((first_split_percent / 100f32) * amount as f32) as u64;
+    let side_two_amount: u64 = amount - side_one_amount;
+
+    let (star_one_amount, star_two_amount, star_three_amount) = if side_one_amount > side_two_amount {
+        //Side one is larger so we split side one.
+        //Side two is set as star 2
+        //We further split side one to create star one and star three
+        let one: u64 = ((second_split_percent / 100f32) * side_one_amount as f32) as u64;
+        let three: u64 = side_one_amount - one;
+        //msg!("Third split on first side");
+        (one,side_two_amount,three)
+    } else {
+        //Side two is larger so we split side two.
+        //Side one is set as star one
+        //We further split side two to create side two and side three
+        let two: u64 = ((second_split_percent / 100f32) * side_two_amount as f32) as u64;
+        let three: u64 = side_two_amount - two;
+        //msg!("Third split on second side");
+        (side_one_amount,two,three)
+    };
+
+    require!(star_one_amount + star_two_amount + star_three_amount == amount, OridionError::StarHopCalculationError);
+    Ok((star_one_amount, star_two_amount, star_three_amount))
+}
+
+/// Checks destination splits against the deposit amount. Split mode 0 takes no splits.
+pub fn validate_splits(mode: u8, splits: &[DestinationSplit], lamports: u64) -> Result<()> {
+    if mode == 0 {
+        require!(splits.is_empty(), OridionError::SplitsInvalid);
+        return Ok(());
+    }
+    require!(splits.len() >= 2 && splits.len() <= MAX_DESTINATIONS, OridionError::SplitsInvalid);
+    for (index, split) in splits.iter().enumerate() {
+        require!(splits[..index].iter().all(|other| other.destination != split.destination), OridionError::SplitsInvalid);
+    }
+    match mode {
+        //Basis points must cover the whole deposit
+        1 => {
+            require!(splits.iter().all(|split| split.bps > 0), OridionError::SplitsInvalid);
+            let total: u64 = splits.iter().map(|split| split.bps as u64).sum();
+            require!(total == BPS_DENOMINATOR, OridionError::SplitsInvalid);
+        }
+        //Minimums must fit in the deposit
+        2 => {
+            let minimums: u64 = splits.iter().map(|split| split.min).sum();
+            require!(minimums <= lamports, OridionError::SplitsInvalid);
+        }
+        _ => return err!(OridionError::SplitsInvalid),
+    }
+    Ok(())
+}
+
+/// Withdraws the next tranche of a SOL deposit to its destinations (split destinations
+/// are `others`). A relayer is paid its fee out of the tranche. The deposit stays open
+/// until the last tranche, then its rent goes to the manager. `planets` is the planet
+/// index, for the route policy. Returns true once closed.
+pub fn withdraw_tranche<'info>(
+    deposit: &mut Account<'info, Deposit>,
+    from_planet: &mut Account<'info, Planet>,
+    destination: &AccountInfo<'info>,
+    others: &[AccountInfo<'info>],
+    manager: &AccountInfo<'info>,
+    relayer: Option<(&AccountInfo<'info>, u64)>,
+    planets: &[u16],
+) -> Result<bool> {
+    let clock: Clock = Clock::get()?;
+    let deposit_key: Pubkey = deposit.key();
+
+    check_deposit_landed(deposit)?;
+    require!(is_route_complete(deposit, planets), OridionError::RouteIncomplete);
+
+    //Later tranches wait for their spacing
+    require!(deposit.last_process != 2 || clock.unix_timestamp >= deposit.next_process_at, OridionError::TrancheNotDue);
+    let withdraw_lamports: u64 = get_tranche_amount(&deposit_key, deposit)?;
+
+    //VALIDATION
+    let current_from_lamports_balance: u64 = from_planet.get_lamports();
+    require!(current_from_lamports_balance > withdraw_lamports, OridionError::PlanetNotEnoughFundsError);
+
+    let relayer_fee: u64 = relayer.map_or(0, |(_, fee)| fee);
+    require!(withdraw_lamports > relayer_fee, OridionError::DepositTooSmallForFee);
+
+    // TRANSACTION - Transfer to destinations, fee to relayer
+    let fees: Vec<(&AccountInfo<'info>, u64)> = relayer.into_iter().collect();
+    pay_destinations(&deposit_key, deposit, from_planet.as_ref(), destination, others, withdraw_lamports - relayer_fee, &fees)?;
+
+    if withdraw_lamports < deposit.lamports {
+        schedule_next_tranche(&deposit_key, deposit, withdraw_lamports)?;
+        return Ok(false);
+    }
+    deposit.close(manager.clone())?;
+    Ok(true)
+}
+
+/// Commitment stored by committed deposits in place of the destination.
+pub fn get_destination_commitment(destination: &Pubkey, salt: &[u8; 32]) -> [u8; 32] {
+    hashv(&[destination.as_ref(), salt]).to_bytes()
+}
+
+/// Hash stored by note deposits. The secret, the destination and the relayer fee
+/// are its preimage, so a withdrawal seen in flight cannot be sent anywhere else
+/// and a relayer cannot charge more than the note allows.
+pub fn get_note_hash(secret: &[u8; 32], destination: &Pubkey, relay_fee: u64) -> [u8; 32] {
+    hashv(&[secret, destination.as_ref(), &relay_fee.to_le_bytes()]).to_bytes()
+}
+
+/// Checks the signer created the deposit. Bucket deposits pass their nonce.
+pub fn check_deposit_creator(deposit_key: &Pubkey, creator: &Pubkey, nonce: Option<u8>, program_id: &Pubkey) -> Result<()> {
+    let (expected, _bump) = match nonce {
+        Some(nonce) => Pubkey::find_program_address(&[DEPOSIT_PDA_SEED_PRE, creator.as_ref(), &[nonce], DEPOSIT_PDA_SEED_POST], program_id),
+        None => Pubkey::find_program_address(&[DEPOSIT_PDA_SEED_PRE, creator.as_ref(), DEPOSIT_PDA_SEED_POST], program_id),
+    };
+    require!(*deposit_key == expected, OridionError::DepositCreatorMismatch);
+    Ok(())
+}
+
+/// Checks a withdrawal schedule. A single tranche is a plain withdrawal.
+pub fn validate_schedule(tranches: u8, spacing_min: u32, spacing_max: u32) -> Result<()> {
+    require!((1..=MAX_TRANCHES).contains(&tranches), OridionError::ScheduleInvalid);
+    require!(spacing_min <= spacing_max, OridionError::ScheduleInvalid);
+    if tranches > 1 {
+        require!(spacing_min >= MIN_TRANCHE_SPACING, OridionError::ScheduleInvalid);
+    }
+    Ok(())
+}
+
+/// Lamports released by the next withdrawal. Every tranche but the last is a
+/// random 50-150% of an even share of what is left; the last releases the rest.
+pub fn get_tranche_amount(deposit_key: &Pubkey, deposit: &Deposit) -> Result<u64> {
+    if deposit.tranches <= 1 {
+        return Ok(deposit.lamports);
+    }
+    let hash = get_tranche_hash(deposit_key, deposit)?;
+    let percent: u64 = 50 + u16::from_le_bytes([hash[0], hash[1]]) as u64 % 101;
+    let even: u64 = deposit.lamports / deposit.tranches as u64;
+    let amount: u64 = (even as u128 * percent as u128 / 100) as u64;
+    require!(amount > 0 && amount < deposit.lamports, OridionError::ScheduleInvalid);
+    Ok(amount)
+}
+
+/// Keeps the deposit open after a tranche of `released` lamports and schedules
+/// the next one a random spacing from now.
+pub fn schedule_next_tranche(deposit_key: &Pubkey, deposit_account: &mut Account<Deposit>, released: u64) -> Result<()> {
+    let clock: Clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+    let hash = get_tranche_hash(deposit_key, deposit_account)?;
+    let range: u64 = (deposit_account.spacing_max - deposit_account.spacing_min) as u64 + 1;
+    let spacing: u64 = deposit_account.spacing_min as u64 + u64::from_le_bytes(hash[2..10].try_into().unwrap()) % range;
+
+    deposit_account.lamports -= released;
+    deposit_account.tranches -= 1;
+    deposit_account.last_process = 2; // Last action -> (2 = tranche withdrawn)
+    deposit_account.last_process_at = now;
+    deposit_account.next_process = 1; //1 = withdraw
+    deposit_account.next_process_at = now + spacing as i64;
+    Ok(())
+}
+
+fn get_tranche_hash(deposit_key: &Pubkey, deposit: &Deposit) -> Result<[u8; 32]> {
+    let clock: Clock = Clock::get()?;
+    Ok(hashv(&[deposit_key.as_ref(), &clock.slot.to_le_bytes(), &[deposit.tranches], &deposit.lamports.to_le_bytes()]).to_bytes())
+}
+
+/// Lamports for each destination of a withdrawal of `amount`, in split order.
+/// Random shares (mode 2) come from the deposit key and slot; the last
+/// destination takes the rounding remainder.
+pub fn get_split_amounts(deposit_key: &Pubkey, deposit: &Deposit, amount: u64) -> Result<Vec<u64>> {
+    let splits: &Vec<DestinationSplit> = &deposit.splits;
+    if deposit.sm == 0 || splits.is_empty() {
+        return Ok(vec![amount]);
+    }
+
+    let shares: Vec<u64> = if deposit.sm == 1 {
+        splits.iter().map(|split| (amount as u128 * split.bps as u128 / BPS_DENOMINATOR as u128) as u64).collect()
+    } else {
+        //Minimums larger than a withdrawal tranche are scaled down to it
+        let total_minimum: u64 = splits.iter().map(|split| split.min).sum();
+        let minimums: Vec<u64> = if total_minimum <= amount {
+            splits.iter().map(|split| split.min).collect()
+        } else {
+            splits.iter().map(|split| (split.min as u128 * amount as u128 / total_minimum as u128) as u64).collect()
+        };
+        let spread: u64 = amount - minimums.iter().sum::<u64>();
+        let clock: Clock = Clock::get()?;
+        let hash = hashv(&[deposit_key.as_ref(), &clock.slot.to_le_bytes(), &amount.to_le_bytes()]).to_bytes();
+        let weights: Vec<u64> = (0..splits.len())
+            .map(|index| u16::from_le_bytes([hash[index * 2], hash[index * 2 + 1]]) as u64 + 1)
+            .collect();
+        let total_weight: u64 = weights.iter().sum();
+        minimums.iter().zip(&weights)
+            .map(|(minimum, weight)| minimum + (spread as u128 * *weight as u128 / total_weight as u128) as u64)
+            .collect()
+    };
+
+    //Rounding remainder goes to the last destination
+    let mut amounts: Vec<u64> = shares;
+    let paid: u64 = amounts.iter().sum();
+    let last: usize = amounts.len() - 1;
+    amounts[last] += amount - paid;
+    Ok(amounts)
+}
+
+/// Pays a withdrawal out of `from` to the deposit destination and, for split deposits,
+/// the other split destinations passed as remaining accounts in split order.
+/// `fees` are paid out of `from` in the same transfer.
+pub fn pay_destinations<'info>(
+    deposit_key: &Pubkey,
+    deposit: &Deposit,
+    from: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    others: &[AccountInfo<'info>],
+    amount: u64,
+    fees: &[(&AccountInfo<'info>, u64)],
+) -> Result<()> {
+    let amounts: Vec<u64> = get_split_amounts(deposit_key, deposit, amount)?;
+    require!(others.len() + 1 >= amounts.len(), OridionError::SplitDestinationMismatch);
+    let mut transfers: Vec<LamportTransfer> = vec![(from, destination, amounts[0])];
+    for (index, lamports) in amounts.iter().enumerate().skip(1) {
+        let account: &AccountInfo = &others[index - 1];
+        require!(account.key() == deposit.splits[index].destination && account.is_writable, OridionError::SplitDestinationMismatch);
+        transfers.push((from, account, *lamports));
+    }
+    transfers.extend(fees.iter().map(|(to, lamports)| (from, *to, *lamports)));
+    transfer_lamports(&transfers)
+}
+
+/// Opens a new SOL deposit on `planet`, counts it in the planet stats and moves the
+/// lamports from the creator straight to the planet. Shared by the create_*deposit
+/// handlers, which then record how the destination is kept. The universe is not
+/// written, so deposits on different planets do not share its lock.
+#[allow(clippy::too_many_arguments)]
+pub fn create_sol_deposit<'info>(
+    deposit_account: &mut Account<'info, Deposit>,
+    planet: &mut Account<'info, Planet>,
+    stats: &mut PlanetStats,
+    creator: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    deposit_lamports: u64,
+    mode: u8,
+    delay: u32,
+    withdraw_at: i64,
+) -> Result<()> {
+    init_deposit(deposit_account, deposit_lamports, mode, delay, withdraw_at);
+    deposit_account.mint = NATIVE_SOL;
+
+    // INCREMENT PLANET VISIT
+    planet.visits += 1;
+    deposit_account.location = planet.id;
+
+    record_deposit(stats, deposit_lamports, 0)?;
+
+    // DEPOSIT TRANSFER - From creator straight to the planet
+    transfer_from_signer(creator.as_ref(), planet.as_ref(), system_program.as_ref(), deposit_lamports)
+}
+
+///-------------------------------------------------------------------///
+/// LAMPORT TRANSFERS
+/// Every lamport move goes through these. The total of the accounts
+/// involved is taken before and after, and must not change.
+///-------------------------------------------------------------------///
+/// (from, to, lamports)
+pub type LamportTransfer<'a, 'info> = (&'a AccountInfo<'info>, &'a AccountInfo<'info>, u64);
+
+/// Moves lamports out of program owned accounts, in order.
+pub fn transfer_lamports(transfers: &[LamportTransfer]) -> Result<()> {
+    let before: u128 = get_total_lamports(transfers);
+    for (from, to, lamports) in transfers {
+        from.sub_lamports(*lamports)?;
+        to.add_lamports(*lamports)?;
+    }
+    require!(get_total_lamports(transfers) == before, OridionError::LamportsNotConserved);
+    Ok(())
+}
+
+/// Moves lamports out of a signer through the system program.
+pub fn transfer_from_signer<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    lamports: u64,
+) -> Result<()> {
+    let transfers: [LamportTransfer; 1] = [(from, to, lamports)];
+    let before: u128 = get_total_lamports(&transfers);
+    let transfer_instruction: Instruction = system_instruction::transfer(from.key, to.key, lamports);
+    solana_program::program::invoke(&transfer_instruction, &[from.clone(), to.clone(), system_program.clone()])?;
+    require!(get_total_lamports(&transfers) == before, OridionError::LamportsNotConserved);
+    Ok(())
+}
+
+/// Total lamports of the accounts in `transfers`, each counted once.
+pub fn get_total_lamports(transfers: &[LamportTransfer]) -> u128 {
+    let mut seen: Vec<&Pubkey> = Vec::with_capacity(transfers.len() * 2);
+    let mut total: u128 = 0;
+    for account in transfers.iter().flat_map(|(from, to, _)| [*from, *to]) {
+        if !seen.contains(&account.key) {
+            seen.push(account.key);
+            total += account.lamports() as u128;
+        }
+    }
+    total
+}
+
+/// Adds planet `id` to (`add`) or removes it from the MintConfigs of its `mints`,
+/// passed as writable `configs` in the same order.
+pub fn update_mint_planets<'info>(id: u16, mints: &[Pubkey], configs: &'info [AccountInfo<'info>], add: bool, program_id: &Pubkey) -> Result<()> {
+    require!(configs.len() == mints.len(), OridionError::MintConfigMissing);
+    for (mint, config) in mints.iter().zip(configs) {
+        let mut config: Account<MintConfig> = Account::try_from(config).map_err(|_| OridionError::MintConfigMissing)?;
+        require!(config.mint == *mint && config.to_account_info().is_writable, OridionError::MintConfigMissing);
+        if add {
+            config.p.push(id);
+        } else {
+            config.p.retain(|x| *x != id);
+        }
+        config.exit(program_id)?;
+    }
+    Ok(())
+}
+
+/// Rejects mints with a freeze authority, which could freeze planet and star token
+/// accounts, and Token-2022 mints whose extensions break the deposit model
+/// (see UNSUPPORTED_MINT_EXTENSIONS). Classic SPL mints have no extensions.
+pub fn check_mint_extensions(mint: &AccountInfo) -> Result<()> {
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    require!(state.base.freeze_authority.is_none(), OridionError::MintFreezeAuthorityNotSupported);
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+    for extension in state.get_extension_types()? {
+        require!(!UNSUPPORTED_MINT_EXTENSIONS.contains(&extension), OridionError::MintExtensionNotSupported);
+    }
+    Ok(())
+}
+
+/// Token-2022 transfer fee withheld from `amount` this epoch. 0 for mints without a fee.
+pub fn get_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(0);
+    }
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    let Ok(config) = state.get_extension::<TransferFeeConfig>() else {
+        return Ok(0);
+    };
+    let fee = config.calculate_epoch_fee(Clock::get()?.epoch, amount).ok_or(OridionError::TransferFeeError)?;
+    Ok(fee)
+}
+
+/// Whether the mint is a Token-2022 mint with the transfer fee extension.
+pub fn has_transfer_fee(mint: &AccountInfo) -> Result<bool> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(false);
+    }
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    Ok(state.get_extension::<TransferFeeConfig>().is_ok())
+}
+
+/// Token transfer out of an account owned by a program PDA (planet or star).
+/// Returns the amount that landed in `to`, the transfer fee is withheld there.
+pub fn transfer_tokens<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    authority: AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[u8]],
+) -> Result<u64> {
+    let fee = get_transfer_fee(&mint.to_account_info(), amount)?;
+    require!(amount > fee, OridionError::DepositTooSmallForFee);
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            token_interface::TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority,
+            },
+            &[signer_seeds],
+        ),
+        amount,
+        mint.decimals,
+    )?;
+    Ok(amount - fee)
+}
+
+/// Closes a star's emptied token account, rent goes to the manager. Fees withheld
+/// in the account are harvested to the mint first, Token-2022 refuses to close it otherwise.
+pub fn close_star_token_account<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    account: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    star: AccountInfo<'info>,
+    manager: AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    if has_transfer_fee(&mint.to_account_info())? {
+        token_interface::harvest_withheld_tokens_to_mint(
+            CpiContext::new(
+                token_program.to_account_info(),
+                token_interface::HarvestWithheldTokensToMint {
+                    token_program_id: token_program.to_account_info(),
+                    mint: mint.to_account_info(),
+                },
+            ),
+            vec![account.to_account_info()],
+        )?;
+    }
+    token_interface::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token_interface::CloseAccount {
+            account: account.to_account_info(),
+            destination: manager,
+            authority: star,
+        },
+        &[signer_seeds],
+    ))
+}
+
 pub fn get_random_percent() -> f32 {
     let clock: Clock = Clock::get().unwrap();
     // First get the percent to split deposit between two stars.
@@ -49,11 +754,143 @@ pub fn get_random_percent() -> f32 {
     //msg!("Lamports splitting: {}", deposit.to_string());
     //let first_split_percent: f32 = f32::from_str(first_split_percent).unwrap();
     let percent: f32 = percent.parse::<f32>().unwrap();
-    if percent < 10f32 {
-        return 10f32
-    } else if percent > 90f32 {
-        return 90f32
-    } else {
-        return percent
+    percent.clamp(10f32, 90f32)
+}
+
+
+/// Planet picked by the program for a deposit's next hop. Seeded from the deposit's
+/// own state, so anyone can work it out off-chain but no caller gets to choose it.
+pub fn select_next_planet(planets: &[u16], deposit_key: &Pubkey, deposit: &Deposit) -> Option<u16> {
+    let candidates: Vec<u16> = get_route_candidates(planets, deposit);
+    if candidates.is_empty() {
+        return None;
+    }
+    let hash = hashv(&[
+        deposit_key.as_ref(),
+        &deposit.hops.to_le_bytes(),
+        &deposit.last_process_at.to_le_bytes()
+    ]).to_bytes();
+    let random = u64::from_le_bytes(hash[..8].try_into().unwrap());
+    Some(candidates[(random % candidates.len() as u64) as usize])
+}
+
+/// With the universe's hop destination mode `hdm` at 1, a hop may only land on
+/// the program-selected planet. At 0 the manager picks the destination.
+pub fn check_hop_destination(hdm: u8, planets: &[u16], deposit_key: &Pubkey, deposit: &Deposit, to: u16) -> Result<()> {
+    if hdm == 1 {
+        require!(select_next_planet(planets, deposit_key, deposit) == Some(to), OridionError::HopErrorWrongDestination);
+    }
+    Ok(())
+}
+
+///-------------------------------------------------------------------///
+/// ROUTE POLICY
+/// Limits the creator can put on a deposit's route. Checked on every
+/// landing, and before any withdrawal.
+///-------------------------------------------------------------------///
+/// Hops made so far. Deposit.hops starts at 2, as in v3.0.0.
+pub fn get_route_hops(deposit: &Deposit) -> u16 {
+    deposit.hops.saturating_sub(2)
+}
+
+/// Bit of planet `id` in Deposit.visited. Planet ids are below MAX_PLANETS.
+pub fn get_planet_bit(id: u16) -> u64 {
+    1u64.checked_shl(id as u32).unwrap_or(0)
+}
+
+/// Whether the deposit is or has been on planet `id`.
+pub fn is_planet_visited(deposit: &Deposit, id: u16) -> bool {
+    id == deposit.location || deposit.visited & get_planet_bit(id) != 0
+}
+
+/// Records a planet the deposit landed on.
+pub fn push_visited_planet(deposit: &mut Deposit, id: u16) {
+    deposit.visited |= get_planet_bit(id);
+}
+
+/// Checks a policy can be met in a universe of `planets` planets.
+pub fn validate_route_policy(policy: &RoutePolicy, planets: usize) -> Result<()> {
+    if policy.max_hops > 0 {
+        require!(policy.min_hops <= policy.max_hops, OridionError::RoutePolicyInvalid);
+        require!(policy.min_stars as u16 <= policy.max_hops, OridionError::RoutePolicyInvalid);
+    }
+    //Without revisits a deposit runs out of planets
+    if policy.avoid_revisit {
+        require!((policy.min_hops as usize) < planets && (policy.min_stars as usize) < planets, OridionError::RoutePolicyInvalid);
+    }
+    Ok(())
+}
+
+/// Minimum hops and star hops made, or no planet left to hop to: the deposit can
+/// be withdrawn. `planets` is the planet index.
+pub fn is_route_complete(deposit: &Deposit, planets: &[u16]) -> bool {
+    let minimums: bool = get_route_hops(deposit) >= deposit.route.min_hops && deposit.star_hops >= deposit.route.min_stars;
+    minimums || is_route_exhausted(deposit, planets)
+}
+
+/// A route avoiding revisits that has been on every planet in `planets`.
+pub fn is_route_exhausted(deposit: &Deposit, planets: &[u16]) -> bool {
+    deposit.route.avoid_revisit && get_route_candidates(planets, deposit).is_empty()
+}
+
+/// Maximum hops made, the deposit can only be withdrawn.
+pub fn is_route_full(deposit: &Deposit) -> bool {
+    deposit.route.max_hops > 0 && get_route_hops(deposit) >= deposit.route.max_hops
+}
+
+pub fn check_route_hop(deposit: &Deposit, to: u16, fee: u64) -> Result<()> {
+    require!(!is_route_full(deposit), OridionError::RouteMaxHopsReached);
+    require!(deposit.route.max_fees == 0 || deposit.fees + fee <= deposit.route.max_fees, OridionError::RouteFeeBudgetExceeded);
+    if deposit.route.avoid_revisit {
+        require!(!is_planet_visited(deposit, to), OridionError::RoutePlanetRevisited);
     }
+    Ok(())
+}
+
+/// Planets the deposit may hop to next: any but its own or one it left
+/// recently, and none it has been on when its route policy avoids revisits.
+pub fn get_route_candidates(planets: &[u16], deposit: &Deposit) -> Vec<u16> {
+    let avoid_revisit: bool = deposit.route.avoid_revisit;
+    let recent: &[u16] = get_recent_planets(deposit);
+    planets.iter().copied()
+        .filter(|id| *id != deposit.location && !(avoid_revisit && is_planet_visited(deposit, *id)) && !recent.contains(id))
+        .collect()
+}
+
+///-------------------------------------------------------------------///
+/// RECENT PLANETS
+/// A deposit remembers the last planets it left, newest first, so hops
+/// cannot bounce it straight back (A to B to A). How many count is the
+/// universe's recent window.
+///-------------------------------------------------------------------///
+pub fn get_recent_planets(deposit: &Deposit) -> &[u16] {
+    &deposit.recent[..(deposit.rn as usize).min(MAX_RECENT_PLANETS)]
+}
+
+/// A window of `window` planets must leave a deposit among `planets` planets one
+/// besides its own and the last `window` to hop to.
+pub fn check_recent_window(window: u8, planets: usize) -> Result<()> {
+    require!(window as usize <= MAX_RECENT_PLANETS, OridionError::RecentWindowInvalid);
+    require!(window == 0 || window as usize + 1 < planets, OridionError::RecentWindowInvalid);
+    Ok(())
+}
+
+pub fn check_recent_planets(deposit: &Deposit, to: u16, window: u8) -> Result<()> {
+    let recent: &[u16] = get_recent_planets(deposit);
+    let window: usize = (window as usize).min(recent.len());
+    require!(!recent[..window].contains(&to), OridionError::HopErrorRecentPlanet);
+    Ok(())
+}
+
+/// Remembers the planet the deposit is leaving, keeping `window` entries.
+pub fn push_recent_planet(deposit: &mut Deposit, window: u8) {
+    let window: usize = (window as usize).min(MAX_RECENT_PLANETS);
+    deposit.recent.copy_within(..MAX_RECENT_PLANETS - 1, 1);
+    deposit.recent[0] = deposit.location;
+    deposit.rn = (deposit.rn as usize + 1).min(window) as u8;
+}
+
+/// Keeper's share of a collected fee.
+pub fn get_keeper_reward(fee: u64) -> u64 {
+    fee * KEEPER_REWARD_BPS / BPS_DENOMINATOR
 }
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::extension::ExtensionType;
 
 pub const MANAGER_PUBKEY: Pubkey = pubkey!("ordnd8TZFYW4k4MeLrR3qSwXMxezL6W3WryUPYTzLQM");
 pub const UNIVERSE_PDA_SEED: &[u8] = b"_x_ORIDION_x_";
@@ -14,6 +15,45 @@ pub const DEPOSIT_PDA_SEED_POST: &[u8] = b"_TRAVEL_";
 pub const STAR_SEED_PRE: &[u8] = b"_ST_";
 pub const STAR_SEED_POST: &[u8] = b"_AR_";
 
+//PLANET INDEX SEED
+pub const PLANET_INDEX_SEED: &[u8] = b"_PLANETS_";
+
+//MINT CONFIG SEED
+pub const MINT_CONFIG_SEED_PRE: &[u8] = b"_MINT_";
+pub const MINT_CONFIG_SEED_POST: &[u8] = b"_CONFIG_";
+
+//RELAYER REGISTRY SEED
+pub const RELAYER_REGISTRY_SEED: &[u8] = b"_RELAYERS_";
+
+//GALAXY STATS SEED
+pub const GALAXY_STATS_SEED: &[u8] = b"_STATS_";
+
+//PLANET STATS SEED, followed by the planet id
+pub const PLANET_STATS_SEED: &[u8] = b"_PLANET_STATS_";
+
+//Remaining accounts per batch_hop entry: deposit, from planet, to planet, to planet stats
+pub const BATCH_HOP_ACCOUNTS: usize = 4;
+
+//Layout version of Universe, Planet, Deposit and Star. v3.0.0 accounts have none (0).
+pub const ACCOUNT_VERSION: u8 = 1;
+
+//Deposit.mint of native SOL deposits
+pub const NATIVE_SOL: Pubkey = Pubkey::new_from_array([0; 32]);
+
+//Token-2022 mint extensions that break the deposit model: a permanent delegate can move
+//funds out of planets and stars, non-transferable tokens cannot hop, transfer hooks need
+//extra accounts on every transfer, frozen default accounts cannot receive a hop, a closable
+//mint can vanish under open deposits and confidential balances are not seen by the program.
+pub const UNSUPPORTED_MINT_EXTENSIONS: [ExtensionType; 7] = [
+    ExtensionType::PermanentDelegate,
+    ExtensionType::NonTransferable,
+    ExtensionType::TransferHook,
+    ExtensionType::DefaultAccountState,
+    ExtensionType::MintCloseAuthority,
+    ExtensionType::ConfidentialTransferMint,
+    ExtensionType::ConfidentialTransferFeeConfig,
+];
+
 pub  const DISCRIMINATOR_LENGTH: usize = 8;
 pub const PUBLIC_KEY_LENGTH: usize = 32;
 
@@ -26,7 +66,41 @@ pub const STRING_LENGTH_PREFIX: usize = 4; // Stores the size of the string.
 pub const LAMPORT_LENGTH: usize = 8; //u64 = 8 bytes
 
 pub const U8_LENGTH: usize = 1; //BUMP = u8 1 = byte
+pub const U16_LENGTH: usize = 2; //Planet id = u16 2 = bytes
 pub const U64_LENGTH: usize = 8; //8 = bytes
 
-//Planet count length
-pub const PLANETS_VEC_LENGTH: usize = 104; // 4 + ((4 + 6) * 10)
\ No newline at end of file
+//Planet count length (v3.0.0 universe)
+pub const PLANETS_VEC_LENGTH: usize = 104; // 4 + ((4 + 6) * 10)
+
+//Planet index. Planet ids are below MAX_PLANETS, one bit each in Deposit.visited
+pub const MAX_PLANETS: usize = 64;
+pub const PLANET_INDEX_VEC_LENGTH: usize = 4 + U16_LENGTH * MAX_PLANETS;
+
+//Most planets a deposit remembers leaving, the universe's recent window is at most this
+pub const MAX_RECENT_PLANETS: usize = 8;
+
+//Denomination buckets
+pub const MAX_BUCKETS: usize = 8;
+pub const BUCKETS_VEC_LENGTH: usize = 4 + U64_LENGTH * MAX_BUCKETS;
+
+//Destinations a deposit can split its withdrawal across
+pub const MAX_DESTINATIONS: usize = 4;
+
+//Staggered withdrawals
+pub const MAX_TRANCHES: u8 = 8;
+pub const MIN_TRANCHE_SPACING: u32 = 60; //Seconds
+
+//Galaxy stats day buckets
+pub const STATS_DAYS: usize = 30;
+pub const SECONDS_PER_DAY: i64 = 86400;
+
+//Relayers
+pub const MAX_RELAYERS: usize = 16;
+
+//Token mints a planet can serve
+pub const MAX_PLANET_MINTS: usize = 4;
+pub const PLANET_MINTS_VEC_LENGTH: usize = 4 + PUBLIC_KEY_LENGTH * MAX_PLANET_MINTS;
+
+//KEEPER
+pub const KEEPER_REWARD_BPS: u64 = 2000; //Share of the collected fee paid to the keeper (20%)
+pub const BPS_DENOMINATOR: u64 = 10000;